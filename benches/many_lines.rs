@@ -0,0 +1,45 @@
+//! Parse time for growing go.mod files, to check it stays linear in the number of lines.
+//!
+//! cargo bench --bench many_lines
+
+use std::time::{Duration, Instant};
+
+fn text(lines: usize) -> String {
+    let mut s = String::from("module example.com/m\n\nrequire (\n");
+    for i in 0..lines / 2 {
+        s.push_str(&format!("\texample.com/m{i} v1.0.{i} // c\n"));
+    }
+    s.push_str(")\n");
+    for i in 0..lines / 2 {
+        s.push_str(&format!("exclude example.com/m{i} v0.0.{i}\n"));
+    }
+    s
+}
+
+// best of a few runs, to leave out noise from the rest of the machine
+fn parse_time(s: &str) -> Duration {
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            gomod_rs::parse_gomod(s).unwrap();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let mut previous: Option<(usize, Duration)> = None;
+    for lines in [50_000, 100_000, 200_000, 400_000] {
+        let elapsed = parse_time(&text(lines));
+        match previous {
+            Some((n, t)) => println!(
+                "{lines:>7} lines: {elapsed:?}, {:.2}x the time for {:.0}x the lines",
+                elapsed.as_secs_f64() / t.as_secs_f64(),
+                lines as f64 / n as f64
+            ),
+            None => println!("{lines:>7} lines: {elapsed:?}"),
+        }
+        previous = Some((lines, elapsed));
+    }
+}
@@ -8,7 +8,7 @@ fn main() {
         .iter()
         .filter_map(|i| match i {
             Context {
-                value: Directive::Require { specs },
+                value: Directive::Require { specs, .. },
                 ..
             } => Some(specs),
             _ => None,
@@ -17,10 +17,10 @@ fn main() {
             require_specs.iter().for_each(|spec| {
                 println!(
                     "Requirement {{name: {}, version: {}}} at line {}, fragment: {}",
-                    spec.value.0,
+                    &spec.value.0 as &str,
                     &spec.value.1 as &str,
                     spec.range.0.line,
-                    &contents[spec.range.0.offset..spec.range.1.offset]
+                    spec.source(&contents).unwrap()
                 );
             });
         });
@@ -0,0 +1,247 @@
+//! Random but valid go.mod trees, for property tests of code producing or consuming go.mod
+//! text. Enabled by the `arbitrary` feature.
+//!
+//! ```
+//! use gomod_rs::{arbitrary::Generator, parse_gomod, GoModOwned};
+//!
+//! let mut generator = Generator::new(42);
+//! for _ in 0..10 {
+//!     let tree = generator.gomod();
+//!     let text = tree.to_string();
+//!     let parsed: GoModOwned = (&parse_gomod(&text).unwrap()).into();
+//!     assert_eq!(parsed.directives.len(), tree.directives.len());
+//! }
+//! ```
+
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{
+    ContextOwned, DirectiveOwned, GoModOwned, ReplaceSpecOwned, ReplacementOwned, RetractSpecOwned,
+};
+
+/// Deterministic generator of go.mod trees, the same seed giving the same trees.
+///
+/// Module paths and versions are valid, other tokens may need quoting, and comments hold
+/// any character but line breaks. `use` directives aren't generated as go.mod doesn't allow
+/// them.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    state: u64,
+}
+
+const ELEMENT: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+const TEXT: &[char] = &[
+    'a', 'b', 'z', '0', '9', ' ', '\t', '/', '"', '`', '\\', '=', '>', '(', ')', '[', ']', ',',
+    ';', '.', '-', 'é', '日',
+];
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    // splitmix64
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    fn many<T>(&mut self, max: usize, mut f: impl FnMut(&mut Self) -> T) -> Vec<T> {
+        let n = self.below(max + 1);
+        (0..n).map(|_| f(self)).collect()
+    }
+
+    /// A path like `example.com/a1/b`, maybe with a `/vN` suffix
+    pub fn module_path(&mut self) -> String {
+        let mut path = String::from(["example.com", "golang.org/x", "a-b.io"][self.below(3)]);
+        for _ in 0..=self.below(3) {
+            path.push('/');
+            for _ in 0..=self.below(6) {
+                path.push(ELEMENT[self.below(ELEMENT.len())] as char);
+            }
+        }
+        if self.chance(20) {
+            path = format!("{path}/v{}", 2 + self.below(10));
+        }
+        path
+    }
+
+    /// A semantic version, maybe with a pre-release or `+incompatible`
+    pub fn version(&mut self) -> String {
+        let mut version = format!("v{}.{}.{}", self.below(3), self.below(30), self.below(30));
+        match self.below(10) {
+            0 => version.push_str("-rc.1"),
+            1 => version.push_str("-0.20230101000000-abcdef123456"),
+            2 => version.push_str("+incompatible"),
+            _ => {}
+        }
+        version
+    }
+
+    /// Text without line breaks
+    pub fn text(&mut self) -> String {
+        self.many(12, |g| TEXT[g.below(TEXT.len())])
+            .into_iter()
+            .collect()
+    }
+
+    /// A token of the go.mod grammar, usually a version but sometimes text that needs quoting.
+    /// A tab can't be escaped, only written in a raw string, so tokens holding one have no `` ` ``.
+    fn token(&mut self) -> String {
+        match self.chance(15) {
+            true => {
+                let mut text = self.text();
+                if text.contains('\t') {
+                    text.retain(|c| c != '`');
+                }
+                if text.is_empty() {
+                    "x".into()
+                } else {
+                    text
+                }
+            }
+            false => self.version(),
+        }
+    }
+
+    /// A godebug key or value needing quotes, ending with a space so it's never empty. Godebug
+    /// tokens have no escape sequences, so one holding `"` or `\` goes between backticks and
+    /// can't hold one.
+    fn godebug_token(&mut self) -> String {
+        let mut text = self.text();
+        if text.contains(['"', '\\']) {
+            text.retain(|c| c != '`');
+        }
+        text.push(' ');
+        text
+    }
+
+    fn comments(&mut self) -> Vec<String> {
+        match self.chance(30) {
+            true => self.many(3, Self::text),
+            false => Vec::new(),
+        }
+    }
+
+    // Display writes the last comment of a spec on its line, and those of a directive above it
+    fn node<T>(&mut self, spec: bool, value: impl FnOnce(&mut Self) -> T) -> ContextOwned<T> {
+        let comments = self.comments();
+        ContextOwned {
+            range: None,
+            suffix_comment: spec && !comments.is_empty(),
+            comments,
+            value: value(self),
+        }
+    }
+
+    fn spec<T>(&mut self, value: impl FnOnce(&mut Self) -> T) -> ContextOwned<T> {
+        self.node(true, value)
+    }
+
+    fn directive(&mut self) -> DirectiveOwned {
+        match self.below(8) {
+            0 => DirectiveOwned::Go {
+                version: format!("1.{}", self.below(25)),
+            },
+            1 => DirectiveOwned::Toolchain {
+                name: format!("go1.{}.{}", self.below(25), self.below(10)),
+            },
+            2 => DirectiveOwned::Godebug {
+                specs: self.many(3, |g| {
+                    g.spec(|g| match g.chance(15) {
+                        true => (g.godebug_token(), g.godebug_token()),
+                        false => {
+                            let key = ["panicnil", "http2client", "x509sha1"][g.below(3)].into();
+                            (key, format!("{}", g.below(2)))
+                        }
+                    })
+                }),
+            },
+            3 => DirectiveOwned::Require {
+                specs: self.many(4, |g| g.spec(|g| (g.module_path(), g.token()))),
+            },
+            4 => DirectiveOwned::Exclude {
+                specs: self.many(3, |g| g.spec(|g| (g.module_path(), g.token()))),
+            },
+            5 => DirectiveOwned::Replace {
+                specs: self.many(3, |g| {
+                    g.spec(|g| ReplaceSpecOwned {
+                        module_path: g.module_path(),
+                        version: g.chance(50).then(|| g.token()),
+                        replacement: match g.chance(50) {
+                            true => ReplacementOwned::FilePath(format!("../{}", g.below(100))),
+                            false => ReplacementOwned::Module((g.module_path(), g.token())),
+                        },
+                    })
+                }),
+            },
+            _ => DirectiveOwned::Retract {
+                specs: self.many(3, |g| {
+                    g.spec(|g| match g.chance(50) {
+                        true => RetractSpecOwned::Version(g.token()),
+                        false => RetractSpecOwned::Range((g.token(), g.token())),
+                    })
+                }),
+            },
+        }
+    }
+
+    /// A tree starting with a `module` directive, its nodes built without a range
+    pub fn gomod(&mut self) -> GoModOwned {
+        let mut directives = Vec::new();
+        let module_path = self.module_path();
+        directives.push(self.node(false, |_| DirectiveOwned::Module { module_path }));
+        for _ in 0..self.below(8) {
+            directives.push(self.node(false, Self::directive));
+        }
+        GoModOwned { directives }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::{parse_gomod, GoModOwned};
+
+    use super::Generator;
+
+    fn without_ranges(mut gomod: GoModOwned) -> GoModOwned {
+        use crate::DirectiveOwned::*;
+        for directive in &mut gomod.directives {
+            directive.range = None;
+            match &mut directive.value {
+                Require { specs } | Exclude { specs } | Godebug { specs } => {
+                    specs.iter_mut().for_each(|i| i.range = None)
+                }
+                Replace { specs } => specs.iter_mut().for_each(|i| i.range = None),
+                Retract { specs } => specs.iter_mut().for_each(|i| i.range = None),
+                Use { specs } | Ignore { specs } => specs.iter_mut().for_each(|i| i.range = None),
+                Module { .. } | Go { .. } | Toolchain { .. } => {}
+            }
+        }
+        gomod
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut generator = Generator::new(0);
+        for _ in 0..1000 {
+            let tree = generator.gomod();
+            let text = tree.to_string();
+            let parsed = parse_gomod(&text).unwrap_or_else(|e| panic!("{e:?}\n{text}"));
+            assert_eq!(without_ranges((&parsed).into()), tree, "{text}");
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::{fs, process::ExitCode};
+
+use gomod_rs::{format_gomod, parse_with_options, ParseError, ParseOptions};
+
+const USAGE: &str = "usage: gomod <fmt|json|lint> <go.mod>";
+
+fn parse_error(path: &str, text: &str, e: &ParseError) -> ExitCode {
+    match e.located() {
+        Some(e) => eprint!("{path}: {}", e.render(text)),
+        None => eprintln!("{path}: {e}"),
+    }
+    ExitCode::FAILURE
+}
+
+fn main() -> ExitCode {
+    let args: Vec<_> = std::env::args().skip(1).collect();
+    let [command, path] = &args[..] else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("{path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match command.as_str() {
+        "fmt" => match format_gomod(&text) {
+            Ok(formatted) => print!("{formatted}"),
+            Err(e) => return parse_error(path, &text, &e),
+        },
+        "json" => match parse_with_options(&text, ParseOptions::default()) {
+            Ok(gomod) => print!("{}", gomod.to_go_edit_json()),
+            Err(e) => return parse_error(path, &text, &e),
+        },
+        "lint" => {
+            let gomod = match parse_with_options(&text, ParseOptions::default()) {
+                Ok(gomod) => gomod,
+                Err(e) => return parse_error(path, &text, &e),
+            };
+            let mut failed = false;
+            for diagnostic in gomod.validate() {
+                eprintln!("{path}: {diagnostic}");
+                failed = true;
+            }
+            for issue in gomod.ordering_issues() {
+                eprintln!("{path}: line {}: {}", issue.range.0.line, issue.kind);
+                failed = true;
+            }
+            if failed {
+                return ExitCode::FAILURE;
+            }
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    }
+    ExitCode::SUCCESS
+}
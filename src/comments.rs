@@ -0,0 +1,139 @@
+use alloc::vec::Vec;
+
+use crate::{Context, Directive, GoMod};
+
+/// What a comment annotates, see [`GoMod::comments`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentOwner<'g, 'a> {
+    /// Before the first directive and separated from it by a blank line
+    Header,
+    Directive(&'g Context<'a, Directive<'a>>),
+    /// The spec at `index` in the specs of a block directive
+    Spec {
+        directive: &'g Context<'a, Directive<'a>>,
+        index: usize,
+    },
+    /// After the last directive of the file
+    Trailing,
+}
+
+/// A comment and its owner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentRef<'g, 'a> {
+    /// Text after `//`, a slice of the parsed text
+    pub text: &'a str,
+    pub owner: CommentOwner<'g, 'a>,
+}
+
+fn spec_comments<'g, 'a>(directive: &'g Directive<'a>) -> Vec<&'g [&'a str]> {
+    fn comments<'g, 'a, T>(specs: &'g [Context<'a, T>]) -> Vec<&'g [&'a str]> {
+        specs.iter().map(|i| &i.comments[..]).collect()
+    }
+    match directive {
+        Directive::Module { .. } | Directive::Go { .. } | Directive::Toolchain { .. } => Vec::new(),
+        Directive::Require { specs, .. } | Directive::Exclude { specs, .. } => comments(specs),
+        Directive::Godebug { specs, .. } => comments(specs),
+        Directive::Replace { specs, .. } => comments(specs),
+        Directive::Retract { specs, .. } => comments(specs),
+        Directive::Use { specs, .. } | Directive::Ignore { specs, .. } => comments(specs),
+    }
+}
+
+impl<'a> GoMod<'a> {
+    /// Every comment of the file in document order, with what it annotates.
+    ///
+    /// Comments are attached the way the parser collects them:
+    /// - comment lines at the top of the file followed by a blank line are the `Header`
+    /// - other comment lines before a directive, including ones separated from it by blank
+    ///   lines, belong to that directive, as does a comment on its line
+    /// - in a block, comment lines before a spec and the comment on its line belong to the
+    ///   spec, the comments on the `(` and `)` lines and after the last spec to the directive
+    /// - comments after the last directive are `Trailing`
+    ///
+    /// The order relies on the comments being slices of the same text, as they are once parsed.
+    pub fn comments(&self) -> impl Iterator<Item = CommentRef<'_, 'a>> {
+        let mut ret: Vec<_> = self
+            .header_comments
+            .iter()
+            .map(|text| CommentRef {
+                text,
+                owner: CommentOwner::Header,
+            })
+            .collect();
+        for directive in self.iter() {
+            let start = ret.len();
+            ret.extend(directive.comments.iter().map(|text| CommentRef {
+                text,
+                owner: CommentOwner::Directive(directive),
+            }));
+            for (index, comments) in spec_comments(&directive.value).into_iter().enumerate() {
+                ret.extend(comments.iter().map(|text| CommentRef {
+                    text,
+                    owner: CommentOwner::Spec { directive, index },
+                }));
+            }
+            ret[start..].sort_by_key(|i| i.text.as_ptr() as usize);
+        }
+        ret.extend(self.trailing_comments.iter().map(|text| CommentRef {
+            text,
+            owner: CommentOwner::Trailing,
+        }));
+        ret.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_gomod;
+
+    use super::CommentOwner;
+
+    #[test]
+    fn test_comments() {
+        let gomod = parse_gomod(
+            r#"// Copyright
+
+// about the module
+module example.com/m // main
+
+// floating
+
+require ( // open
+    // about a
+    example.com/a v1.0.0 // a
+    example.com/b v1.0.0
+    // last
+) // close
+// trailing
+"#,
+        )
+        .unwrap();
+        let owners = gomod
+            .comments()
+            .map(|i| {
+                let owner = match i.owner {
+                    CommentOwner::Directive(d) => alloc::format!("directive {}", d.range.0.line),
+                    CommentOwner::Spec { index, .. } => alloc::format!("spec {index}"),
+                    CommentOwner::Trailing => "trailing".into(),
+                    CommentOwner::Header => "header".into(),
+                };
+                (i.text, owner)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            owners,
+            vec![
+                (" Copyright", "header".into()),
+                (" about the module", "directive 4".into()),
+                (" main", "directive 4".into()),
+                (" floating", "directive 8".into()),
+                (" open", "directive 8".into()),
+                (" about a", "spec 0".into()),
+                (" a", "spec 0".into()),
+                (" last", "directive 8".into()),
+                (" close", "directive 8".into()),
+                (" trailing", "trailing".into()),
+            ]
+        );
+    }
+}
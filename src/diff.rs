@@ -0,0 +1,220 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use crate::{Context, Directive, GoMod, Identifier, ReplaceSpec, Replacement};
+
+type RequireSpec<'a> = Context<'a, (Identifier<'a>, Identifier<'a>)>;
+
+/// A value present in both files but different
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change<T> {
+    pub old: T,
+    pub new: T,
+}
+
+/// Differences between two go.mod files, see [`diff`].
+///
+/// Entries borrow the specs of both trees, so their `range` can be used to point at the
+/// lines in the old and new text. Specs from the new file are listed in its order, removed
+/// ones in the order of the old file.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GoModDiff<'g, 'a> {
+    pub added_requires: Vec<&'g RequireSpec<'a>>,
+    pub removed_requires: Vec<&'g RequireSpec<'a>>,
+    pub changed_requires: Vec<Change<&'g RequireSpec<'a>>>,
+    pub added_replaces: Vec<&'g Context<'a, ReplaceSpec<'a>>>,
+    pub removed_replaces: Vec<&'g Context<'a, ReplaceSpec<'a>>>,
+    pub changed_replaces: Vec<Change<&'g Context<'a, ReplaceSpec<'a>>>>,
+    /// `Some` if the `go` directive was added, removed or its version changed
+    pub go: Option<Change<Option<&'g Context<'a, Directive<'a>>>>>,
+    /// `Some` if the `toolchain` directive was added, removed or its name changed
+    pub toolchain: Option<Change<Option<&'g Context<'a, Directive<'a>>>>>,
+}
+
+impl GoModDiff<'_, '_> {
+    pub fn is_empty(&self) -> bool {
+        self.added_requires.is_empty()
+            && self.removed_requires.is_empty()
+            && self.changed_requires.is_empty()
+            && self.added_replaces.is_empty()
+            && self.removed_replaces.is_empty()
+            && self.changed_replaces.is_empty()
+            && self.go.is_none()
+            && self.toolchain.is_none()
+    }
+}
+
+fn replace_key<'g>(spec: &'g ReplaceSpec) -> (&'g str, Option<&'g str>) {
+    (&spec.module_path, spec.version.as_deref())
+}
+
+fn same_replacement(a: &Replacement, b: &Replacement) -> bool {
+    match (a, b) {
+        (Replacement::FilePath(a), Replacement::FilePath(b)) => **a == **b,
+        (Replacement::Module((a, av)), Replacement::Module((b, bv))) => **a == **b && **av == **bv,
+        _ => false,
+    }
+}
+
+fn diff_singleton<'g, 'a>(
+    old: &'g GoMod<'a>,
+    new: &'g GoMod<'a>,
+    value: impl Fn(&'g Directive<'a>) -> Option<&'g str>,
+) -> Option<Change<Option<&'g Context<'a, Directive<'a>>>>> {
+    let find = |gomod: &'g GoMod<'a>| gomod.iter().find(|i| value(&i.value).is_some());
+    let (old, new) = (find(old), find(new));
+    if old.and_then(|i| value(&i.value)) == new.and_then(|i| value(&i.value)) {
+        None
+    } else {
+        Some(Change { old, new })
+    }
+}
+
+/// Compare the requires, replaces, `go` and `toolchain` directives of two go.mod files.
+///
+/// Requires are matched by module path, so `example.com/m` and `example.com/m/v2` are distinct
+/// modules. Replaces are matched by module path and the optional version on their left side.
+pub fn diff<'g, 'a>(old: &'g GoMod<'a>, new: &'g GoMod<'a>) -> GoModDiff<'g, 'a> {
+    let mut old_requires = BTreeMap::new();
+    for spec in old.requires() {
+        old_requires.entry(&*spec.value.0).or_insert(spec);
+    }
+    let mut new_requires = BTreeMap::new();
+    let (mut added_requires, mut changed_requires) = (Vec::new(), Vec::new());
+    for spec in new.requires() {
+        new_requires.entry(&*spec.value.0).or_insert(spec);
+        match old_requires.get(&*spec.value.0) {
+            None => added_requires.push(spec),
+            Some(&old) if *old.value.1 != *spec.value.1 => {
+                changed_requires.push(Change { old, new: spec })
+            }
+            _ => {}
+        }
+    }
+    let removed_requires = old
+        .requires()
+        .filter(|i| !new_requires.contains_key(&*i.value.0))
+        .collect();
+
+    let mut old_replaces = BTreeMap::new();
+    for spec in old.replaces() {
+        old_replaces.entry(replace_key(&spec.value)).or_insert(spec);
+    }
+    let mut new_replaces = BTreeMap::new();
+    let (mut added_replaces, mut changed_replaces) = (Vec::new(), Vec::new());
+    for spec in new.replaces() {
+        new_replaces.entry(replace_key(&spec.value)).or_insert(spec);
+        match old_replaces.get(&replace_key(&spec.value)) {
+            None => added_replaces.push(spec),
+            Some(&old) if !same_replacement(&old.value.replacement, &spec.value.replacement) => {
+                changed_replaces.push(Change { old, new: spec })
+            }
+            _ => {}
+        }
+    }
+    let removed_replaces = old
+        .replaces()
+        .filter(|i| !new_replaces.contains_key(&replace_key(&i.value)))
+        .collect();
+
+    GoModDiff {
+        added_requires,
+        removed_requires,
+        changed_requires,
+        added_replaces,
+        removed_replaces,
+        changed_replaces,
+        go: diff_singleton(old, new, |i| match i {
+            Directive::Go { version } => Some(version),
+            _ => None,
+        }),
+        toolchain: diff_singleton(old, new, |i| match i {
+            Directive::Toolchain { name } => Some(name),
+            _ => None,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_gomod, Directive};
+
+    use super::diff;
+
+    #[test]
+    fn test_diff() {
+        let old = parse_gomod(
+            r#"module example.com/m
+
+go 1.21
+
+require (
+    example.com/a v1.0.0
+    example.com/b v1.0.0
+    example.com/c v1.0.0
+)
+
+replace example.com/a => ../a
+replace example.com/b v1.0.0 => example.com/fork/b v1.0.1
+"#,
+        )
+        .unwrap();
+        let new = parse_gomod(
+            r#"module example.com/m
+
+go 1.22
+
+toolchain go1.22.1
+
+require (
+    example.com/a v1.1.0
+    example.com/c v1.0.0
+    example.com/c/v2 v2.0.0
+)
+
+replace example.com/a => ../a2
+replace example.com/b => example.com/fork/b v1.0.1
+"#,
+        )
+        .unwrap();
+        let d = diff(&old, &new);
+        assert_eq!(
+            d.added_requires
+                .iter()
+                .map(|i| &*i.value.0)
+                .collect::<Vec<_>>(),
+            vec!["example.com/c/v2"]
+        );
+        assert_eq!(d.removed_requires.len(), 1);
+        assert_eq!(&*d.removed_requires[0].value.0, "example.com/b");
+        assert_eq!(d.removed_requires[0].range.0.line, 7);
+        assert_eq!(d.changed_requires.len(), 1);
+        assert_eq!(&*d.changed_requires[0].old.value.1, "v1.0.0");
+        assert_eq!(&*d.changed_requires[0].new.value.1, "v1.1.0");
+        assert_eq!(d.changed_requires[0].old.range.0.line, 6);
+        assert_eq!(d.changed_requires[0].new.range.0.line, 8);
+
+        assert_eq!(d.changed_replaces.len(), 1);
+        assert_eq!(
+            &*d.changed_replaces[0].new.value.module_path,
+            "example.com/a"
+        );
+        assert_eq!(d.added_replaces.len(), 1);
+        assert_eq!(d.added_replaces[0].value.version, None);
+        assert_eq!(d.removed_replaces.len(), 1);
+        assert_eq!(d.removed_replaces[0].range.0.line, 12);
+
+        let go = d.go.as_ref().unwrap();
+        assert!(
+            matches!(&go.old.unwrap().value, Directive::Go { version } if &**version == "1.21")
+        );
+        assert!(
+            matches!(&go.new.unwrap().value, Directive::Go { version } if &**version == "1.22")
+        );
+        let toolchain = d.toolchain.as_ref().unwrap();
+        assert!(toolchain.old.is_none());
+        assert_eq!(toolchain.new.unwrap().range.0.line, 5);
+
+        assert!(!d.is_empty());
+        assert!(diff(&old, &old).is_empty());
+    }
+}
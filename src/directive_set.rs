@@ -0,0 +1,146 @@
+use core::ops::BitOr;
+
+use crate::{Directive, Span};
+
+/// A set of directive kinds, see `ParseOptions::allowed_directives`.
+///
+/// Sets are combined with `|`, e.g. `DirectiveSet::GO | DirectiveSet::REPLACE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DirectiveSet(u16);
+
+pub(crate) const KEYWORDS: [(&str, DirectiveSet); 10] = [
+    ("module", DirectiveSet::MODULE),
+    ("go", DirectiveSet::GO),
+    ("require", DirectiveSet::REQUIRE),
+    ("toolchain", DirectiveSet::TOOLCHAIN),
+    ("godebug", DirectiveSet::GODEBUG),
+    ("replace", DirectiveSet::REPLACE),
+    ("exclude", DirectiveSet::EXCLUDE),
+    ("retract", DirectiveSet::RETRACT),
+    ("use", DirectiveSet::USE),
+    ("ignore", DirectiveSet::IGNORE),
+];
+
+impl DirectiveSet {
+    // bits follow the order of `DirectiveKind`
+    pub const MODULE: Self = Self(1);
+    pub const GO: Self = Self(1 << 1);
+    pub const REQUIRE: Self = Self(1 << 2);
+    pub const TOOLCHAIN: Self = Self(1 << 3);
+    pub const GODEBUG: Self = Self(1 << 4);
+    pub const REPLACE: Self = Self(1 << 5);
+    pub const EXCLUDE: Self = Self(1 << 6);
+    pub const RETRACT: Self = Self(1 << 7);
+    /// go.work only
+    pub const USE: Self = Self(1 << 8);
+    pub const IGNORE: Self = Self(1 << 9);
+    /// Every directive of a go.mod file, the default
+    pub const GO_MOD: Self = Self(0xff | Self::IGNORE.0);
+    /// Directives of a go.work file
+    pub const GO_WORK: Self =
+        Self(Self::GO.0 | Self::TOOLCHAIN.0 | Self::GODEBUG.0 | Self::REPLACE.0 | Self::USE.0);
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Whether all of `other` is in `self`
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub(crate) fn of(directive: &Directive) -> Self {
+        Self(1 << directive.kind() as u16)
+    }
+
+    /// The keyword `input` starts with, if it names a directive missing from `input.extra`
+    pub(crate) fn disallowed_keyword(input: &Span) -> Option<&'static str> {
+        let allowed = input.extra.allowed_directives;
+        KEYWORDS.iter().find_map(|&(keyword, set)| {
+            let rest = input.fragment().strip_prefix(keyword)?;
+            let boundary = rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n', '(']);
+            (boundary && !allowed.contains(set)).then_some(keyword)
+        })
+    }
+}
+
+impl Default for DirectiveSet {
+    fn default() -> Self {
+        Self::GO_MOD
+    }
+}
+
+impl BitOr for DirectiveSet {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_with_options, ParseError, ParseOptions};
+
+    use super::DirectiveSet;
+
+    #[test]
+    fn test_allowed_directives() {
+        assert!(DirectiveSet::GO_MOD.contains(
+            DirectiveSet::GO
+                | DirectiveSet::TOOLCHAIN
+                | DirectiveSet::GODEBUG
+                | DirectiveSet::REPLACE
+        ));
+        assert!(!DirectiveSet::GO_MOD.contains(DirectiveSet::USE));
+        assert!(!DirectiveSet::GO_WORK.contains(DirectiveSet::GO | DirectiveSet::REQUIRE));
+        let options = ParseOptions {
+            allowed_directives: DirectiveSet::GO_WORK,
+            ..Default::default()
+        };
+        let text = "go 1.21\n\nreplace example.com/a => ../a\n\n  require example.com/a v1.0.0\n";
+        assert_eq!(
+            parse_with_options(text, options),
+            Err(ParseError::DirectiveNotAllowed {
+                line: 5,
+                offset: 42,
+                len: 7,
+                directive: "require"
+            })
+        );
+        assert_eq!(parse_with_options(&text[..40], options).unwrap().len(), 2);
+        let options = ParseOptions {
+            allowed_directives: DirectiveSet::MODULE,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_with_options("module example.com/m\n  go 1.21\n", options),
+            Err(ParseError::DirectiveNotAllowed {
+                line: 2,
+                offset: 23,
+                len: 2,
+                directive: "go"
+            })
+        );
+        assert!(parse_with_options(text, Default::default()).is_ok());
+        let text = "go 1.21\n\nuse ./a\n";
+        assert_eq!(
+            parse_with_options(text, Default::default()),
+            Err(ParseError::DirectiveNotAllowed {
+                line: 3,
+                offset: 9,
+                len: 3,
+                directive: "use"
+            })
+        );
+        let options = ParseOptions {
+            allowed_directives: DirectiveSet::GO_WORK,
+            ..Default::default()
+        };
+        assert_eq!(parse_with_options(text, options).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,360 @@
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+
+use nom::Slice;
+
+use crate::{
+    module::{validate_module_path, PathError},
+    owned::token,
+    parse_with_options, parser,
+    query::is_indirect,
+    Context, Directive, ParseError, ParseOptions, Span,
+};
+
+/// Error returned by the text editing functions, e.g. [`set_require_version`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// The text isn't a valid go.mod file
+    Parse(ParseError),
+    /// No require spec for the module path
+    NotFound { module_path: String },
+    /// The module path is required more than once, so the one to edit is ambiguous
+    Ambiguous { module_path: String, count: usize },
+    /// The module path is already required, see [`set_require_version`] to change its version
+    AlreadyRequired { module_path: String },
+    /// The module path to add isn't valid, see [`validate_module_path`]
+    InvalidModulePath {
+        module_path: String,
+        error: PathError,
+    },
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "{e}"),
+            Self::NotFound { module_path } => write!(f, "{module_path} is not required"),
+            Self::Ambiguous { module_path, count } => {
+                write!(f, "{module_path} is required {count} times")
+            }
+            Self::AlreadyRequired { module_path } => write!(f, "{module_path} is already required"),
+            Self::InvalidModulePath { module_path, error } => {
+                write!(f, "invalid module path {module_path:?}: {error}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EditError {}
+
+impl From<ParseError> for EditError {
+    fn from(e: ParseError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+/// Replace the version of the require spec for `module_path`, leaving the rest of `text`
+/// byte-identical.
+///
+/// `new_version` is inserted as is, it's up to the caller to pass a valid token.
+pub fn set_require_version(
+    text: &str,
+    module_path: &str,
+    new_version: &str,
+) -> Result<String, EditError> {
+    let gomod = parse_with_options(text, ParseOptions::default())?;
+    let mut specs = gomod.requires().filter(|i| &*i.value.0 == module_path);
+    let spec = specs.next().ok_or_else(|| EditError::NotFound {
+        module_path: module_path.into(),
+    })?;
+    let count = specs.count() + 1;
+    if count > 1 {
+        return Err(EditError::Ambiguous {
+            module_path: module_path.into(),
+            count,
+        });
+    }
+    let input = Span::new_extra(text, ParseOptions::default()).slice(spec.range.0.offset..);
+    let (_, (start, end)) =
+        parser::spec_version_range(input).map_err(|e| ParseError::syntax(e, input))?;
+    let mut ret = String::with_capacity(text.len() - (end - start) + new_version.len());
+    ret.push_str(&text[..start]);
+    ret.push_str(new_version);
+    ret.push_str(&text[end..]);
+    Ok(ret)
+}
+
+// Offset of the start of the line holding the first comment of `context`, or its first token
+// when it has no comment above it
+fn line_start<T>(text: &str, context: &Context<T>) -> usize {
+    let start = context
+        .comments
+        .iter()
+        .filter_map(|i| (i.as_ptr() as usize).checked_sub(text.as_ptr() as usize))
+        .fold(context.range.0.offset, usize::min);
+    text[..start].rfind('\n').map_or(0, |i| i + 1)
+}
+
+// The line of `text` starting at `offset`, without its line ending
+fn rest_of_line(text: &str, offset: usize) -> &str {
+    let line = &text[offset..];
+    line[..line.find('\n').unwrap_or(line.len())].trim_end_matches('\r')
+}
+
+/// Add a require spec for `module_path` the way `go get` does, leaving the rest of `text`
+/// byte-identical:
+///
+/// - into a `require` block, the first one of `// indirect` specs only when `indirect` and of
+///   direct ones otherwise if there are both, before the first spec of a greater module path
+/// - else single-line `require` directives are merged into a block at the first of them, with
+///   the new spec
+/// - else in a new block after the `go` directive, or the `module` one without it
+///
+/// The version is quoted when needed. Fails with [`EditError::InvalidModulePath`] if
+/// `module_path` isn't a valid module path, and [`EditError::AlreadyRequired`] if it's already
+/// required.
+pub fn append_require(
+    text: &str,
+    module_path: &str,
+    version: &str,
+    indirect: bool,
+) -> Result<String, EditError> {
+    validate_module_path(module_path).map_err(|error| EditError::InvalidModulePath {
+        module_path: module_path.into(),
+        error,
+    })?;
+    let gomod = parse_with_options(text, ParseOptions::default())?;
+    if gomod.requires().any(|i| &*i.value.0 == module_path) {
+        return Err(EditError::AlreadyRequired {
+            module_path: module_path.into(),
+        });
+    }
+    let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut new = format!("{module_path} {}", token(version));
+    if indirect {
+        new.push_str(" // indirect");
+    }
+    let blocks = gomod
+        .iter()
+        .filter_map(|i| match &i.value {
+            Directive::Require {
+                specs,
+                block: Some(block),
+                ..
+            } => Some((specs, block)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let all_indirect = |specs: &Vec<Context<_>>| {
+        !specs.is_empty()
+            && specs
+                .iter()
+                .all(|i: &Context<(_, _)>| is_indirect(&i.comments, i.suffix_comment))
+    };
+    let block = blocks
+        .iter()
+        .find(|(specs, _)| all_indirect(specs) == indirect)
+        .or(blocks.first());
+    let mut edits = Vec::new();
+    if let Some((specs, block)) = block {
+        let indent = specs.first().and_then(|i| i.indent(text)).unwrap_or("\t");
+        let line = format!("{indent}{new}{newline}");
+        match specs.iter().find(|i| *i.value.0 > *module_path) {
+            Some(next) => edits.push((line_start(text, next), 0, line)),
+            None => {
+                let at = block.close_paren.offset;
+                let start = text[..at].rfind('\n').map_or(0, |i| i + 1);
+                if text[start..at].trim().is_empty() {
+                    edits.push((start, 0, line));
+                } else {
+                    edits.push((at, 0, format!("{newline}{line}")));
+                }
+            }
+        }
+    } else {
+        let singles = gomod
+            .iter()
+            .filter_map(|i| match &i.value {
+                Directive::Require {
+                    specs, block: None, ..
+                } => Some((i, &specs[0])),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if let Some(&(first, _)) = singles.first() {
+            let mut lines = Vec::new();
+            let mut added = false;
+            for (i, &(directive, spec)) in singles.iter().enumerate() {
+                if !added && *spec.value.0 > *module_path {
+                    lines.push(format!("\t{new}{newline}"));
+                    added = true;
+                }
+                if i > 0 {
+                    for comment in directive.leading_comments() {
+                        lines.push(format!("\t//{comment}{newline}"));
+                    }
+                }
+                let line = rest_of_line(text, spec.range.0.offset);
+                lines.push(format!("\t{line}{newline}"));
+            }
+            if !added {
+                lines.push(format!("\t{new}{newline}"));
+            }
+            let start = first.range.0.offset;
+            let block = format!("require ({newline}{}){newline}", lines.concat());
+            edits.push((start, first.range.1.offset - start, block));
+            for &(directive, _) in &singles[1..] {
+                let start = line_start(text, directive);
+                let mut end = directive.range.1.offset;
+                let after = &text[end..];
+                let blank = after.strip_prefix(newline).is_some()
+                    && (text[..start].ends_with("\n\n") || text[..start].ends_with("\n\r\n"));
+                if blank {
+                    end += newline.len();
+                }
+                edits.push((start, end - start, String::new()));
+            }
+        } else {
+            let at = gomod
+                .iter()
+                .find(|i| matches!(i.value, Directive::Go { .. }))
+                .or_else(|| {
+                    gomod
+                        .iter()
+                        .find(|i| matches!(i.value, Directive::Module { .. }))
+                })
+                .map_or(gomod.end.offset, |i| i.range.1.offset);
+            let mut block = String::new();
+            if at > 0 {
+                if !text[..at].ends_with('\n') {
+                    block.push_str(newline);
+                }
+                block.push_str(newline);
+            }
+            block.push_str(&format!("require ({newline}\t{new}{newline}){newline}"));
+            edits.push((at, 0, block));
+        }
+    }
+    let mut ret = String::from(text);
+    for (start, len, replacement) in edits.into_iter().rev() {
+        ret.replace_range(start..start + len, &replacement);
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::module::PathError;
+
+    use super::{append_require, set_require_version, EditError};
+
+    const GOMOD: &str = r#"module example.com/m
+
+require example.com/a  "v1.0.0"   // pinned
+require (
+	example.com/b v1.0.0 // indirect
+	example.com/c v1.0.0
+	example.com/c v1.1.0
+)
+"#;
+
+    #[test]
+    fn test_set_require_version() {
+        assert_eq!(
+            set_require_version(GOMOD, "example.com/a", "v1.2.0").unwrap(),
+            GOMOD.replace("\"v1.0.0\"", "v1.2.0")
+        );
+        assert_eq!(
+            set_require_version(GOMOD, "example.com/b", "v2.0.0").unwrap(),
+            GOMOD.replace("example.com/b v1.0.0", "example.com/b v2.0.0")
+        );
+        assert_eq!(
+            set_require_version(GOMOD, "example.com/d", "v1.0.0"),
+            Err(EditError::NotFound {
+                module_path: "example.com/d".into()
+            })
+        );
+        assert_eq!(
+            set_require_version(GOMOD, "example.com/c", "v1.2.0"),
+            Err(EditError::Ambiguous {
+                module_path: "example.com/c".into(),
+                count: 2
+            })
+        );
+        assert!(matches!(
+            set_require_version("foo", "example.com/a", "v1.0.0"),
+            Err(EditError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_append_require_block() {
+        let text = "module example.com/m\n\ngo 1.21\n\nrequire (\n    example.com/a v1.0.0\n    // the c\n    example.com/c v1.0.0\n)\n\nrequire (\n    example.com/x v1.0.0 // indirect\n)\n";
+        assert_eq!(
+            append_require(text, "example.com/b", "v1.1.0", false).unwrap(),
+            text.replace("    // the c", "    example.com/b v1.1.0\n    // the c")
+        );
+        assert_eq!(
+            append_require(text, "example.com/d", "v1.1.0", false).unwrap(),
+            text.replace(
+                "example.com/c v1.0.0\n",
+                "example.com/c v1.0.0\n    example.com/d v1.1.0\n"
+            )
+        );
+        assert_eq!(
+            append_require(text, "example.com/y", "v0.1.0", true).unwrap(),
+            text.replace(
+                "// indirect\n",
+                "// indirect\n    example.com/y v0.1.0 // indirect\n"
+            )
+        );
+        assert_eq!(
+            append_require(text, "example.com/a", "v1.1.0", false),
+            Err(EditError::AlreadyRequired {
+                module_path: "example.com/a".into()
+            })
+        );
+        assert_eq!(
+            append_require("module m\n\nrequire ()\n", "example.com/a", "v1.0.0", false).unwrap(),
+            "module m\n\nrequire (\n\texample.com/a v1.0.0\n)\n"
+        );
+    }
+
+    #[test]
+    fn test_append_require_single_lines() {
+        let text = "module example.com/m\n\n// deps\nrequire example.com/a v1.0.0 // pinned\n\n// the c\nrequire example.com/c v1.0.0\n\nreplace example.com/a => ../a\n";
+        assert_eq!(
+            append_require(text, "example.com/b", "v1.0.0", true).unwrap(),
+            "module example.com/m\n\n// deps\nrequire (\n\texample.com/a v1.0.0 // pinned\n\texample.com/b v1.0.0 // indirect\n\t// the c\n\texample.com/c v1.0.0\n)\n\nreplace example.com/a => ../a\n"
+        );
+    }
+
+    #[test]
+    fn test_append_require_new_block() {
+        assert_eq!(
+            append_require(
+                "module example.com/m\r\n\r\ngo 1.21\r\n\r\nreplace example.com/a => ../a\r\n",
+                "example.com/a",
+                "v1.0.0 rc",
+                false
+            )
+            .unwrap(),
+            "module example.com/m\r\n\r\ngo 1.21\r\n\r\nrequire (\r\n\texample.com/a \"v1.0.0 rc\"\r\n)\r\n\r\nreplace example.com/a => ../a\r\n"
+        );
+        let text = "module example.com/m\n\ngo 1.21\n";
+        let ret = append_require(text, "example.com/a", "v1.0.0 rc", false).unwrap();
+        assert!(crate::parse_gomod(&ret).is_ok());
+        assert_eq!(
+            append_require(text, "example.com/a b", "v1.0.0", false),
+            Err(EditError::InvalidModulePath {
+                module_path: "example.com/a b".into(),
+                error: PathError::InvalidChar(' ')
+            })
+        );
+        assert_eq!(
+            append_require("module example.com/m", "example.com/a", "v1.0.0", false).unwrap(),
+            "module example.com/m\n\nrequire (\n\texample.com/a v1.0.0\n)\n"
+        );
+    }
+}
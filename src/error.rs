@@ -0,0 +1,780 @@
+use alloc::{format, string::String};
+use core::fmt;
+
+use nom::{
+    error::{Error, ErrorKind},
+    Err, Slice,
+};
+
+use crate::{
+    parser::{display_width, location, parse_module_path_identifier},
+    DirectiveSet, Span,
+};
+
+/// Error returned by the checked parse entries, e.g. [`crate::parse_gomod_limited`].
+///
+/// `line` is 1-based (unless `ParseOptions::zero_based_positions` is set), `offset` is a
+/// 0-based byte offset into the parsed text and `len` the length in bytes of the offending
+/// token there, 0 at the end of the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The text doesn't match the go.mod grammar at (line, offset)
+    Syntax {
+        line: u32,
+        offset: usize,
+        len: usize,
+    },
+    /// What starts at (line, offset), after the last directive and trailing comments, is
+    /// neither, e.g. a misspelled directive
+    TrailingContent {
+        line: u32,
+        offset: usize,
+        len: usize,
+    },
+    /// A `/* */` comment starts at (line, offset). go.mod only supports `//` line comments,
+    /// so these are rejected rather than skipped.
+    BlockCommentUnsupported {
+        line: u32,
+        offset: usize,
+        len: usize,
+    },
+    /// A directive missing from `ParseOptions::allowed_directives` starts at (line, offset)
+    DirectiveNotAllowed {
+        line: u32,
+        offset: usize,
+        len: usize,
+        directive: &'static str,
+    },
+    /// A string opened by the `"` or `` ` `` at (line, offset) isn't closed on its line
+    UnterminatedString {
+        line: u32,
+        offset: usize,
+        len: usize,
+    },
+    /// `token` follows the value of a directive or spec on its line at (line, offset), where
+    /// only a comment may, e.g. a second directive on the same line
+    UnexpectedTrailingToken {
+        line: u32,
+        offset: usize,
+        len: usize,
+        token: String,
+    },
+    /// The require spec at (line, offset) has a module path but no version
+    MissingVersion {
+        line: u32,
+        offset: usize,
+        len: usize,
+        module_path: String,
+    },
+    /// A module path runs into `ch` at (line, offset), e.g. the `:` of `localhost:8080/m`. Like
+    /// Go, module paths only allow ASCII letters, digits and `-._~/`, so this is reported rather
+    /// than cutting the path short.
+    InvalidModulePathChar {
+        line: u32,
+        offset: usize,
+        len: usize,
+        ch: char,
+    },
+    /// A `\r` not followed by `\n` at (line, offset), i.e. a classic Mac line ending. Only `\n`
+    /// and `\r\n` end lines, so this is reported instead of a syntax error when parsing fails.
+    UnsupportedLineEnding {
+        line: u32,
+        offset: usize,
+        len: usize,
+    },
+    /// The bytes passed to [`crate::parse_gomod_bytes`] aren't valid UTF-8 from (line, offset)
+    InvalidUtf8 {
+        line: u32,
+        offset: usize,
+        len: usize,
+    },
+    /// The text is longer than `Limits::max_input_len`
+    InputTooLong { len: usize, max: usize },
+    /// The directive starting at (line, offset) exceeds `Limits::max_directives`
+    TooManyDirectives {
+        line: u32,
+        offset: usize,
+        len: usize,
+        max: usize,
+    },
+    /// The block directive starting at (line, offset) exceeds `Limits::max_specs_per_block`
+    TooManySpecs {
+        line: u32,
+        offset: usize,
+        len: usize,
+        max: usize,
+    },
+}
+
+impl ParseError {
+    pub(crate) fn syntax(e: Err<Error<Span>>, input: Span) -> Self {
+        let pos = match &e {
+            Err::Error(e) | Err::Failure(e) => e.input,
+            Err::Incomplete(_) => input.slice(input.len()..),
+        };
+        let text = input.fragment();
+        let at = pos
+            .location_offset()
+            .saturating_sub(input.location_offset())
+            .min(text.len());
+        // a lone `\r` counts when it's where parsing stopped, or in the blanks right before
+        let blanks = text[..at].trim_end_matches([' ', '\t', '\r']).len();
+        let lone_cr = text[blanks..]
+            .match_indices('\r')
+            .map(|(i, _)| blanks + i)
+            .take_while(|&i| i <= at)
+            .find(|&i| !text[i + 1..].starts_with('\n'));
+        if let Some(i) = lone_cr {
+            let loc = location(&input.slice(i..));
+            return Self::UnsupportedLineEnding {
+                line: loc.line,
+                offset: loc.offset,
+                len: 1,
+            };
+        }
+        // an unclosed quote counts when it opens on the line where parsing stopped, at or after
+        // the start of the token there
+        let line_start = text[..at].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[at..].find('\n').map_or(text.len(), |i| at + i + 1);
+        let token_start = text[..at]
+            .rfind(char::is_whitespace)
+            .map_or(0, |i| i + 1)
+            .max(line_start);
+        let quote = unterminated_string(&text[line_start..line_end], input.extra.hash_comments)
+            .map(|i| line_start + i)
+            .filter(|&i| i >= token_start);
+        if let Some(i) = quote {
+            let loc = location(&input.slice(i..));
+            let rest = &text[i..];
+            return Self::UnterminatedString {
+                line: loc.line,
+                offset: loc.offset,
+                len: rest.find(['\r', '\n']).unwrap_or(rest.len()),
+            };
+        }
+        let loc = location(&pos);
+        if let Err::Failure(e) = &e {
+            if e.code == ErrorKind::Not {
+                let token = pos.fragment().split([' ', '\t', '\r', '\n']).next();
+                let token = token.unwrap_or_default();
+                return Self::UnexpectedTrailingToken {
+                    line: loc.line,
+                    offset: loc.offset,
+                    len: token.len(),
+                    token: token.into(),
+                };
+            }
+            if e.code == ErrorKind::Complete {
+                let (len, module_path) = parse_module_path_identifier(pos)
+                    .map(|(rest, i)| {
+                        let len = rest.location_offset() - pos.location_offset();
+                        (len, String::from(&*i))
+                    })
+                    .unwrap_or_default();
+                return Self::MissingVersion {
+                    line: loc.line,
+                    offset: loc.offset,
+                    len,
+                    module_path,
+                };
+            }
+        }
+        if matches!(&e, Err::Failure(e) if e.code == ErrorKind::Char) {
+            let ch = pos.fragment().chars().next().unwrap_or_default();
+            return Self::InvalidModulePathChar {
+                line: loc.line,
+                offset: loc.offset,
+                len: ch.len_utf8(),
+                ch,
+            };
+        }
+        let disallowed = match e {
+            Err::Failure(_) => DirectiveSet::disallowed_keyword(&pos),
+            _ => None,
+        };
+        if let Some(directive) = disallowed {
+            Self::DirectiveNotAllowed {
+                line: loc.line,
+                offset: loc.offset,
+                len: directive.len(),
+                directive,
+            }
+        } else if matches!(&e, Err::Failure(e) if e.code == ErrorKind::Eof) {
+            Self::TrailingContent {
+                line: loc.line,
+                offset: loc.offset,
+                len: token_len(pos.fragment()),
+            }
+        } else if pos.fragment().starts_with("/*") {
+            Self::BlockCommentUnsupported {
+                line: loc.line,
+                offset: loc.offset,
+                len: pos.fragment().find("*/").map_or(pos.len(), |i| i + 2),
+            }
+        } else {
+            Self::Syntax {
+                line: loc.line,
+                offset: loc.offset,
+                len: token_len(pos.fragment()),
+            }
+        }
+    }
+}
+
+/// Length of the token starting `s`, up to the next whitespace
+pub(crate) fn token_len(s: &str) -> usize {
+    s.find(char::is_whitespace).unwrap_or(s.len())
+}
+
+/// Offset of the first quote opening a string that isn't closed on its line. Quotes in
+/// comments and inside bare tokens don't open one.
+fn unterminated_string(text: &str, hash_comments: bool) -> Option<usize> {
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let mut chars = line.char_indices().peekable();
+        let mut boundary = true;
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '/' if line[i..].starts_with("//") => break,
+                '#' if hash_comments && boundary => break,
+                '"' | '`' if boundary => {
+                    let mut closed = false;
+                    while let Some((_, d)) = chars.next() {
+                        match d {
+                            '\\' if c == '"' => {
+                                chars.next_if(|&(_, d)| d != '\n');
+                            }
+                            '\n' => break,
+                            d if d == c => {
+                                closed = true;
+                                break;
+                            }
+                            _ => {}
+                        }
+                    }
+                    if !closed {
+                        return Some(start + i);
+                    }
+                }
+                _ => {}
+            }
+            boundary = " \t\r(),[]=".contains(c);
+        }
+        start += line.len();
+    }
+    None
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { line, offset, .. } => {
+                write!(f, "invalid go.mod syntax at line {line} (offset {offset})")
+            }
+            Self::TrailingContent { line, offset, .. } => write!(
+                f,
+                "unexpected content after the last directive at line {line} (offset {offset})"
+            ),
+            Self::BlockCommentUnsupported { line, offset, .. } => write!(
+                f,
+                "unsupported /* */ comment at line {line} (offset {offset}), use // instead"
+            ),
+            Self::DirectiveNotAllowed {
+                line,
+                offset,
+                directive,
+                ..
+            } => write!(
+                f,
+                "`{directive}` directive not allowed at line {line} (offset {offset})"
+            ),
+            Self::UnterminatedString { line, offset, .. } => write!(
+                f,
+                "unterminated string starting at line {line} (offset {offset})"
+            ),
+            Self::UnexpectedTrailingToken {
+                line,
+                offset,
+                token,
+                ..
+            } => write!(
+                f,
+                "unexpected `{token}` at line {line} (offset {offset}), expected a comment or the end of the line"
+            ),
+            Self::MissingVersion {
+                line,
+                offset,
+                module_path,
+                ..
+            } => write!(
+                f,
+                "missing version after {module_path} at line {line} (offset {offset})"
+            ),
+            Self::InvalidModulePathChar {
+                line, offset, ch, ..
+            } => write!(
+                f,
+                "invalid char {ch:?} in module path at line {line} (offset {offset})"
+            ),
+            Self::UnsupportedLineEnding { line, offset, .. } => write!(
+                f,
+                "unsupported \\r line ending at line {line} (offset {offset}), use \\n or \\r\\n"
+            ),
+            Self::InvalidUtf8 { line, offset, .. } => {
+                write!(f, "invalid UTF-8 at line {line} (offset {offset})")
+            }
+            Self::InputTooLong { len, max } => {
+                write!(f, "input is {len} bytes long, exceeding the limit of {max}")
+            }
+            Self::TooManyDirectives {
+                line, offset, max, ..
+            } => write!(
+                f,
+                "more than {max} directives, exceeded at line {line} (offset {offset})"
+            ),
+            Self::TooManySpecs {
+                line, offset, max, ..
+            } => write!(
+                f,
+                "directive at line {line} (offset {offset}) has more than {max} specs"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    /// `None` for errors not tied to a position, e.g. `InputTooLong`
+    pub fn located(&self) -> Option<LocatedError> {
+        let (line, offset, len) = match *self {
+            Self::Syntax {
+                line, offset, len, ..
+            }
+            | Self::BlockCommentUnsupported {
+                line, offset, len, ..
+            }
+            | Self::TrailingContent {
+                line, offset, len, ..
+            }
+            | Self::InvalidUtf8 {
+                line, offset, len, ..
+            }
+            | Self::UnsupportedLineEnding {
+                line, offset, len, ..
+            }
+            | Self::UnterminatedString {
+                line, offset, len, ..
+            }
+            | Self::MissingVersion {
+                line, offset, len, ..
+            }
+            | Self::InvalidModulePathChar {
+                line, offset, len, ..
+            }
+            | Self::UnexpectedTrailingToken {
+                line, offset, len, ..
+            }
+            | Self::DirectiveNotAllowed {
+                line, offset, len, ..
+            }
+            | Self::TooManyDirectives {
+                line, offset, len, ..
+            }
+            | Self::TooManySpecs {
+                line, offset, len, ..
+            } => (line, offset, len),
+            Self::InputTooLong { .. } => return None,
+        };
+        Some(LocatedError {
+            line,
+            offset,
+            len,
+            message: format!("{self}"),
+        })
+    }
+}
+
+/// An error at a position of the parsed text, see [`LocatedError::render`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocatedError {
+    /// Line as reported by the parser
+    pub line: u32,
+    /// 0-based byte offset into the parsed text
+    pub offset: usize,
+    /// Length in bytes of the offending token at `offset`, 0 when unknown
+    pub len: usize,
+    pub message: String,
+}
+
+impl From<Err<Error<(u32, usize)>>> for LocatedError {
+    /// Convert the error of [`crate::parse_gomod`]
+    fn from(e: Err<Error<(u32, usize)>>) -> Self {
+        match e {
+            Err::Error(e) | Err::Failure(e) => Self {
+                line: e.input.0,
+                offset: e.input.1,
+                len: 0,
+                message: "invalid go.mod syntax".into(),
+            },
+            Err::Incomplete(_) => Self {
+                line: 1,
+                offset: 0,
+                len: 0,
+                message: "incomplete input".into(),
+            },
+        }
+    }
+}
+
+impl LocatedError {
+    /// Render the error with the line of `source` it points at and a caret under its column:
+    ///
+    /// ```text
+    /// error: invalid go.mod syntax
+    ///  --> 3:5
+    ///   |
+    /// 3 | go 1.21 extra
+    ///   |         ^
+    /// ```
+    ///
+    /// `source` must be the parsed text. Tabs before the caret are kept, so it lines up
+    /// whatever the tab width of the terminal.
+    pub fn render(&self, source: &str) -> String {
+        self.render_impl(source, None)
+    }
+
+    /// Like [`LocatedError::render`], but tabs are expanded to the next multiple of
+    /// `tab_width` so the caret lands under the right glyph whatever the terminal, and the
+    /// column is the display column as with `ParseOptions::tab_width`.
+    pub fn render_with_tab_width(&self, source: &str, tab_width: usize) -> String {
+        self.render_impl(source, Some(tab_width))
+    }
+
+    fn render_impl(&self, source: &str, tab_width: Option<usize>) -> String {
+        let mut offset = self.offset.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |i| offset + i);
+        let mut text = String::from(source[start..end].trim_end_matches('\r'));
+        let prefix = &source[start..offset];
+        let (column, pad) = match tab_width {
+            Some(width) => {
+                text = expand_tabs(&text, width);
+                let column = display_width(prefix, width);
+                (column + 1, " ".repeat(column))
+            }
+            None => (
+                prefix.chars().count() + 1,
+                prefix
+                    .chars()
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect(),
+            ),
+        };
+        let line = format!("{}", self.line);
+        let gutter = " ".repeat(line.len());
+        format!(
+            "error: {}\n{gutter}--> {line}:{column}\n{gutter} |\n{line} | {text}\n{gutter} | {pad}^\n",
+            self.message
+        )
+    }
+}
+
+fn expand_tabs(s: &str, tab_width: usize) -> String {
+    let tab_width = tab_width.max(1);
+    let mut out = String::new();
+    for c in s.chars() {
+        if c == '\t' {
+            let col = out.chars().count();
+            out.extend(core::iter::repeat_n(' ', tab_width - col % tab_width));
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+impl fmt::Display for LocatedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LocatedError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_gomod, parse_gomod_limited, Limits};
+
+    use super::{LocatedError, ParseError};
+
+    #[test]
+    fn test_syntax_error() {
+        assert_eq!(
+            parse_gomod_limited("module example.com/m\n/* c */\n", Limits::default()),
+            Err(ParseError::BlockCommentUnsupported {
+                line: 2,
+                offset: 21,
+                len: 7
+            })
+        );
+        assert_eq!(
+            parse_gomod_limited("foo", Limits::default()),
+            Err(ParseError::Syntax {
+                line: 1,
+                offset: 0,
+                len: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_trailing_content() {
+        let text = "module example.com/m\n\ngo 1.21\n// c\n  requir example.com/a v1.0.0\n";
+        let e = ParseError::TrailingContent {
+            line: 5,
+            offset: 37,
+            len: 6,
+        };
+        assert_eq!(parse_gomod_limited(text, Limits::default()), Err(e.clone()));
+        assert_eq!(
+            crate::parse_with_options(text, Default::default()).unwrap_err(),
+            e
+        );
+        assert_eq!(
+            parse_gomod(text).map(|_| ()).map_err(LocatedError::from),
+            Err(LocatedError {
+                line: 5,
+                offset: 37,
+                len: 0,
+                message: "invalid go.mod syntax".into()
+            })
+        );
+        assert!(
+            parse_gomod_limited("module example.com/m\n// c\n \t\n", Limits::default()).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_lone_cr() {
+        let e = parse_gomod_limited("module example.com/m\rgo 1.21\r", Limits::default());
+        assert_eq!(
+            e,
+            Err(ParseError::UnsupportedLineEnding {
+                line: 1,
+                offset: 20,
+                len: 1
+            })
+        );
+        assert_eq!(
+            e.unwrap_err().to_string(),
+            "unsupported \\r line ending at line 1 (offset 20), use \\n or \\r\\n"
+        );
+        // a `\r` the parser took, e.g. in a comment, doesn't hide the actual error
+        let e = parse_gomod_limited("// a\rb\nmodule m\n\ngo 1.21 extra\n", Limits::default());
+        assert!(matches!(
+            e,
+            Err(ParseError::UnexpectedTrailingToken { line: 4, .. })
+        ));
+        assert!(
+            parse_gomod_limited("module example.com/m\r\ngo 1.21\r\n", Limits::default()).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        let e = parse_gomod_limited("module \"foo\n", Limits::default());
+        assert_eq!(
+            e,
+            Err(ParseError::UnterminatedString {
+                line: 1,
+                offset: 7,
+                len: 4
+            })
+        );
+        assert_eq!(
+            e.unwrap_err().to_string(),
+            "unterminated string starting at line 1 (offset 7)"
+        );
+        let text =
+            "module m // \"note\n\nrequire a v1.0.0 // \"b\nrequire c \"v\\\"1\"\nrequire d `v1\n";
+        assert_eq!(
+            parse_gomod_limited(text, Limits::default()),
+            Err(ParseError::UnterminatedString {
+                line: 5,
+                offset: 69,
+                len: 3
+            })
+        );
+        let text = "module m\n\nreplace a => \"../a\n";
+        assert!(matches!(
+            parse_gomod_limited(text, Limits::default()),
+            Err(ParseError::UnterminatedString { line: 3, .. })
+        ));
+        // the error where parsing stopped wins over a quote further down
+        let text = "module m\n\ngo 1.21 extra\n\nreplace a => \"../a\n";
+        assert!(matches!(
+            parse_gomod_limited(text, Limits::default()),
+            Err(ParseError::UnexpectedTrailingToken { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_missing_version() {
+        let e = parse_gomod_limited(
+            "module m\n\nrequire example.com/foo // todo\n",
+            Limits::default(),
+        );
+        assert_eq!(
+            e,
+            Err(ParseError::MissingVersion {
+                line: 3,
+                offset: 18,
+                len: 15,
+                module_path: "example.com/foo".into()
+            })
+        );
+        assert_eq!(
+            e.unwrap_err().to_string(),
+            "missing version after example.com/foo at line 3 (offset 18)"
+        );
+        let text = "module m\n\nrequire (\n\texample.com/a v1.0.0\n\texample.com/b\n\texample.com/c v1.0.0\n)\n";
+        assert_eq!(
+            parse_gomod_limited(text, Limits::default()),
+            Err(ParseError::MissingVersion {
+                line: 5,
+                offset: 43,
+                len: 13,
+                module_path: "example.com/b".into()
+            })
+        );
+        assert!(matches!(
+            parse_gomod_limited("module m\nrequire \"example.com/a\"", Limits::default()),
+            Err(ParseError::MissingVersion { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_module_path_char() {
+        let e = parse_gomod_limited("module localhost:8080/m\n", Limits::default());
+        assert_eq!(
+            e,
+            Err(ParseError::InvalidModulePathChar {
+                line: 1,
+                offset: 16,
+                len: 1,
+                ch: ':'
+            })
+        );
+        assert_eq!(
+            e.unwrap_err().to_string(),
+            "invalid char ':' in module path at line 1 (offset 16)"
+        );
+        let text = "module m\n\nrequire (\n\tlocalhost:8080/a v1.0.0\n)\n";
+        assert!(matches!(
+            parse_gomod_limited(text, Limits::default()),
+            Err(ParseError::InvalidModulePathChar {
+                line: 4,
+                ch: ':',
+                ..
+            })
+        ));
+        assert_eq!(
+            parse_gomod_limited("module example.com/Łódź\n", Limits::default()),
+            Err(ParseError::InvalidModulePathChar {
+                line: 1,
+                offset: 19,
+                len: 2,
+                ch: 'Ł'
+            })
+        );
+        let text = "module m\n\nreplace example.com/a => C:\\src\\a\n";
+        assert!(parse_gomod_limited(text, Limits::default()).is_ok());
+    }
+
+    #[test]
+    fn test_unexpected_trailing_token() {
+        let e = parse_gomod_limited("module m\ngo 1.21 require foo v1\n", Limits::default());
+        assert_eq!(
+            e,
+            Err(ParseError::UnexpectedTrailingToken {
+                line: 2,
+                offset: 17,
+                len: 7,
+                token: "require".into()
+            })
+        );
+        let text = "module m\n\nrequire (\n\texample.com/a v1.0.0 v1.1.0 // c\n)\n";
+        assert!(matches!(
+            parse_gomod_limited(text, Limits::default()),
+            Err(ParseError::UnexpectedTrailingToken { line: 4, token, .. }) if token == "v1.1.0"
+        ));
+        let text = "module m\nreplace a => ../a b\n";
+        assert!(matches!(
+            parse_gomod_limited(text, Limits::default()),
+            Err(ParseError::UnexpectedTrailingToken { line: 2, token, .. }) if token == "b"
+        ));
+    }
+
+    #[test]
+    fn test_render() {
+        let text = "module example.com/m\n\n\tgo 1.21 extra\n";
+        let e = parse_gomod_limited("\tgo 1.21 extra\n", Limits::default()).unwrap_err();
+        assert_eq!(
+            e.located().unwrap().render("\tgo 1.21 extra\n"),
+            "error: unexpected `extra` at line 1 (offset 9), expected a comment or the end of the line\n --> 1:10\n  |\n1 | \tgo 1.21 extra\n  | \t        ^\n"
+        );
+        let e = LocatedError {
+            line: 3,
+            offset: 31,
+            len: 0,
+            message: "unexpected token".into(),
+        };
+        assert_eq!(
+            e.render(text),
+            "error: unexpected token\n --> 3:10\n  |\n3 | \tgo 1.21 extra\n  | \t        ^\n"
+        );
+        let e: LocatedError = parse_gomod("foo").unwrap_err().into();
+        assert_eq!(
+            e.render("foo"),
+            "error: invalid go.mod syntax\n --> 1:1\n  |\n1 | foo\n  | ^\n"
+        );
+        assert_eq!(ParseError::InputTooLong { len: 2, max: 1 }.located(), None);
+    }
+
+    #[test]
+    fn test_located_len() {
+        let e = parse_gomod_limited("\tfoo 1.21\n", Limits::default())
+            .unwrap_err()
+            .located()
+            .unwrap();
+        assert_eq!((e.offset, e.len), (1, 3));
+        let e = parse_gomod_limited("module \"a b\n", Limits::default())
+            .unwrap_err()
+            .located()
+            .unwrap();
+        assert_eq!((e.offset, e.len), (7, 4));
+    }
+
+    #[test]
+    fn test_render_with_tab_width() {
+        let text = "require (\n\texample.com/a v1.0.0 extra\n)\n";
+        let e = LocatedError {
+            line: 2,
+            offset: 32,
+            len: 0,
+            message: "unexpected token".into(),
+        };
+        assert_eq!(
+            e.render_with_tab_width(text, 4),
+            "error: unexpected token\n --> 2:26\n  |\n2 |     example.com/a v1.0.0 extra\n  |                          ^\n"
+        );
+    }
+}
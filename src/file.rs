@@ -0,0 +1,80 @@
+use std::{fmt, fs, io, path::Path};
+
+use crate::{parse_with_options, GoModOwned, ParseError, ParseOptions};
+
+/// Error returned by [`parse_gomod_file`]
+#[derive(Debug)]
+pub enum FileError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Parse(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for FileError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ParseError> for FileError {
+    fn from(e: ParseError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+/// Read and parse the go.mod file at `path`.
+///
+/// The tree is owned since it can't borrow the buffer read internally.
+pub fn parse_gomod_file(path: impl AsRef<Path>) -> Result<GoModOwned, FileError> {
+    let text = fs::read_to_string(path)?;
+    let gomod = parse_with_options(&text, ParseOptions::default())?;
+    Ok((&gomod).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{DirectiveOwned, ParseError};
+
+    use super::{parse_gomod_file, FileError};
+
+    #[test]
+    fn test_parse_gomod_file() {
+        let dir = std::env::temp_dir().join(format!("gomod-rs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("go.mod");
+        fs::write(&path, "module example.com/m\n\ngo 1.21\n").unwrap();
+        let gomod = parse_gomod_file(&path).unwrap();
+        assert_eq!(
+            gomod.directives[1].value,
+            DirectiveOwned::Go {
+                version: "1.21".into()
+            }
+        );
+        fs::write(&path, "foo\n").unwrap();
+        assert!(matches!(
+            parse_gomod_file(&path),
+            Err(FileError::Parse(ParseError::Syntax { line: 1, .. }))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(parse_gomod_file(&path), Err(FileError::Io(_))));
+    }
+}
@@ -0,0 +1,599 @@
+//! Canonical formatting, in the layout printed by `go mod edit -fmt`.
+
+use alloc::{collections::BTreeSet, format, string::String, vec, vec::Vec};
+use core::cmp::Ordering;
+
+use crate::{
+    owned::godebug_token, parse_gomod_limited, version::version_cmp, Context, Directive, GoMod,
+    Identifier, Limits, ParseError, ReplaceSpec, Replacement, RetractSpec,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item<'t> {
+    Blank,
+    Comment(&'t str),
+}
+
+/// A directive line, or a spec line inside a block
+struct Line<'t> {
+    before: Vec<Item<'t>>,
+    tokens: String,
+    suffix: Option<&'t str>,
+}
+
+enum Stmt<'t> {
+    Comments(Vec<&'t str>),
+    Line {
+        before: Vec<Item<'t>>,
+        verb: &'static str,
+        line: Line<'t>,
+        suffixes: Vec<&'t str>,
+    },
+    Block {
+        before: Vec<&'t str>,
+        verb: &'static str,
+        open: Option<&'t str>,
+        lines: Vec<Line<'t>>,
+        trailing: Vec<Item<'t>>,
+        close: Option<&'t str>,
+    },
+}
+
+/// Split a line into its tokens and the text after `//`, skipping over quoted strings
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut chars = line.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '`' => {
+                chars.by_ref().find(|&(_, c)| c == '`');
+            }
+            '/' if line[i + 1..].starts_with('/') => {
+                return (&line[..i], Some(line[i + 2..].trim_end()))
+            }
+            _ => {}
+        }
+    }
+    (line, None)
+}
+
+/// Classify a line holding no tokens, `None` if it does
+fn item(line: &str) -> Option<Item<'_>> {
+    match split_comment(line) {
+        (head, _) if !head.trim().is_empty() => None,
+        (_, Some(comment)) => Some(Item::Comment(comment)),
+        (_, None) => Some(Item::Blank),
+    }
+}
+
+fn comments<'t>(items: &[Item<'t>]) -> Vec<&'t str> {
+    items
+        .iter()
+        .filter_map(|i| match i {
+            Item::Comment(c) => Some(*c),
+            Item::Blank => None,
+        })
+        .collect()
+}
+
+/// Split comment lines into blank-line separated groups. The last group is returned apart
+/// if nothing separates it from what follows.
+fn comment_groups<'t>(items: &[Item<'t>]) -> (Vec<Vec<&'t str>>, Vec<&'t str>) {
+    let mut groups: Vec<Vec<&str>> = items
+        .split(|i| *i == Item::Blank)
+        .map(comments)
+        .filter(|i| !i.is_empty())
+        .collect();
+    let attached = match items.last() {
+        Some(Item::Comment(_)) => groups.pop().unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    (groups, attached)
+}
+
+pub(crate) fn push_identifier(out: &mut String, identifier: &Identifier) {
+    match identifier {
+        Identifier::Bare(s) => out.push_str(s),
+        Identifier::Raw(s) => {
+            out.push('`');
+            out.push_str(s);
+            out.push('`');
+        }
+        Identifier::Interpreted(s) => {
+            out.push('"');
+            for c in s.chars() {
+                match c {
+                    '"' | '\\' => {
+                        out.push('\\');
+                        out.push(c);
+                    }
+                    '\n' => out.push_str("\\n"),
+                    '\t' => out.push_str("\\t"),
+                    _ => out.push(c),
+                }
+            }
+            out.push('"');
+        }
+    }
+}
+
+fn tokens(identifiers: &[&Identifier]) -> String {
+    let mut out = String::new();
+    for (i, identifier) in identifiers.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        push_identifier(&mut out, identifier);
+    }
+    out
+}
+
+fn replace_tokens(spec: &ReplaceSpec) -> String {
+    let mut out = tokens(&[&spec.module_path]);
+    if let Some(version) = &spec.version {
+        out.push(' ');
+        push_identifier(&mut out, version);
+    }
+    out.push_str(" => ");
+    match &spec.replacement {
+        Replacement::FilePath(path) => push_identifier(&mut out, path),
+        Replacement::Module((path, version)) => out.push_str(&tokens(&[path, version])),
+    }
+    out
+}
+
+fn retract_tokens(spec: &RetractSpec) -> String {
+    match spec {
+        RetractSpec::Version(version) => tokens(&[version]),
+        RetractSpec::Range((low, high)) => {
+            let mut out = String::from("[");
+            push_identifier(&mut out, low);
+            out.push_str(", ");
+            push_identifier(&mut out, high);
+            out.push(']');
+            out
+        }
+    }
+}
+
+fn spec_tokens<T>(specs: &[Context<T>], f: impl Fn(&T) -> String) -> Vec<String> {
+    specs.iter().map(|i| f(&i.value)).collect()
+}
+
+/// Keyword and the tokens of each spec (a single one for non-block directives)
+fn directive_tokens(directive: &Directive) -> (&'static str, Vec<String>) {
+    let tokens = match directive {
+        Directive::Module { module_path } => vec![tokens(&[module_path])],
+        Directive::Go { version } => vec![tokens(&[version])],
+        Directive::Toolchain { name } => vec![tokens(&[name])],
+        Directive::Require { specs, .. } | Directive::Exclude { specs, .. } => {
+            spec_tokens(specs, |(p, v)| tokens(&[p, v]))
+        }
+        Directive::Godebug { specs, .. } => spec_tokens(specs, |(k, v)| {
+            format!("{}={}", godebug_token(k), godebug_token(v))
+        }),
+        Directive::Replace { specs, .. } => spec_tokens(specs, replace_tokens),
+        Directive::Retract { specs, .. } => spec_tokens(specs, retract_tokens),
+        Directive::Use { specs, .. } | Directive::Ignore { specs, .. } => {
+            spec_tokens(specs, |p| tokens(&[p]))
+        }
+    };
+    (directive.kind().keyword(), tokens)
+}
+
+fn retract_bounds(tokens: &str) -> (&str, &str) {
+    let t = tokens.trim_start_matches('[').trim_end_matches(']');
+    t.split_once(", ").unwrap_or((t, t))
+}
+
+fn semver_order(a: &str, b: &str) -> Ordering {
+    version_cmp(a, b).unwrap_or_else(|| a.cmp(b))
+}
+
+/// Order of spec lines in a block, as in `modfile.File.SortBlocks`
+fn line_order(verb: &str, semantic_exclude: bool, a: &Line, b: &Line) -> Ordering {
+    match verb {
+        "exclude" if semantic_exclude => {
+            let (ap, av) = a.tokens.split_once(' ').unwrap_or((&a.tokens, ""));
+            let (bp, bv) = b.tokens.split_once(' ').unwrap_or((&b.tokens, ""));
+            ap.cmp(bp).then_with(|| semver_order(av, bv))
+        }
+        // intervals in descending order, by low then high version
+        "retract" => {
+            let ((al, ah), (bl, bh)) = (retract_bounds(&a.tokens), retract_bounds(&b.tokens));
+            semver_order(bl, al).then_with(|| semver_order(bh, ah))
+        }
+        _ => a.tokens.cmp(&b.tokens),
+    }
+}
+
+fn is_semantic_exclude(gomod: &GoMod) -> bool {
+    gomod
+        .go_version()
+        .is_some_and(|i| (i.major, i.minor) >= (1, 21))
+}
+
+/// Specs dropped as in `modfile.File.removeDups`, as `(directive, spec)` indexes: an exclude
+/// seen before, and a replace of a module version replaced again further down
+fn duplicates(gomod: &GoMod) -> BTreeSet<(usize, usize)> {
+    let mut dropped = BTreeSet::new();
+    let (mut excludes, mut replaces) = (BTreeSet::new(), Vec::new());
+    for (d, directive) in gomod.iter().enumerate() {
+        match &directive.value {
+            Directive::Exclude { specs, .. } => {
+                for (s, spec) in specs.iter().enumerate() {
+                    if !excludes.insert((&*spec.value.0, &*spec.value.1)) {
+                        dropped.insert((d, s));
+                    }
+                }
+            }
+            Directive::Replace { specs, .. } => {
+                replaces.extend(specs.iter().enumerate().map(|(s, spec)| {
+                    let old = (&*spec.value.module_path, spec.value.version.as_deref());
+                    ((d, s), old)
+                }))
+            }
+            _ => {}
+        }
+    }
+    let mut replaced = BTreeSet::new();
+    for (at, old) in replaces.into_iter().rev() {
+        if !replaced.insert(old) {
+            dropped.insert(at);
+        }
+    }
+    dropped
+}
+
+fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+fn build<'t>(text: &'t str, gomod: &GoMod) -> Vec<Stmt<'t>> {
+    let semantic_exclude = is_semantic_exclude(gomod);
+    let dropped = duplicates(gomod);
+    let mut stmts = Vec::new();
+    let mut pos = 0;
+    for (d, directive) in gomod.iter().enumerate() {
+        let start = line_start(text, directive.range.0.offset);
+        let leading: Vec<_> = text[pos..start].lines().filter_map(item).collect();
+        let (groups, before) = comment_groups(&leading);
+        stmts.extend(groups.into_iter().map(Stmt::Comments));
+        let before: Vec<_> = before.into_iter().map(Item::Comment).collect();
+        pos = directive.range.1.offset;
+
+        let (verb, specs) = directive_tokens(&directive.value);
+        let mut lines = text[start..pos].lines();
+        let (head, open) = split_comment(lines.next().unwrap_or_default());
+        let single_line = !head.trim_end().ends_with('(');
+        if single_line && !specs.is_empty() {
+            if !dropped.contains(&(d, 0)) {
+                stmts.push(Stmt::Line {
+                    before,
+                    verb,
+                    line: Line {
+                        before: Vec::new(),
+                        tokens: specs.into_iter().next().unwrap_or_default(),
+                        suffix: open,
+                    },
+                    suffixes: Vec::new(),
+                });
+            }
+            continue;
+        }
+        // `verb ()` is an empty block, its comment follows `)`
+        let (open, close) = match single_line {
+            true => (None, open),
+            false => (open, lines.next_back().and_then(|i| split_comment(i).1)),
+        };
+        let mut specs = specs.into_iter().enumerate();
+        let mut block = Vec::new();
+        let mut items = Vec::new();
+        for line in lines {
+            match item(line) {
+                // blank lines right after `(` or repeated are dropped
+                Some(Item::Blank) if block.is_empty() && items.is_empty() => {}
+                Some(Item::Blank) if items.last() == Some(&Item::Blank) => {}
+                Some(i) => items.push(i),
+                None => {
+                    let before = core::mem::take(&mut items);
+                    let (s, tokens) = specs.next().unwrap_or_default();
+                    // a dropped spec takes the comments above it along
+                    if !dropped.contains(&(d, s)) {
+                        block.push(Line {
+                            before,
+                            tokens,
+                            suffix: split_comment(line).1,
+                        });
+                    }
+                }
+            }
+        }
+        // `modfile.File.SortBlocks` sorts the whole block, lines keeping the comments and blank
+        // line above them
+        if !matches!(verb, "module" | "go" | "toolchain") {
+            block.sort_by(|a, b| line_order(verb, semantic_exclude, a, b));
+        }
+        // `modfile.FileSyntax.Cleanup` drops blocks left without specs along with their
+        // comments, and collapses a block of a single spec unless something is left above `)`.
+        // The comment after `(` is lost then.
+        match block.len() {
+            0 => {}
+            1 if items.is_empty() => {
+                let line = block.pop().unwrap();
+                let mut before = before;
+                before.extend(&line.before);
+                stmts.push(Stmt::Line {
+                    before,
+                    verb,
+                    line,
+                    suffixes: close.into_iter().collect(),
+                });
+            }
+            _ => stmts.push(Stmt::Block {
+                before: comments(&before),
+                verb,
+                open,
+                lines: block,
+                trailing: items,
+                close,
+            }),
+        }
+    }
+    // the parser only leaves comments and blank lines after the last directive, Go keeps them
+    // apart from it
+    let trailing: Vec<_> = text[pos..].lines().filter_map(item).collect();
+    let (groups, last) = comment_groups(&trailing);
+    stmts.extend(groups.into_iter().map(Stmt::Comments));
+    if !last.is_empty() {
+        stmts.push(Stmt::Comments(last));
+    }
+    stmts
+}
+
+fn push_comment(out: &mut String, indent: &str, comment: &str) {
+    out.push_str(indent);
+    out.push_str("//");
+    out.push_str(comment);
+    out.push('\n');
+}
+
+fn push_items(out: &mut String, indent: &str, items: &[Item]) {
+    for item in items {
+        match item {
+            Item::Blank => out.push('\n'),
+            Item::Comment(comment) => push_comment(out, indent, comment),
+        }
+    }
+}
+
+// Like Go's printer, a comment after the first one on a line goes on a line of its own
+fn push_line(out: &mut String, indent: &str, line: &Line, suffixes: &[&str]) {
+    out.push_str(indent);
+    out.push_str(&line.tokens);
+    for (i, suffix) in line.suffix.iter().chain(suffixes).enumerate() {
+        match i {
+            0 => out.push(' '),
+            _ => {
+                out.push('\n');
+                out.push_str(indent);
+            }
+        }
+        out.push_str("//");
+        out.push_str(suffix);
+    }
+    out.push('\n');
+}
+
+fn print(stmts: &[Stmt]) -> String {
+    let mut out = String::new();
+    for (n, stmt) in stmts.iter().enumerate() {
+        if n > 0 {
+            out.push('\n');
+        }
+        match stmt {
+            Stmt::Comments(comments) => {
+                for comment in comments {
+                    push_comment(&mut out, "", comment);
+                }
+            }
+            Stmt::Line {
+                before,
+                verb,
+                line,
+                suffixes,
+            } => {
+                push_items(&mut out, "", before);
+                out.push_str(verb);
+                out.push(' ');
+                push_line(&mut out, "", line, suffixes);
+            }
+            Stmt::Block {
+                before,
+                verb,
+                open,
+                lines,
+                trailing,
+                close,
+            } => {
+                for comment in before {
+                    push_comment(&mut out, "", comment);
+                }
+                out.push_str(verb);
+                out.push_str(" (");
+                if let Some(comment) = open {
+                    out.push_str(" //");
+                    out.push_str(comment);
+                }
+                out.push('\n');
+                for line in lines {
+                    push_items(&mut out, "\t", &line.before);
+                    push_line(&mut out, "\t", line, &[]);
+                }
+                push_items(&mut out, "\t", trailing);
+                out.push(')');
+                if let Some(comment) = close {
+                    out.push_str(" //");
+                    out.push_str(comment);
+                }
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// Reformat go.mod `text` the way `go mod edit -fmt` does.
+///
+/// - directives are separated by a single blank line and spec lines are indented with a tab
+/// - tokens are separated by a single space, columns aren't aligned
+/// - blocks holding a single spec are collapsed to a single line unless comments are left
+///   above `)`, and blocks without specs are removed along with their comments
+/// - the spec lines of a block are sorted, each keeping the comments and blank line above it
+/// - an exclude seen before is dropped, and so is a replace of a module version replaced again
+///   further down
+/// - comments are kept on the line they annotate, comment groups separated by blank lines
+///   stay apart
+///
+/// These follow `SortBlocks` and `Cleanup` in golang.org/x/mod/modfile. Formatting its own
+/// output returns it unchanged, unless a collapsed block had comments after both its spec and
+/// `)`: Go then writes the second one on a line of its own, which reads back as a comment
+/// group.
+pub fn format_gomod(text: &str) -> Result<String, ParseError> {
+    let gomod = parse_gomod_limited(
+        text,
+        Limits {
+            max_input_len: usize::MAX,
+            max_directives: usize::MAX,
+            max_specs_per_block: usize::MAX,
+        },
+    )?;
+    Ok(print(&build(text, &gomod)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ParseError;
+
+    use super::format_gomod;
+
+    // Expected output follows golang.org/x/mod/modfile as run by `go mod edit -fmt`:
+    // `File.SortBlocks` sorts whole blocks, `FileSyntax.Cleanup` collapses and drops blocks,
+    // `removeDups` drops repeated excludes and replaces, and the printer puts a blank line
+    // between statements, comment groups included.
+    const INPUT: &str = "// banner
+
+// about the module
+module   example.com/m   // main
+
+go 1.21
+toolchain go1.22.1
+require (
+    example.com/z v1.0.0 // z
+    // about a
+    example.com/a v1.0.0
+
+    example.com/y v1.0.0
+    example.com/b   v1.0.0 // indirect
+)
+require (
+  example.com/single v0.1.0 // only one
+)
+exclude (
+    example.com/x v1.10.0
+    example.com/x v1.9.0
+    // repeated
+    example.com/x v1.10.0
+)
+replace example.com/a v1.0.0=>../a
+retract [v1.0.0,v1.0.5]   // broken
+godebug ( // dropped
+    panicnil=1
+)
+replace (
+    example.com/a v1.0.0 => ../a2
+
+)
+// about nothing
+require (
+    // nothing
+)
+exclude ()
+replace ( ) // empty
+// trailing
+";
+
+    const OUTPUT: &str = "// banner
+
+// about the module
+module example.com/m // main
+
+go 1.21
+
+toolchain go1.22.1
+
+require (
+\t// about a
+\texample.com/a v1.0.0
+\texample.com/b v1.0.0 // indirect
+
+\texample.com/y v1.0.0
+\texample.com/z v1.0.0 // z
+)
+
+require example.com/single v0.1.0 // only one
+
+exclude (
+\texample.com/x v1.9.0
+\texample.com/x v1.10.0
+)
+
+retract [v1.0.0, v1.0.5] // broken
+
+godebug panicnil=1
+
+replace (
+\texample.com/a v1.0.0 => ../a2
+
+)
+
+// trailing
+";
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_gomod(INPUT).unwrap(), OUTPUT);
+        assert_eq!(format_gomod(OUTPUT).unwrap(), OUTPUT);
+        assert_eq!(
+            format_gomod("module \"example.com/m\" // \"//\"\r\n").unwrap(),
+            "module \"example.com/m\" // \"//\"\n"
+        );
+        assert_eq!(
+            format_gomod("module m\n\ngodebug \"a b\"=1\ngodebug x=`y\"`\n").unwrap(),
+            "module m\n\ngodebug \"a b\"=1\n\ngodebug x=`y\"`\n"
+        );
+        // the printer writes each suffix comment after the first on a line of its own
+        assert_eq!(
+            format_gomod("module m\n\nrequire (\n\ta v1.0.0 // a\n) // block\n").unwrap(),
+            "module m\n\nrequire a v1.0.0 // a\n// block\n"
+        );
+        assert_eq!(
+            format_gomod("module m\n\nfoo\n"),
+            Err(ParseError::TrailingContent {
+                line: 3,
+                offset: 10,
+                len: 3
+            })
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use alloc::vec::Vec;
+
+use crate::{
+    directive_set::KEYWORDS, parse_with_options, GoMod, Location, ParseError, ParseOptions, Range,
+};
+
+/// A `path version hash` line of a go.sum file, e.g.
+/// `golang.org/x/text v0.3.0/go.mod h1:NqM8EUOU14njkJ3fqMW+pc6Ldnwhi/IjpwHt7yyuwOQ=`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoSumEntry<'a> {
+    pub module_path: &'a str,
+    /// The version, ending with `/go.mod` when the hash is of the go.mod file only
+    pub version: &'a str,
+    pub hash: &'a str,
+    pub range: Range,
+}
+
+impl GoSumEntry<'_> {
+    /// Whether the hash is of the module's go.mod file rather than its whole content
+    pub fn is_gomod_hash(&self) -> bool {
+        self.version.ends_with("/go.mod")
+    }
+}
+
+/// A go.mod or go.sum file, see [`detect_and_parse`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Detected<'a> {
+    GoMod(GoMod<'a>),
+    GoSum(Vec<GoSumEntry<'a>>),
+}
+
+/// Parse the lines of a go.sum file.
+///
+/// Like Go, blank lines are skipped and other lines must have exactly three fields. Lines
+/// starting with `//` are skipped as well, which Go would reject.
+pub fn parse_gosum(text: &str) -> Result<Vec<GoSumEntry<'_>>, ParseError> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with("//") {
+            continue;
+        }
+        let line_number = i as u32 + 1;
+        let fields: Vec<_> = line.split_whitespace().collect();
+        let [module_path, version, hash] = fields[..] else {
+            return Err(ParseError::Syntax {
+                line: line_number,
+                offset: start + line.len() - trimmed.len(),
+                len: trimmed.trim_end().len(),
+            });
+        };
+        let location = |column: usize| Location {
+            line: line_number,
+            column: column + 1,
+            offset: start + column,
+        };
+        let first = line.len() - trimmed.len();
+        let last = line.trim_end().len();
+        entries.push(GoSumEntry {
+            module_path,
+            version,
+            hash,
+            range: (location(first), location(last)),
+        });
+    }
+    Ok(entries)
+}
+
+/// Parse `text` as go.sum if its first line that isn't blank or a `//` comment has the
+/// `path version hash` form, as go.mod otherwise.
+///
+/// A line starting with a directive keyword is always go.mod, so a malformed go.mod still
+/// fails with the go.mod error.
+pub fn detect_and_parse(text: &str) -> Result<Detected<'_>, ParseError> {
+    let first = text
+        .lines()
+        .map(str::trim)
+        .find(|i| !i.is_empty() && !i.starts_with("//"));
+    let is_gosum = first.is_some_and(|line| {
+        let fields: Vec<_> = line.split_whitespace().collect();
+        let keyword = fields[0].split('(').next().unwrap_or_default();
+        fields.len() == 3 && !KEYWORDS.iter().any(|&(k, _)| k == keyword)
+    });
+    if is_gosum {
+        parse_gosum(text).map(Detected::GoSum)
+    } else {
+        parse_with_options(text, ParseOptions::default()).map(Detected::GoMod)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ParseError;
+
+    use super::{detect_and_parse, parse_gosum, Detected};
+
+    const GO_SUM: &str = "
+golang.org/x/text v0.3.0 h1:g61tztE5qeGQ89tm6NTjjM9VPIm088od1l6aSorWRWg=
+golang.org/x/text v0.3.0/go.mod h1:NqM8EUOU14njkJ3fqMW+pc6Ldnwhi/IjpwHt7yyuwOQ=\r
+";
+
+    #[test]
+    fn test_parse_gosum() {
+        let entries = parse_gosum(GO_SUM).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].module_path, "golang.org/x/text");
+        assert!(!entries[0].is_gomod_hash());
+        assert_eq!(entries[1].version, "v0.3.0/go.mod");
+        assert!(entries[1].is_gomod_hash());
+        assert_eq!(
+            entries[1].hash,
+            "h1:NqM8EUOU14njkJ3fqMW+pc6Ldnwhi/IjpwHt7yyuwOQ="
+        );
+        assert_eq!(entries[1].range.0.line, 3);
+        assert_eq!(entries[1].range.0.column, 1);
+        assert_eq!(
+            &GO_SUM[entries[1].range.0.offset..entries[1].range.1.offset],
+            GO_SUM.lines().nth(2).unwrap().trim_end()
+        );
+
+        assert_eq!(
+            parse_gosum("a v1.0.0 h1:x=\n  b v1.0.0\n"),
+            Err(ParseError::Syntax {
+                line: 2,
+                offset: 17,
+                len: 8
+            })
+        );
+    }
+
+    #[test]
+    fn test_detect_and_parse() {
+        let text = format!("// fixture\n{GO_SUM}");
+        assert!(matches!(detect_and_parse(&text), Ok(Detected::GoSum(i)) if i.len() == 2));
+
+        let text = "\n// fixture\n\nmodule example.com/m\n\ngo 1.21\n";
+        assert!(matches!(detect_and_parse(text), Ok(Detected::GoMod(i)) if i.len() == 2));
+        let text = "require example.com/a v1.0.0\n";
+        assert!(matches!(detect_and_parse(text), Ok(Detected::GoMod(_))));
+        let text = "require(example.com/a v1.0.0\n";
+        assert!(matches!(
+            detect_and_parse(text),
+            Err(ParseError::Syntax { .. })
+        ));
+    }
+}
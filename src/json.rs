@@ -0,0 +1,325 @@
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt::Write;
+
+use crate::{query::is_indirect, Directive, GoMod, Replacement, RetractSpec};
+
+enum Value<'s> {
+    Str(&'s str),
+    Owned(String),
+    Bool(bool),
+    Null,
+    Object(Vec<(&'static str, Value<'s>)>),
+    Array(Vec<Value<'s>>),
+}
+
+// escaping of Go's encoding/json, including its HTML-safe escapes
+fn write_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '<' | '>' | '&' | '\u{2028}' | '\u{2029}' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// layout of Go's json.MarshalIndent with a tab indent
+fn write_value(out: &mut String, value: &Value, depth: usize) {
+    let indent = |out: &mut String, depth| {
+        out.push('\n');
+        (0..depth).for_each(|_| out.push('\t'));
+    };
+    match value {
+        Value::Str(s) => write_str(out, s),
+        Value::Owned(s) => write_str(out, s),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Null => out.push_str("null"),
+        Value::Object(fields) => {
+            out.push('{');
+            for (i, (key, value)) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                indent(out, depth + 1);
+                write_str(out, key);
+                out.push_str(": ");
+                write_value(out, value, depth + 1);
+            }
+            indent(out, depth);
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                indent(out, depth + 1);
+                write_value(out, item, depth + 1);
+            }
+            indent(out, depth);
+            out.push(']');
+        }
+    }
+}
+
+/// Go encodes nil slices as `null`
+fn array(items: Vec<Value>) -> Value {
+    match items.is_empty() {
+        true => Value::Null,
+        false => Value::Array(items),
+    }
+}
+
+/// Comment lines joined with `\n`, as `modfile` reads retract rationales and deprecations
+fn comment_text(comments: &[&str]) -> String {
+    let mut ret = String::new();
+    for (i, comment) in comments.iter().enumerate() {
+        if i > 0 {
+            ret.push('\n');
+        }
+        ret.push_str(comment.trim());
+    }
+    ret
+}
+
+fn deprecation(comments: &[&str]) -> Option<String> {
+    let text = comment_text(comments);
+    text.split("\n\n")
+        .find_map(|i| i.strip_prefix("Deprecated:"))
+        .map(|i| i.trim_start_matches(' ').into())
+}
+
+fn module_version<'s>(path: &'s str, version: Option<&'s str>) -> Value<'s> {
+    let mut fields = vec![("Path", Value::Str(path))];
+    if let Some(version) = version {
+        fields.push(("Version", Value::Str(version)));
+    }
+    Value::Object(fields)
+}
+
+impl GoMod<'_> {
+    /// Render the file the way `go mod edit -json` prints it, with the field names and
+    /// nesting of Go's `modfile` types and a trailing newline.
+    ///
+    /// Like Go, a require is `Indirect` when its line ends with a `// indirect` comment, and
+    /// the comments of a retract (or of its block, when it has none) are its `Rationale`.
+    pub fn to_go_edit_json(&self) -> String {
+        let (mut module, mut go, mut toolchain) = (None, None, None);
+        let (mut godebug, mut require, mut exclude, mut replace, mut retract, mut ignore) =
+            (vec![], vec![], vec![], vec![], vec![], vec![]);
+        for directive in self.iter() {
+            match &directive.value {
+                Directive::Module { module_path } => {
+                    module.get_or_insert((&**module_path, &directive.comments[..]));
+                }
+                Directive::Go { version } => {
+                    go.get_or_insert(&**version);
+                }
+                Directive::Toolchain { name } => {
+                    toolchain.get_or_insert(&**name);
+                }
+                Directive::Godebug { specs, .. } => godebug.extend(specs.iter().map(|i| {
+                    Value::Object(vec![
+                        ("Key", Value::Str(i.value.0)),
+                        ("Value", Value::Str(i.value.1)),
+                    ])
+                })),
+                Directive::Require { specs, .. } => require.extend(specs.iter().map(|i| {
+                    let mut fields = vec![
+                        ("Path", Value::Str(&i.value.0)),
+                        ("Version", Value::Str(&i.value.1)),
+                    ];
+                    if is_indirect(&i.comments, i.suffix_comment) {
+                        fields.push(("Indirect", Value::Bool(true)));
+                    }
+                    Value::Object(fields)
+                })),
+                Directive::Exclude { specs, .. } => exclude.extend(
+                    specs
+                        .iter()
+                        .map(|i| module_version(&i.value.0, Some(&i.value.1))),
+                ),
+                Directive::Replace { specs, .. } => replace.extend(specs.iter().map(|i| {
+                    let new = match &i.value.replacement {
+                        Replacement::FilePath(path) => module_version(path, None),
+                        Replacement::Module((path, version)) => module_version(path, Some(version)),
+                    };
+                    Value::Object(vec![
+                        (
+                            "Old",
+                            module_version(&i.value.module_path, i.value.version.as_deref()),
+                        ),
+                        ("New", new),
+                    ])
+                })),
+                Directive::Retract { specs, .. } => retract.extend(specs.iter().map(|i| {
+                    let (low, high) = match &i.value {
+                        RetractSpec::Version(v) => (&**v, &**v),
+                        RetractSpec::Range((low, high)) => (&**low, &**high),
+                    };
+                    let comments = match i.comments.is_empty() {
+                        true => &directive.comments,
+                        false => &i.comments,
+                    };
+                    let mut fields = vec![("Low", Value::Str(low)), ("High", Value::Str(high))];
+                    let rationale = comment_text(comments);
+                    if !rationale.is_empty() {
+                        fields.push(("Rationale", Value::Owned(rationale)));
+                    }
+                    Value::Object(fields)
+                })),
+                Directive::Ignore { specs, .. } => ignore.extend(
+                    specs
+                        .iter()
+                        .map(|i| Value::Object(vec![("Path", Value::Str(&i.value))])),
+                ),
+                // go.work only
+                Directive::Use { .. } => {}
+            }
+        }
+
+        let (path, comments) = module.unwrap_or(("", &[]));
+        let mut module = vec![("Path", Value::Str(path))];
+        if let Some(deprecated) = deprecation(comments) {
+            module.push(("Deprecated", Value::Owned(deprecated)));
+        }
+        let mut fields = vec![("Module", Value::Object(module))];
+        if let Some(go) = go {
+            fields.push(("Go", Value::Str(go)));
+        }
+        if let Some(toolchain) = toolchain {
+            fields.push(("Toolchain", Value::Str(toolchain)));
+        }
+        if !godebug.is_empty() {
+            fields.push(("Godebug", Value::Array(godebug)));
+        }
+        fields.extend([
+            ("Require", array(require)),
+            ("Exclude", array(exclude)),
+            ("Replace", array(replace)),
+            ("Retract", array(retract)),
+        ]);
+        // only printed by Go versions knowing the directive, so left out when unused
+        if !ignore.is_empty() {
+            fields.push(("Ignore", Value::Array(ignore)));
+        }
+        let mut out = String::new();
+        write_value(&mut out, &Value::Object(fields), 0);
+        out.push('\n');
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_gomod;
+
+    const GOMOD: &str = r#"// Deprecated: use example.com/m/v2
+module example.com/m
+
+go 1.21
+
+toolchain go1.22.1
+
+godebug panicnil=1
+
+require (
+	example.com/a v1.0.0
+	example.com/b v1.2.0 // indirect
+)
+
+exclude example.com/a v0.9.0
+
+replace (
+	example.com/a v1.0.0 => ../a
+	example.com/b => example.com/fork/b v1.2.1
+)
+
+// published with <bugs> & typos
+retract [v1.0.0, v1.0.5]
+"#;
+
+    // as printed by `go mod edit -json`
+    const JSON: &str = r#"{
+	"Module": {
+		"Path": "example.com/m",
+		"Deprecated": "use example.com/m/v2"
+	},
+	"Go": "1.21",
+	"Toolchain": "go1.22.1",
+	"Godebug": [
+		{
+			"Key": "panicnil",
+			"Value": "1"
+		}
+	],
+	"Require": [
+		{
+			"Path": "example.com/a",
+			"Version": "v1.0.0"
+		},
+		{
+			"Path": "example.com/b",
+			"Version": "v1.2.0",
+			"Indirect": true
+		}
+	],
+	"Exclude": [
+		{
+			"Path": "example.com/a",
+			"Version": "v0.9.0"
+		}
+	],
+	"Replace": [
+		{
+			"Old": {
+				"Path": "example.com/a",
+				"Version": "v1.0.0"
+			},
+			"New": {
+				"Path": "../a"
+			}
+		},
+		{
+			"Old": {
+				"Path": "example.com/b"
+			},
+			"New": {
+				"Path": "example.com/fork/b",
+				"Version": "v1.2.1"
+			}
+		}
+	],
+	"Retract": [
+		{
+			"Low": "v1.0.0",
+			"High": "v1.0.5",
+			"Rationale": "published with \u003cbugs\u003e \u0026 typos"
+		}
+	]
+}
+"#;
+
+    #[test]
+    fn test_go_edit_json() {
+        assert_eq!(parse_gomod(GOMOD).unwrap().to_go_edit_json(), JSON);
+        assert_eq!(
+            parse_gomod("module example.com/m\n")
+                .unwrap()
+                .to_go_edit_json(),
+            "{\n\t\"Module\": {\n\t\t\"Path\": \"example.com/m\"\n\t},\n\t\"Require\": null,\n\t\"Exclude\": null,\n\t\"Replace\": null,\n\t\"Retract\": null\n}\n"
+        );
+    }
+}
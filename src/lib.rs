@@ -1,11 +1,56 @@
-use std::ops::Deref;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-use nom::{error::Error, Err};
+extern crate alloc;
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::ops::{Deref, DerefMut};
+
+use nom::{error::Error, Err, Slice};
 use nom_locate::LocatedSpan;
 
+#[cfg(any(feature = "arbitrary", test))]
+pub mod arbitrary;
+mod comments;
+mod diff;
+mod directive_set;
+mod edit;
+mod error;
+#[cfg(feature = "std")]
+mod file;
+mod format;
+mod gosum;
+mod json;
+mod limits;
+mod merge;
+pub mod module;
+mod owned;
 mod parser;
+mod query;
+mod recover;
+mod stream;
+mod validate;
+pub mod version;
+
+pub use comments::{CommentOwner, CommentRef};
+pub use diff::{diff, Change, GoModDiff};
+pub use directive_set::DirectiveSet;
+pub use edit::{append_require, set_require_version, EditError};
+pub use error::{LocatedError, ParseError};
+#[cfg(feature = "std")]
+pub use file::{parse_gomod_file, FileError};
+pub use format::format_gomod;
+pub use gosum::{detect_and_parse, parse_gosum, Detected, GoSumEntry};
+pub use limits::{parse_gomod_limited, Limits};
+pub use merge::{merge, MergeError};
+pub use owned::{
+    ContextOwned, DirectiveOwned, GoModOwned, ReplaceSpecOwned, ReplacementOwned, RetractSpecOwned,
+};
+pub use query::{EffectiveVersion, GoModSummary, ModuleRole, NodeRef, Outdated, SingletonView};
+pub use recover::parse_gomod_recover;
+pub use stream::directives;
+pub use validate::{Diagnostic, DiagnosticKind, OrderingIssue, OrderingIssueKind};
 
-type Span<'a> = LocatedSpan<&'a str>;
+type Span<'a> = LocatedSpan<&'a str, ParseOptions>;
 
 #[derive(Debug)]
 pub enum Sundry<'a> {
@@ -14,91 +59,1091 @@ pub enum Sundry<'a> {
     EOF,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// An identifier or string token, see [the go.mod lexical elements](https://go.dev/ref/mod#go-mod-file-lexical).
+///
+/// Derefs to the resolved value, the variant records how the token was written.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Identifier<'a> {
+    /// Unquoted, e.g. `v1.0.0`
+    Bare(&'a str),
+    /// Raw string between backticks, e.g. `` `v1.0.0` ``
     Raw(&'a str),
+    /// Interpreted string between double quotes, with escape sequences resolved, e.g. `"v1.0.0"`
     Interpreted(String),
 }
 
+/// How an [`Identifier`] was written in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuoteStyle {
+    Bare,
+    Interpreted,
+    Raw,
+}
+
+/// The whitespace indenting a line, see [`Context::indent_char`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IndentKind {
+    Tab,
+    Space,
+    /// Both tabs and spaces
+    Mixed,
+}
+
+/// Whether `s` must be quoted to be written as a go.mod token, following Go's
+/// `modfile.MustQuote`: it's empty, or contains a space, a quote, `//`, `/*` or a
+/// non-printable character, or a bracket or comma alongside other characters.
+pub fn needs_quoting(s: &str) -> bool {
+    s.is_empty()
+        || s.contains("//")
+        || s.contains("/*")
+        || s.chars().any(|c| match c {
+            ' ' | '"' | '\'' | '`' => true,
+            '(' | ')' | '[' | ']' | '{' | '}' | ',' => s.len() > 1,
+            _ => c.is_control() || c.is_whitespace(),
+        })
+}
+
+impl Identifier<'_> {
+    pub fn quote_style(&self) -> QuoteStyle {
+        match self {
+            Self::Bare(_) => QuoteStyle::Bare,
+            Self::Raw(_) => QuoteStyle::Raw,
+            Self::Interpreted(_) => QuoteStyle::Interpreted,
+        }
+    }
+
+    /// Whether this looks like a file path rather than a module path, following Go's
+    /// `modfile.IsDirectoryPath`: `.`, `..`, or starting with `./`, `../`, `/`, their `\`
+    /// variants, or a drive letter like `C:`.
+    pub fn is_file_path(&self) -> bool {
+        let s: &str = self;
+        let b = s.as_bytes();
+        s == "."
+            || s == ".."
+            || ["./", "../", "/", ".\\", "..\\", "\\"]
+                .iter()
+                .any(|i| s.starts_with(i))
+            || (b.len() >= 2 && b[0].is_ascii_alphabetic() && b[1] == b':')
+    }
+}
+
+impl<'a> Identifier<'a> {
+    /// The resolved value, borrowing the parsed text unless it was an interpreted string.
+    /// How it was written is left to [`Identifier::quote_style`].
+    pub fn to_cow(&self) -> Cow<'a, str> {
+        match self {
+            Self::Bare(s) | Self::Raw(s) => Cow::Borrowed(s),
+            Self::Interpreted(s) => Cow::Owned(s.clone()),
+        }
+    }
+}
+
+impl<'a> From<Identifier<'a>> for Cow<'a, str> {
+    fn from(value: Identifier<'a>) -> Self {
+        match value {
+            Identifier::Bare(s) | Identifier::Raw(s) => Cow::Borrowed(s),
+            Identifier::Interpreted(s) => Cow::Owned(s),
+        }
+    }
+}
+
 impl Deref for Identifier<'_> {
     type Target = str;
 
     fn deref(&self) -> &Self::Target {
         match self {
-            Self::Raw(s) => s,
+            Self::Bare(s) | Self::Raw(s) => s,
             Self::Interpreted(s) => s.as_str(),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RetractSpec<'a> {
     Version(Identifier<'a>),
     Range((Identifier<'a>, Identifier<'a>)),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ReplaceSpec<'a> {
-    pub module_path: &'a str,
+    pub module_path: Identifier<'a>,
     pub version: Option<Identifier<'a>>,
+    /// Position of the `=>` token
+    pub arrow: Location,
     pub replacement: Replacement<'a>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Replacement<'a> {
     FilePath(Identifier<'a>),
-    Module((&'a str, Identifier<'a>)),
+    Module((Identifier<'a>, Identifier<'a>)),
+}
+
+impl ReplaceSpec<'_> {
+    /// Whether the replacement is a local directory, which shouldn't ship in a published module.
+    ///
+    /// Like Go, that's a target starting with `./`, `../`, `/` or a drive letter, see
+    /// [`Identifier::is_file_path`]. A versionless module path, e.g. `m => example.com/fork`,
+    /// parses as [`Replacement::FilePath`] but isn't local: Go rejects it.
+    pub fn is_local(&self) -> bool {
+        match &self.replacement {
+            Replacement::FilePath(path) | Replacement::Module((path, _)) => path.is_file_path(),
+        }
+    }
+
+    /// The directory or module path on the right of `=>`
+    pub fn target_path(&self) -> &str {
+        match &self.replacement {
+            Replacement::FilePath(path) | Replacement::Module((path, _)) => path,
+        }
+    }
+
+    /// The version on the right of `=>`, `None` for a directory
+    pub fn target_version(&self) -> Option<&str> {
+        match &self.replacement {
+            Replacement::FilePath(_) => None,
+            Replacement::Module((_, version)) => Some(version),
+        }
+    }
 }
 
 // comments on directive includes preceding-line comments and same-line comment
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Directive<'a> {
     Module {
-        module_path: &'a str,
+        module_path: Identifier<'a>,
     },
     Go {
         version: Identifier<'a>,
     },
     Require {
-        specs: Vec<Context<'a, (&'a str, Identifier<'a>)>>,
+        specs: Vec<Context<'a, (Identifier<'a>, Identifier<'a>)>>,
+        /// Index in `specs` of each spec starting a new group, i.e. preceded by blank lines
+        /// inside the block. A blank line right after `(` doesn't start a group.
+        group_starts: Vec<usize>,
+        block: Option<Block<'a>>,
     },
     Toolchain {
         name: Identifier<'a>,
     },
     Godebug {
         specs: Vec<Context<'a, (&'a str, &'a str)>>,
+        block: Option<Block<'a>>,
     },
     Replace {
         specs: Vec<Context<'a, ReplaceSpec<'a>>>,
+        block: Option<Block<'a>>,
     },
     Exclude {
-        specs: Vec<Context<'a, (&'a str, Identifier<'a>)>>,
+        specs: Vec<Context<'a, (Identifier<'a>, Identifier<'a>)>>,
+        block: Option<Block<'a>>,
     },
     Retract {
         specs: Vec<Context<'a, RetractSpec<'a>>>,
+        block: Option<Block<'a>>,
+    },
+    /// A go.work `use` directive, only parsed when allowed by `ParseOptions::allowed_directives`
+    Use {
+        specs: Vec<Context<'a, Identifier<'a>>>,
+        block: Option<Block<'a>>,
     },
+    /// Directories the go command skips when matching package patterns, e.g. `./node_modules`
+    Ignore {
+        specs: Vec<Context<'a, Identifier<'a>>>,
+        block: Option<Block<'a>>,
+    },
+}
+
+/// The comments on the lines of a block directive and where it closes, see [`Directive::block`].
+///
+/// They're part of `Context::comments` as well, which holds the comments before the directive
+/// first, see [`Context::leading_comments`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Block<'a> {
+    /// Comment on the `(` line
+    pub open_comment: Option<&'a str>,
+    /// Comment lines after the last spec
+    pub end_comments: Vec<&'a str>,
+    /// Comment on the `)` line, or after `()` for an empty block
+    pub close_comment: Option<&'a str>,
+    /// Location of the `)`, new specs go on the lines before it
+    pub close_paren: Location,
+}
+
+impl Block<'_> {
+    fn comment_count(&self) -> usize {
+        usize::from(self.open_comment.is_some())
+            + self.end_comments.len()
+            + usize::from(self.close_comment.is_some())
+    }
+}
+
+/// Discriminant of a [`Directive`], see [`Directive::kind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum DirectiveKind {
+    Module,
+    Go,
+    Require,
+    Toolchain,
+    Godebug,
+    Replace,
+    Exclude,
+    Retract,
+    Use,
+    Ignore,
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+impl DirectiveKind {
+    /// The Go version a go.mod needs to use this directive, as `(major, minor)`
+    pub fn min_go(self) -> Option<(u16, u16)> {
+        match self {
+            Self::Toolchain => Some((1, 21)),
+            Self::Godebug => Some((1, 23)),
+            Self::Ignore => Some((1, 25)),
+            _ => None,
+        }
+    }
+
+    /// The keyword starting the directive, e.g. `require`
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Self::Module => "module",
+            Self::Go => "go",
+            Self::Require => "require",
+            Self::Toolchain => "toolchain",
+            Self::Godebug => "godebug",
+            Self::Replace => "replace",
+            Self::Exclude => "exclude",
+            Self::Retract => "retract",
+            Self::Use => "use",
+            Self::Ignore => "ignore",
+        }
+    }
+}
+
+impl<'a> Directive<'a> {
+    /// `None` unless this is a block directive, written with parentheses. `module` blocks aren't
+    /// covered, their comments are only in `Context::comments`.
+    pub fn block(&self) -> Option<&Block<'_>> {
+        match self {
+            Self::Require { block, .. }
+            | Self::Godebug { block, .. }
+            | Self::Replace { block, .. }
+            | Self::Exclude { block, .. }
+            | Self::Retract { block, .. }
+            | Self::Use { block, .. }
+            | Self::Ignore { block, .. } => block.as_ref(),
+            Self::Module { .. } | Self::Go { .. } | Self::Toolchain { .. } => None,
+        }
+    }
+
+    /// Location of the `)` closing a block directive, `None` for a single-line one
+    pub fn close_paren(&self) -> Option<&Location> {
+        self.block().map(|i| &i.close_paren)
+    }
+
+    pub fn kind(&self) -> DirectiveKind {
+        match self {
+            Self::Module { .. } => DirectiveKind::Module,
+            Self::Go { .. } => DirectiveKind::Go,
+            Self::Require { .. } => DirectiveKind::Require,
+            Self::Toolchain { .. } => DirectiveKind::Toolchain,
+            Self::Godebug { .. } => DirectiveKind::Godebug,
+            Self::Replace { .. } => DirectiveKind::Replace,
+            Self::Exclude { .. } => DirectiveKind::Exclude,
+            Self::Retract { .. } => DirectiveKind::Retract,
+            Self::Use { .. } => DirectiveKind::Use,
+            Self::Ignore { .. } => DirectiveKind::Ignore,
+        }
+    }
+
+    /// The value or specs of the directive, without its block
+    pub fn view(&self) -> DirectiveView<'_, 'a> {
+        match self {
+            Self::Module { module_path } => DirectiveView::Module(module_path),
+            Self::Go { version } => DirectiveView::Go(version),
+            Self::Toolchain { name } => DirectiveView::Toolchain(name),
+            Self::Godebug { specs, .. } => DirectiveView::Godebug(specs),
+            Self::Require { specs, .. } => DirectiveView::Require(specs),
+            Self::Exclude { specs, .. } => DirectiveView::Exclude(specs),
+            Self::Replace { specs, .. } => DirectiveView::Replace(specs),
+            Self::Retract { specs, .. } => DirectiveView::Retract(specs),
+            Self::Use { specs, .. } => DirectiveView::Use(specs),
+            Self::Ignore { specs, .. } => DirectiveView::Ignore(specs),
+        }
+    }
+}
+
+/// A directive reduced to its value or specs, see [`Directive::view`]. Single-line and block
+/// directives look the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectiveView<'g, 'a> {
+    Module(&'g Identifier<'a>),
+    Go(&'g Identifier<'a>),
+    Toolchain(&'g Identifier<'a>),
+    Godebug(&'g [Context<'a, (&'a str, &'a str)>]),
+    Require(&'g [Context<'a, (Identifier<'a>, Identifier<'a>)>]),
+    Exclude(&'g [Context<'a, (Identifier<'a>, Identifier<'a>)>]),
+    Replace(&'g [Context<'a, ReplaceSpec<'a>>]),
+    Retract(&'g [Context<'a, RetractSpec<'a>>]),
+    Use(&'g [Context<'a, Identifier<'a>>]),
+    Ignore(&'g [Context<'a, Identifier<'a>>]),
+}
+
+/// Options accepted by [`parse_gomod_with_options`] and [`parse_with_options`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    /// Report `Location::line` and `Location::column` starting from 0 (as LSP does) instead of 1.
+    /// `Location::offset` is a byte offset and is always 0-based.
+    pub zero_based_positions: bool,
+    /// Count `Location::column` in characters instead of bytes
+    pub utf8_columns: bool,
+    /// Count `Location::column` in display columns, in characters with a tab advancing to
+    /// the next multiple of this width. Takes precedence over `utf8_columns`.
+    pub tab_width: Option<usize>,
+    /// Keep the text of each directive in `Context::source`
+    pub preserve_source: bool,
+    /// Directives accepted, others fail with an `Err::Failure` located at their keyword.
+    /// Defaults to [`DirectiveSet::GO_MOD`].
+    pub allowed_directives: DirectiveSet,
+    /// Also take `#` as starting a line comment wherever `//` may, but not inside a token, for
+    /// go.mod-like files written by other tools. Off by default, as Go rejects `#`. The text
+    /// after it is kept as the comment, so writing the tree back uses `//`.
+    pub hash_comments: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub struct Location {
+    /// 1-based line number, 0-based with `ParseOptions::zero_based_positions`.
+    ///
+    /// Only a text of more than 4 GiB can overflow it, see [`parse_gomod_limited`] to bound the
+    /// input length.
     pub line: u32,
+    /// 1-based column within the line, 0-based with `ParseOptions::zero_based_positions`.
+    /// Counted in bytes, or as set by `ParseOptions::utf8_columns` and `ParseOptions::tab_width`.
+    pub column: usize,
+    /// 0-based byte offset into the parsed text
     pub offset: usize,
 }
 
 pub type Range = (Location, Location);
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Context<'a, T: 'a> {
     pub range: Range,
+    /// The text covered by `range`, kept for directives parsed with
+    /// `ParseOptions::preserve_source` so untouched ones can be written back verbatim.
+    /// `None` for specs.
+    pub source: Option<&'a str>,
+    /// Each comment is the text after `//` byte for byte, spaces included, up to the `\n` or
+    /// `\r\n` ending its line
     pub comments: Vec<&'a str>,
+    /// Whether the last of `comments` follows the tokens on the line of this spec, or of a
+    /// single-line `module`, `go` or `toolchain` directive, like Go's suffix comments. The
+    /// others are on lines of their own before it.
+    pub suffix_comment: bool,
     pub value: T,
 }
 
-pub type GoMod<'a> = Vec<Context<'a, Directive<'a>>>;
+impl<T> Context<'_, T> {
+    /// The text of this directive or spec, `text` being the parsed text.
+    ///
+    /// `None` if the range doesn't fit `text` or doesn't fall on char boundaries, e.g. when
+    /// passed another text.
+    pub fn source<'s>(&self, text: &'s str) -> Option<&'s str> {
+        text.get(self.range.0.offset..self.range.1.offset)
+    }
+
+    /// The whitespace before this directive or spec on its first line, e.g. `"\t"` for a spec
+    /// of a block indented with a tab. `text` must be the parsed text.
+    ///
+    /// `None` if the range doesn't fit `text`.
+    pub fn indent<'s>(&self, text: &'s str) -> Option<&'s str> {
+        let start = self.range.0.offset;
+        let line_start = text.get(..start)?.rfind('\n').map_or(0, |i| i + 1);
+        Some(&text[line_start..start])
+    }
+
+    /// Whether [`Context::indent`] is made of tabs, spaces or both, e.g. to flag block specs not
+    /// indented with a tab as gofmt does. `text` must be the parsed text.
+    ///
+    /// `None` if not indented, or not first on its line like the spec of a single-line directive.
+    pub fn indent_char(&self, text: &str) -> Option<IndentKind> {
+        let indent = self.indent(text)?;
+        if !indent.bytes().all(|c| c == b'\t' || c == b' ') {
+            return None;
+        }
+        match (indent.contains('\t'), indent.contains(' ')) {
+            (true, false) => Some(IndentKind::Tab),
+            (false, true) => Some(IndentKind::Space),
+            (true, true) => Some(IndentKind::Mixed),
+            (false, false) => None,
+        }
+    }
+
+    /// The whitespace between the tokens on the first line of this directive or spec, and
+    /// before its comment there, in order. `text` and `options` must be the ones it was parsed
+    /// with, `options` telling whether `#` starts a comment.
+    ///
+    /// Together with [`Context::indent`], this lets a rewriter keep the spacing of the lines it
+    /// changes, e.g. spec columns aligned with spaces.
+    pub fn separators<'s>(&self, text: &'s str, options: ParseOptions) -> Vec<&'s str> {
+        let Some(line) = text.get(self.range.0.offset..) else {
+            return Vec::new();
+        };
+        let line = &line[..line.find('\n').unwrap_or(line.len())];
+        let mut ret = Vec::new();
+        let mut run = None;
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                ' ' | '\t' | '\r' => {
+                    run.get_or_insert(i);
+                    continue;
+                }
+                '"' | '`' => {
+                    while let Some((_, d)) = chars.next() {
+                        match d {
+                            '\\' if c == '"' => {
+                                chars.next();
+                            }
+                            d if d == c => break,
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+            let after_space = run.take().map(|start| ret.push(&line[start..i])).is_some();
+            // like the parser, `#` only starts a comment at the start of a token
+            if line[i..].starts_with("//") || (options.hash_comments && after_space && c == '#') {
+                break;
+            }
+        }
+        ret
+    }
+
+    /// Location of the `//`, or `#` with `ParseOptions::hash_comments`, of the comment on the
+    /// first line of this directive or spec, so a formatter can keep it aligned. `text` and
+    /// `options` must be the ones it was parsed with.
+    ///
+    /// `None` without such a comment, or when passed another text.
+    pub fn inline_comment(&self, text: &str, options: ParseOptions) -> Option<Location> {
+        let start = self.range.0.offset;
+        let line_end = text
+            .get(start..)?
+            .find('\n')
+            .map_or(text.len(), |i| start + i);
+        let offset = self.comments.iter().find_map(|comment| {
+            let after = (comment.as_ptr() as usize).checked_sub(text.as_ptr() as usize)?;
+            let hash = options.hash_comments && text.get(..after)?.ends_with('#');
+            let offset = after.checked_sub(if hash { 1 } else { 2 })?;
+            let fits = after + comment.len() <= text.len();
+            (fits && (start..line_end).contains(&offset)).then_some(offset)
+        })?;
+        Some(parser::location(
+            &Span::new_extra(text, options).slice(offset..),
+        ))
+    }
+}
+
+impl<'a> Context<'a, Directive<'a>> {
+    /// The comments before the directive, and the one on its line for `module`, `go` and
+    /// `toolchain`. Those on the lines of a block are in [`Directive::block`].
+    pub fn leading_comments(&self) -> &[&'a str] {
+        let block = self.value.block().map_or(0, Block::comment_count);
+        &self.comments[..self.comments.len() - block]
+    }
+}
+
+/// Directives of a go.mod file, in source order.
+///
+/// The order is part of the API: directives are in the order they appear in the text, and the
+/// specs of a block in the order of their lines, so a tree can be re-emitted as it was read.
+///
+/// Derefs to the underlying `Vec`, so it can be iterated and indexed directly.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct GoMod<'a> {
+    /// Comments at the top of the file separated from the first directive by a blank line, e.g.
+    /// a license banner. Comments right above the first directive belong to it instead.
+    pub header_comments: Vec<&'a str>,
+    pub directives: Vec<Context<'a, Directive<'a>>>,
+    /// Comments after the last directive. Comments before a directive belong to it instead.
+    pub trailing_comments: Vec<&'a str>,
+    /// Position right after the last directive and the comments following it, where a new
+    /// directive can be appended. Left to its default when built from a `Vec`.
+    pub end: Location,
+    /// Whether the parsed text ends with a newline, i.e. whether a directive appended at `end`
+    /// must be preceded by one
+    pub ends_with_newline: bool,
+}
+
+impl<'a> Deref for GoMod<'a> {
+    type Target = Vec<Context<'a, Directive<'a>>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.directives
+    }
+}
+
+impl DerefMut for GoMod<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.directives
+    }
+}
+
+impl<'a> From<Vec<Context<'a, Directive<'a>>>> for GoMod<'a> {
+    fn from(directives: Vec<Context<'a, Directive<'a>>>) -> Self {
+        Self {
+            directives,
+            ..Default::default()
+        }
+    }
+}
+
+impl<'a> IntoIterator for GoMod<'a> {
+    type Item = Context<'a, Directive<'a>>;
+    type IntoIter = alloc::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.directives.into_iter()
+    }
+}
+
+/// Iterate the [`DirectiveView`] of each directive. Use `iter()` to get their
+/// ranges and comments as well.
+impl<'g, 'a> IntoIterator for &'g GoMod<'a> {
+    type Item = DirectiveView<'g, 'a>;
+    type IntoIter = core::iter::Map<
+        core::slice::Iter<'g, Context<'a, Directive<'a>>>,
+        fn(&'g Context<'a, Directive<'a>>) -> DirectiveView<'g, 'a>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.directives.iter().map(|i| i.value.view())
+    }
+}
 
 /// Return an error indicating (line, offset)
-pub fn parse_gomod(text: &str) -> Result<GoMod, Err<Error<(u32, usize)>>> {
-    let (_, ret) = parser::parse_gomod(Span::new(text))
-        .map_err(|e| e.map_input(|i| (i.location_line(), i.location_offset())))?;
+///
+/// Directives and specs are returned in source order, see [`GoMod`].
+///
+/// `/* */` comments aren't part of the go.mod grammar, they fail with an `Err::Failure`
+/// located right at the `/*`.
+pub fn parse_gomod(text: &str) -> Result<GoMod<'_>, Err<Error<(u32, usize)>>> {
+    parse_gomod_with_options(text, ParseOptions::default())
+}
+
+/// Same as [`parse_gomod`], with positions reported according to `options`.
+/// The line in the returned error follows `options` as well.
+pub fn parse_gomod_with_options(
+    text: &str,
+    options: ParseOptions,
+) -> Result<GoMod<'_>, Err<Error<(u32, usize)>>> {
+    let (_, ret) = parser::parse_gomod(Span::new_extra(text, options)).map_err(|e| {
+        e.map_input(|i| {
+            let loc = parser::location(&i);
+            (loc.line, loc.offset)
+        })
+    })?;
+    Ok(ret)
+}
+
+/// Same as [`parse_gomod_with_options`], failing with a [`ParseError`].
+///
+/// Directives missing from `options.allowed_directives` fail with
+/// [`ParseError::DirectiveNotAllowed`], e.g. `require` when parsing a go.work file with
+/// [`DirectiveSet::GO_WORK`].
+pub fn parse_with_options(text: &str, options: ParseOptions) -> Result<GoMod<'_>, ParseError> {
+    let input = Span::new_extra(text, options);
+    let (_, ret) = parser::parse_gomod(input).map_err(|e| ParseError::syntax(e, input))?;
     Ok(ret)
 }
+
+/// Same as [`parse_with_options`] with the default options, for text not yet known to be UTF-8.
+///
+/// Bytes that aren't valid UTF-8 fail with [`ParseError::InvalidUtf8`], located at the first
+/// invalid sequence.
+pub fn parse_gomod_bytes(bytes: &[u8]) -> Result<GoMod<'_>, ParseError> {
+    let text = core::str::from_utf8(bytes).map_err(|e| {
+        let offset = e.valid_up_to();
+        ParseError::InvalidUtf8 {
+            line: bytes[..offset].iter().filter(|&&c| c == b'\n').count() as u32 + 1,
+            offset,
+            len: e.error_len().unwrap_or(bytes.len() - offset),
+        }
+    })?;
+    parse_with_options(text, ParseOptions::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{
+        needs_quoting, parse_gomod, parse_gomod_bytes, parse_gomod_with_options,
+        parse_with_options, Block, Directive, DirectiveKind, DirectiveView, IndentKind, Location,
+        ParseError, ParseOptions,
+    };
+
+    #[test]
+    fn test_positions() {
+        let s = "module example.com/m\n\ngo 1.21\n\trequire example.com/x v1.0.0\n";
+        let gomod = parse_gomod(s).unwrap();
+        assert_eq!(
+            gomod[2].range.0,
+            Location {
+                line: 4,
+                column: 2,
+                offset: 31
+            }
+        );
+        let gomod = parse_gomod_with_options(
+            s,
+            ParseOptions {
+                zero_based_positions: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            gomod[2].range.0,
+            Location {
+                line: 3,
+                column: 1,
+                offset: 31
+            }
+        );
+        let err = |options| match parse_gomod_with_options("\n\nfoo", options) {
+            Err(nom::Err::Error(e)) => e.input,
+            _ => unreachable!(),
+        };
+        // blank lines before the first directive are skipped with the header
+        assert_eq!(err(ParseOptions::default()), (3, 2));
+        assert_eq!(
+            err(ParseOptions {
+                zero_based_positions: true,
+                ..Default::default()
+            }),
+            (2, 2)
+        );
+    }
+
+    #[test]
+    fn test_utf8_columns() {
+        let s = "replace example.com/a vé => ../a\n";
+        let arrow = |utf8_columns| {
+            let gomod = parse_gomod_with_options(
+                s,
+                ParseOptions {
+                    utf8_columns,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            match &gomod[0].value {
+                Directive::Replace { specs, .. } => specs[0].value.arrow.clone(),
+                _ => unreachable!(),
+            }
+        };
+        assert_eq!(
+            arrow(false),
+            Location {
+                line: 1,
+                column: 27,
+                offset: 26
+            }
+        );
+        assert_eq!(
+            arrow(true),
+            Location {
+                line: 1,
+                column: 26,
+                offset: 26
+            }
+        );
+    }
+
+    #[test]
+    fn test_tab_width() {
+        let s = "module example.com/m\n\nrequire (\n\texample.com/a v1.0.0\n  \texample.com/b v1.0.0\n\t\texample.com/c v1.0.0\n)\n";
+        let gomod = parse_gomod_with_options(
+            s,
+            ParseOptions {
+                tab_width: Some(4),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let Directive::Require { specs, .. } = &gomod[1].value else {
+            unreachable!()
+        };
+        let columns: Vec<_> = specs.iter().map(|i| i.range.0.column).collect();
+        assert_eq!(columns, vec![5, 5, 9]);
+        let gomod = parse_gomod(s).unwrap();
+        let Directive::Require { specs, .. } = &gomod[1].value else {
+            unreachable!()
+        };
+        let columns: Vec<_> = specs.iter().map(|i| i.range.0.column).collect();
+        assert_eq!(columns, vec![2, 4, 3]);
+    }
+
+    #[test]
+    fn test_hash_comments() {
+        let s = "# banner\n\n# about m\nmodule example.com/m # main\n\nrequire (\n\t# pinned\n\texample.com/a v1.0.0#1 # indirect\n\texample.com/b # missing\n)\n";
+        let options = ParseOptions {
+            hash_comments: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            parse_with_options(s, options),
+            Err(ParseError::MissingVersion { line: 9, .. })
+        ));
+        let s = s.replace("example.com/b # missing", "example.com/b v1.0.0 // c");
+        let gomod = parse_with_options(&s, options).unwrap();
+        assert_eq!(gomod.header_comments, [" banner"]);
+        assert_eq!(gomod[0].comments, [" about m", " main"]);
+        let Directive::Require { specs, .. } = &gomod[1].value else {
+            unreachable!()
+        };
+        assert_eq!(&*specs[0].value.1, "v1.0.0#1");
+        assert_eq!(specs[0].comments, [" pinned", " indirect"]);
+        assert_eq!(specs[1].comments, [" c"]);
+        assert_eq!(specs[0].inline_comment(&s, options).unwrap().column, 25);
+        assert_eq!(specs[0].separators(&s, options), [" ", " "]);
+        assert_eq!(specs[0].separators(&s, Default::default()), [" ", " ", " "]);
+        assert_eq!(specs[1].inline_comment(&s, options).unwrap().column, 23);
+        assert!(matches!(
+            parse_with_options(&s, ParseOptions::default()),
+            Err(ParseError::Syntax { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_source() {
+        let s = "module example.com/m\n\nrequire (\n\texample.com/a v1.0.0 // é\n)\n";
+        let gomod = parse_gomod(s).unwrap();
+        assert_eq!(
+            gomod[1].source(s),
+            Some("require (\n\texample.com/a v1.0.0 // é\n)\n")
+        );
+        let spec = gomod.requires().next().unwrap();
+        assert_eq!(spec.source(s), Some("example.com/a v1.0.0 // é\n"));
+        assert_eq!(spec.source("module m\n"), None);
+        assert_eq!(spec.source(&s[..48]), None);
+    }
+
+    #[test]
+    fn test_preserve_source() {
+        let s = "// m\nmodule   example.com/m\n\nrequire (\n\texample.com/a v1.0.0\n) // end\n";
+        let gomod = parse_gomod_with_options(
+            s,
+            ParseOptions {
+                preserve_source: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(gomod[0].source, Some("module   example.com/m\n"));
+        assert_eq!(
+            gomod[1].source,
+            Some("require (\n\texample.com/a v1.0.0\n) // end\n")
+        );
+        assert_eq!(gomod.requires().next().unwrap().source, None);
+        assert_eq!(parse_gomod(s).unwrap()[0].source, None);
+    }
+
+    #[test]
+    fn test_block() {
+        let s = "// about\nrequire ( // open\n\texample.com/a v1.0.0\n\t// end\n) // close\nexclude () // empty\ngo 1.21 // line\n";
+        let gomod = parse_gomod(s).unwrap();
+        assert_eq!(
+            gomod[0].value.block(),
+            Some(&Block {
+                open_comment: Some(" open"),
+                end_comments: vec![" end"],
+                close_comment: Some(" close"),
+                close_paren: Location {
+                    line: 5,
+                    column: 1,
+                    offset: 57
+                },
+            })
+        );
+        assert_eq!(gomod[0].leading_comments(), [" about"]);
+        assert_eq!(
+            gomod[1].value.block().unwrap().close_comment,
+            Some(" empty")
+        );
+        assert!(gomod[1].leading_comments().is_empty());
+        assert_eq!(
+            gomod[1].value.close_paren(),
+            Some(&Location {
+                line: 6,
+                column: 10,
+                offset: 77
+            })
+        );
+        assert_eq!(&s[77..78], ")");
+        assert_eq!(gomod[2].value.block(), None);
+        assert_eq!(gomod[2].value.close_paren(), None);
+        assert_eq!(gomod[2].leading_comments(), [" line"]);
+    }
+
+    #[test]
+    fn test_comment_only_block() {
+        for keyword in ["require", "replace", "exclude"] {
+            let s = format!("module m\n\n{keyword} (\n\t// example.com/a => ../a\n\t// off\n)\n");
+            let gomod = parse_gomod(&s).unwrap();
+            let empty = match &gomod[1].value {
+                Directive::Require { specs, .. } | Directive::Exclude { specs, .. } => {
+                    specs.is_empty()
+                }
+                Directive::Replace { specs, .. } => specs.is_empty(),
+                _ => false,
+            };
+            assert!(empty, "{keyword}");
+            assert_eq!(gomod[1].comments, [" example.com/a => ../a", " off"]);
+            assert!(gomod[1].leading_comments().is_empty());
+            assert_eq!(
+                gomod[1].value.block().unwrap().end_comments,
+                [" example.com/a => ../a", " off"]
+            );
+        }
+    }
+
+    #[test]
+    fn test_needs_quoting() {
+        for s in ["v1.0.0", "example.com/m", "../a", "(", ",", "a=b", "é"] {
+            assert!(!needs_quoting(s), "{s:?}");
+        }
+        for s in [
+            "", "a b", "a\tb", "a\"b", "it's", "a`b", "a//b", "a/*b", "(a)", "a,b",
+        ] {
+            assert!(needs_quoting(s), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn test_replace_target() {
+        let gomod = parse_gomod(
+            "replace example.com/a => ../a\nreplace example.com/b => example.com/fork/b v1.0.1\nreplace example.com/c => example.com/fork/c\nreplace example.com/d => C:\\src\\d\n",
+        )
+        .unwrap();
+        let specs: Vec<_> = gomod.replaces().map(|i| &i.value).collect();
+        assert!(specs[0].is_local());
+        assert_eq!(specs[0].target_path(), "../a");
+        assert_eq!(specs[0].target_version(), None);
+        assert!(!specs[1].is_local());
+        assert_eq!(specs[1].target_path(), "example.com/fork/b");
+        assert_eq!(specs[1].target_version(), Some("v1.0.1"));
+        assert!(!specs[2].is_local());
+        assert_eq!(specs[2].target_path(), "example.com/fork/c");
+        assert_eq!(specs[2].target_version(), None);
+        assert!(specs[3].is_local());
+        assert_eq!(
+            gomod
+                .local_replaces()
+                .map(|i| &*i.value.module_path)
+                .collect::<Vec<_>>(),
+            ["example.com/a", "example.com/d"]
+        );
+    }
+
+    #[test]
+    fn test_hash_clone() {
+        let gomod = parse_gomod(
+            "require example.com/a v1.0.0\nrequire example.com/b v1.0.0\nrequire example.com/a v1.0.0\n",
+        )
+        .unwrap();
+        let values: HashSet<_> = gomod.requires().map(|i| i.value.clone()).collect();
+        assert_eq!(values.len(), 2);
+        let copy = gomod.clone();
+        assert_eq!(copy, gomod);
+    }
+
+    #[test]
+    fn test_end() {
+        let s = "module example.com/m\n\ngo 1.21\n// trailing\n\n";
+        let gomod = parse_gomod(s).unwrap();
+        assert_eq!(
+            gomod.end,
+            Location {
+                line: 6,
+                column: 1,
+                offset: s.len()
+            }
+        );
+        assert!(gomod.ends_with_newline);
+        let gomod = parse_gomod("module example.com/m\n// trailing").unwrap();
+        assert_eq!(gomod.end.offset, 32);
+        assert!(!gomod.ends_with_newline);
+    }
+
+    #[test]
+    fn test_kind() {
+        let gomod = parse_gomod(
+            "module example.com/m\nrequire example.com/a v1.0.0\nrequire example.com/b v1.0.0\n",
+        )
+        .unwrap();
+        assert_eq!(
+            gomod
+                .iter()
+                .filter(|d| d.value.kind() == DirectiveKind::Require)
+                .count(),
+            2
+        );
+        assert_eq!(gomod[0].value.kind().keyword(), "module");
+    }
+
+    #[test]
+    fn test_bytes() {
+        let s = "module example.com/m\n\ngo 1.21\n";
+        assert_eq!(
+            parse_gomod_bytes(s.as_bytes()).unwrap(),
+            parse_gomod(s).unwrap()
+        );
+        assert_eq!(
+            parse_gomod_bytes(b"module example.com/m\n// caf\xe9\n"),
+            Err(ParseError::InvalidUtf8 {
+                line: 2,
+                offset: 27,
+                len: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_identifier_cow() {
+        use std::borrow::Cow;
+
+        let gomod = parse_gomod("module `example.com/m`\n\ngo \"1.21\"\n").unwrap();
+        let (Directive::Module { module_path }, Directive::Go { version }) =
+            (&gomod[0].value, &gomod[1].value)
+        else {
+            unreachable!()
+        };
+        assert!(matches!(
+            module_path.to_cow(),
+            Cow::Borrowed("example.com/m")
+        ));
+        assert!(matches!(version.to_cow(), Cow::Owned(s) if s == "1.21"));
+        let version: Cow<str> = version.clone().into();
+        assert_eq!(version, "1.21");
+    }
+
+    #[test]
+    fn test_inline_comment() {
+        let text = "// about\nmodule example.com/m // main\n\nrequire (\n\texample.com/a v1.0.0    // a\n\t// about b\n\texample.com/b v1.0.0\n) // close\n";
+        let gomod = parse_gomod(text).unwrap();
+        let loc = gomod[0].inline_comment(text, Default::default()).unwrap();
+        assert_eq!(
+            (loc.line, loc.column, &text[loc.offset..loc.offset + 7]),
+            (2, 22, "// main")
+        );
+        assert_eq!(gomod[1].inline_comment(text, Default::default()), None);
+        let Directive::Require { specs, .. } = &gomod[1].value else {
+            unreachable!()
+        };
+        let options = ParseOptions {
+            tab_width: Some(4),
+            ..Default::default()
+        };
+        let loc = specs[0].inline_comment(text, options).unwrap();
+        assert_eq!(
+            (loc.line, loc.column, &text[loc.offset..loc.offset + 4]),
+            (5, 29, "// a")
+        );
+        assert_eq!(specs[1].inline_comment(text, options), None);
+        assert_eq!(specs[0].inline_comment("other", options), None);
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let text = "module  example.com/m\n\nrequire (\n\texample.com/a   v1.0.0\t// a\n    \"example.com/b\" `v1 .0`\n)\nretract [v1.0.0, v1.1.0]\n";
+        let gomod = parse_gomod(text).unwrap();
+        assert_eq!(gomod[0].indent(text), Some(""));
+        assert_eq!(gomod[0].separators(text, Default::default()), ["  "]);
+        let Directive::Require { specs, .. } = &gomod[1].value else {
+            unreachable!()
+        };
+        assert_eq!(specs[0].indent(text), Some("\t"));
+        assert_eq!(specs[0].separators(text, Default::default()), ["   ", "\t"]);
+        assert_eq!(specs[1].indent(text), Some("    "));
+        assert_eq!(specs[1].separators(text, Default::default()), [" "]);
+        assert_eq!(gomod[2].separators(text, Default::default()), [" ", " "]);
+        assert_eq!(specs[0].indent(""), None);
+        assert_eq!(specs[0].indent_char(text), Some(IndentKind::Tab));
+        assert_eq!(specs[1].indent_char(text), Some(IndentKind::Space));
+        assert_eq!(gomod[0].indent_char(text), None);
+        let text = "require (\n \texample.com/a v1.0.0\n)\nrequire example.com/b v1.0.0\n";
+        let gomod = parse_gomod(text).unwrap();
+        let specs = gomod.requires().collect::<Vec<_>>();
+        assert_eq!(specs[0].indent_char(text), Some(IndentKind::Mixed));
+        assert_eq!(specs[1].indent_char(text), None);
+    }
+
+    #[test]
+    fn test_directive_view() {
+        let gomod = parse_gomod(
+            "module example.com/m\n\nrequire example.com/a v1.0.0\nrequire (\n\texample.com/b v1.0.0\n\texample.com/c v1.0.0\n)\n",
+        )
+        .unwrap();
+        let mut requires = 0;
+        for directive in &gomod {
+            match directive {
+                DirectiveView::Module(path) => assert_eq!(&**path, "example.com/m"),
+                DirectiveView::Require(specs) => requires += specs.len(),
+                _ => unreachable!(),
+            }
+        }
+        assert_eq!(requires, 3);
+    }
+
+    #[test]
+    fn test_source_order() {
+        let gomod = parse_gomod(
+            "require z.com/z v1.0.0
+module example.com/m
+exclude (
+	z.com/z v0.1.0
+	a.com/a v0.1.0
+)
+require a.com/a v1.0.0
+go 1.21
+require (
+	m.com/m v1.0.0
+	b.com/b v1.0.0
+)
+",
+        )
+        .unwrap();
+        assert_eq!(
+            gomod.iter().map(|i| i.value.kind()).collect::<Vec<_>>(),
+            vec![
+                DirectiveKind::Require,
+                DirectiveKind::Module,
+                DirectiveKind::Exclude,
+                DirectiveKind::Require,
+                DirectiveKind::Go,
+                DirectiveKind::Require,
+            ]
+        );
+        let paths = gomod
+            .iter()
+            .flat_map(|i| match &i.value {
+                Directive::Require { specs, .. } => specs.iter().map(|i| &*i.value.0).collect(),
+                Directive::Exclude { specs, .. } => specs.iter().map(|i| &*i.value.0).collect(),
+                _ => vec![],
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec!["z.com/z", "z.com/z", "a.com/a", "a.com/a", "m.com/m", "b.com/b"]
+        );
+        assert!(gomod
+            .windows(2)
+            .all(|i| i[0].range.1.offset <= i[1].range.0.offset));
+    }
+}
@@ -0,0 +1,161 @@
+use alloc::vec::Vec;
+
+use nom::Err;
+
+use crate::{parser, Directive, GoMod, ParseError, ParseOptions, Span};
+
+/// Caps enforced by [`parse_gomod_limited`], for accepting untrusted input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum length of the text in bytes
+    pub max_input_len: usize,
+    /// Maximum number of directives in the file
+    pub max_directives: usize,
+    /// Maximum number of specs in a single (block) directive
+    pub max_specs_per_block: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_input_len: 1 << 20,
+            max_directives: 10_000,
+            max_specs_per_block: 10_000,
+        }
+    }
+}
+
+fn spec_count(directive: &Directive) -> usize {
+    match directive {
+        Directive::Module { .. } | Directive::Go { .. } | Directive::Toolchain { .. } => 1,
+        Directive::Require { specs, .. } | Directive::Exclude { specs, .. } => specs.len(),
+        Directive::Godebug { specs, .. } => specs.len(),
+        Directive::Replace { specs, .. } => specs.len(),
+        Directive::Retract { specs, .. } => specs.len(),
+        Directive::Use { specs, .. } | Directive::Ignore { specs, .. } => specs.len(),
+    }
+}
+
+/// Same as [`crate::parse_gomod`], but fails with a [`ParseError`] as soon as `limits` is exceeded.
+///
+/// The input length is checked before parsing, and the other limits right after each directive
+/// is parsed, so the work done is bounded by `limits.max_input_len`.
+pub fn parse_gomod_limited(text: &str, limits: Limits) -> Result<GoMod<'_>, ParseError> {
+    if text.len() > limits.max_input_len {
+        return Err(ParseError::InputTooLong {
+            len: text.len(),
+            max: limits.max_input_len,
+        });
+    }
+    let start = Span::new_extra(text, ParseOptions::default());
+    let (mut input, header) =
+        parser::parse_header(start).map_err(|e| ParseError::syntax(e, start))?;
+    let mut ret = Vec::new();
+    loop {
+        let (rest, directive) = match parser::parse_directive(input) {
+            Ok(ok) => ok,
+            Err(Err::Error(_)) if !ret.is_empty() => break,
+            Err(e) => return Err(ParseError::syntax(e, input)),
+        };
+        let (start, end) = &directive.range;
+        if ret.len() == limits.max_directives {
+            return Err(ParseError::TooManyDirectives {
+                line: start.line,
+                offset: start.offset,
+                len: end.offset - start.offset,
+                max: limits.max_directives,
+            });
+        }
+        if spec_count(&directive.value) > limits.max_specs_per_block {
+            return Err(ParseError::TooManySpecs {
+                line: start.line,
+                offset: start.offset,
+                len: end.offset - start.offset,
+                max: limits.max_specs_per_block,
+            });
+        }
+        ret.push(directive);
+        input = rest;
+    }
+    let (input, trailing) =
+        parser::parse_multiline_comments(input).map_err(|e| ParseError::syntax(e, input))?;
+    parser::parse_end(input).map_err(|e| ParseError::syntax(e, start))?;
+    Ok(parser::gomod(start, input, header, ret, trailing))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_gomod, ParseError};
+
+    use super::{parse_gomod_limited, Limits};
+
+    const GOMOD: &str = r#"module example.com/m
+
+go 1.21
+
+require (
+    example.com/a v1.0.0
+    example.com/b v1.0.0
+    example.com/c v1.0.0
+)
+"#;
+
+    #[test]
+    fn test_limits() {
+        assert_eq!(
+            parse_gomod_limited(GOMOD, Limits::default()).unwrap(),
+            parse_gomod(GOMOD).unwrap()
+        );
+        assert_eq!(
+            parse_gomod_limited(
+                GOMOD,
+                Limits {
+                    max_input_len: 10,
+                    ..Default::default()
+                }
+            ),
+            Err(ParseError::InputTooLong {
+                len: GOMOD.len(),
+                max: 10
+            })
+        );
+        assert_eq!(
+            parse_gomod_limited(
+                GOMOD,
+                Limits {
+                    max_directives: 2,
+                    ..Default::default()
+                }
+            ),
+            Err(ParseError::TooManyDirectives {
+                line: 5,
+                offset: 31,
+                len: 87,
+                max: 2
+            })
+        );
+        assert_eq!(
+            parse_gomod_limited(
+                GOMOD,
+                Limits {
+                    max_specs_per_block: 2,
+                    ..Default::default()
+                }
+            ),
+            Err(ParseError::TooManySpecs {
+                line: 5,
+                offset: 31,
+                len: 87,
+                max: 2
+            })
+        );
+        assert_eq!(
+            parse_gomod_limited("", Limits::default()),
+            Err(ParseError::Syntax {
+                line: 1,
+                offset: 0,
+                len: 0
+            })
+        );
+    }
+}
@@ -0,0 +1,300 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use crate::{version::SemVer, ContextOwned, DirectiveOwned, GoModOwned, ReplaceSpecOwned};
+
+/// Error returned by [`merge`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeError {
+    /// Both trees require `module_path` at versions of different major versions, at least one
+    /// of them `v2` or above, e.g. `v1.5.0` and `v2.0.0+incompatible`
+    MajorVersionConflict {
+        module_path: String,
+        base: String,
+        overlay: String,
+    },
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MajorVersionConflict {
+                module_path,
+                base,
+                overlay,
+            } => write!(
+                f,
+                "{module_path} is required at {base} and {overlay}, of different major versions"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MergeError {}
+
+fn check_major(module_path: &str, base: &str, overlay: &str) -> Result<(), MergeError> {
+    match (SemVer::parse(base), SemVer::parse(overlay)) {
+        (Some(a), Some(b)) if a.major != b.major && a.major.max(b.major) >= 2 => {
+            Err(MergeError::MajorVersionConflict {
+                module_path: module_path.into(),
+                base: base.into(),
+                overlay: overlay.into(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+// Merge each overlay spec into the first base spec with the same key, return the others
+fn merge_specs<T, K: PartialEq>(
+    base: &mut GoModOwned,
+    overlay: Vec<ContextOwned<T>>,
+    specs_of: impl Fn(&mut DirectiveOwned) -> Option<&mut Vec<ContextOwned<T>>>,
+    key: impl Fn(&T) -> K,
+    check: impl Fn(&T, &T) -> Result<(), MergeError>,
+) -> Result<Vec<ContextOwned<T>>, MergeError> {
+    let mut rest = Vec::new();
+    for spec in overlay {
+        let old = base
+            .directives
+            .iter_mut()
+            .filter_map(|i| specs_of(&mut i.value))
+            .find_map(|specs| specs.iter_mut().find(|i| key(&i.value) == key(&spec.value)));
+        match old {
+            Some(old) => {
+                check(&old.value, &spec.value)?;
+                merge_node(old, spec);
+            }
+            None => rest.push(spec),
+        }
+    }
+    Ok(rest)
+}
+
+fn merge_node<T>(old: &mut ContextOwned<T>, new: ContextOwned<T>) {
+    old.value = new.value;
+    if !new.comments.is_empty() {
+        old.comments = new.comments;
+        old.suffix_comment = new.suffix_comment;
+        old.range = new.range;
+    }
+}
+
+fn replace_key(spec: &ReplaceSpecOwned) -> (String, Option<String>) {
+    (spec.module_path.clone(), spec.version.clone())
+}
+
+/// Merge `overlay` into `base`, e.g. to compose a shared base go.mod with per-module additions.
+///
+/// - `module`, `go` and `toolchain` of `overlay` replace those of `base`
+/// - a require, godebug, replace, exclude, retract, use or ignore spec of `overlay` replaces the spec of
+///   `base` with the same key: the module path for requires, the key for godebugs, the module
+///   path and optional version for replaces, the whole spec for the others
+/// - comments of `overlay` replace those of the spec or directive they merge into, unless it
+///   has none
+/// - other specs of `overlay` are added in new directives, placed by
+///   [`GoModOwned::insert_directive`]
+///
+/// A module required at different major versions by both trees, at least one `v2` or above,
+/// fails with [`MergeError::MajorVersionConflict`].
+pub fn merge(mut base: GoModOwned, overlay: GoModOwned) -> Result<GoModOwned, MergeError> {
+    for directive in overlay.directives {
+        let kind = directive.value.kind();
+        let rest = match directive.value {
+            DirectiveOwned::Module { .. }
+            | DirectiveOwned::Go { .. }
+            | DirectiveOwned::Toolchain { .. } => {
+                match base.directives.iter_mut().find(|i| i.value.kind() == kind) {
+                    Some(old) => merge_node(old, directive),
+                    None => {
+                        let i = base.insert_directive(directive.value, None);
+                        base.directives[i].comments = directive.comments;
+                        base.directives[i].suffix_comment = directive.suffix_comment;
+                    }
+                }
+                continue;
+            }
+            DirectiveOwned::Require { specs } => DirectiveOwned::Require {
+                specs: merge_specs(
+                    &mut base,
+                    specs,
+                    |i| match i {
+                        DirectiveOwned::Require { specs } => Some(specs),
+                        _ => None,
+                    },
+                    |(path, _)| path.clone(),
+                    |(path, old), (_, new)| check_major(path, old, new),
+                )?,
+            },
+            DirectiveOwned::Godebug { specs } => DirectiveOwned::Godebug {
+                specs: merge_specs(
+                    &mut base,
+                    specs,
+                    |i| match i {
+                        DirectiveOwned::Godebug { specs } => Some(specs),
+                        _ => None,
+                    },
+                    |(key, _)| key.clone(),
+                    |_, _| Ok(()),
+                )?,
+            },
+            DirectiveOwned::Replace { specs } => DirectiveOwned::Replace {
+                specs: merge_specs(
+                    &mut base,
+                    specs,
+                    |i| match i {
+                        DirectiveOwned::Replace { specs } => Some(specs),
+                        _ => None,
+                    },
+                    replace_key,
+                    |_, _| Ok(()),
+                )?,
+            },
+            DirectiveOwned::Exclude { specs } => DirectiveOwned::Exclude {
+                specs: merge_specs(
+                    &mut base,
+                    specs,
+                    |i| match i {
+                        DirectiveOwned::Exclude { specs } => Some(specs),
+                        _ => None,
+                    },
+                    Clone::clone,
+                    |_, _| Ok(()),
+                )?,
+            },
+            DirectiveOwned::Retract { specs } => DirectiveOwned::Retract {
+                specs: merge_specs(
+                    &mut base,
+                    specs,
+                    |i| match i {
+                        DirectiveOwned::Retract { specs } => Some(specs),
+                        _ => None,
+                    },
+                    Clone::clone,
+                    |_, _| Ok(()),
+                )?,
+            },
+            DirectiveOwned::Use { specs } => DirectiveOwned::Use {
+                specs: merge_specs(
+                    &mut base,
+                    specs,
+                    |i| match i {
+                        DirectiveOwned::Use { specs } => Some(specs),
+                        _ => None,
+                    },
+                    Clone::clone,
+                    |_, _| Ok(()),
+                )?,
+            },
+            DirectiveOwned::Ignore { specs } => DirectiveOwned::Ignore {
+                specs: merge_specs(
+                    &mut base,
+                    specs,
+                    |i| match i {
+                        DirectiveOwned::Ignore { specs } => Some(specs),
+                        _ => None,
+                    },
+                    Clone::clone,
+                    |_, _| Ok(()),
+                )?,
+            },
+        };
+        let empty = match &rest {
+            DirectiveOwned::Require { specs }
+            | DirectiveOwned::Godebug { specs }
+            | DirectiveOwned::Exclude { specs } => specs.is_empty(),
+            DirectiveOwned::Replace { specs } => specs.is_empty(),
+            DirectiveOwned::Retract { specs } => specs.is_empty(),
+            DirectiveOwned::Use { specs } | DirectiveOwned::Ignore { specs } => specs.is_empty(),
+            _ => false,
+        };
+        if !empty {
+            let i = base.insert_directive(rest, None);
+            base.directives[i].comments = directive.comments;
+            base.directives[i].suffix_comment = directive.suffix_comment;
+        }
+    }
+    Ok(base)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::{parse_gomod, GoModOwned};
+
+    use super::{merge, MergeError};
+
+    fn owned(text: &str) -> GoModOwned {
+        (&parse_gomod(text).unwrap()).into()
+    }
+
+    #[test]
+    fn test_merge() {
+        let base = owned(
+            r#"module example.com/base
+
+go 1.21
+
+require (
+    example.com/a v1.0.0 // indirect
+    example.com/b v1.0.0
+)
+
+replace example.com/a => ../a
+"#,
+        );
+        let overlay = owned(
+            r#"module example.com/app
+
+go 1.22
+
+// added
+require (
+    example.com/b v1.2.0 // pinned
+    example.com/c v0.1.0
+    example.com/a v1.1.0
+)
+
+replace example.com/a => example.com/fork/a v1.1.0
+exclude example.com/b v1.1.0
+"#,
+        );
+        assert_eq!(
+            merge(base, overlay).unwrap().to_string(),
+            r#"module example.com/app
+
+go 1.22
+
+require (
+	example.com/a v1.1.0 // indirect
+	example.com/b v1.2.0 // pinned
+)
+
+// added
+require example.com/c v0.1.0
+
+exclude example.com/b v1.1.0
+
+replace example.com/a => example.com/fork/a v1.1.0
+"#
+        );
+    }
+
+    #[test]
+    fn test_merge_major_conflict() {
+        let base = owned("module m\n\nrequire example.com/a v1.5.0\n");
+        let overlay = owned("require example.com/a v2.0.0+incompatible\n");
+        assert_eq!(
+            merge(base.clone(), overlay),
+            Err(MergeError::MajorVersionConflict {
+                module_path: "example.com/a".into(),
+                base: "v1.5.0".into(),
+                overlay: "v2.0.0+incompatible".into(),
+            })
+        );
+        let overlay = owned("require example.com/a v0.9.0\n");
+        assert!(merge(base, overlay).is_ok());
+    }
+}
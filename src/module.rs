@@ -0,0 +1,382 @@
+//! Helpers for module paths, following
+//! [golang.org/x/mod/module](https://pkg.go.dev/golang.org/x/mod/module).
+//!
+//! Major versions 2 and up of a module live at a path ending in `/vN`, e.g.
+//! `example.com/new/thing/v2`. `gopkg.in` paths use `.vN` instead, e.g. `gopkg.in/yaml.v3`,
+//! where `.v0` and `.v1` are valid as well.
+
+use core::{fmt, ops::Deref};
+
+fn parse_major(n: &str) -> Option<u64> {
+    if n.is_empty() || !n.bytes().all(|c| c.is_ascii_digit()) || (n.len() > 1 && n.starts_with('0'))
+    {
+        return None;
+    }
+    n.parse().ok()
+}
+
+/// Split `path` into its base and major version, `None` if it has no valid suffix
+fn split_path_version(path: &str) -> Option<(&str, u64)> {
+    if path.starts_with("gopkg.in/") {
+        let (base, n) = path.rsplit_once(".v")?;
+        return Some((base, parse_major(n)?)).filter(|(base, _)| base.len() > "gopkg.in/".len());
+    }
+    let (base, n) = path.rsplit_once("/v")?;
+    let n = parse_major(n)?;
+    (n >= 2 && !base.is_empty()).then_some((base, n))
+}
+
+/// The major version at the end of `path`, e.g. `Some(2)` for `example.com/new/thing/v2` and
+/// `Some(3)` for `gopkg.in/yaml.v3`.
+///
+/// `None` when there's no suffix or it's invalid, such as `/v1`, `/v0` or `/v02`.
+pub fn major_version_suffix(path: &str) -> Option<u64> {
+    split_path_version(path).map(|(_, n)| n)
+}
+
+/// `path` without its major version suffix, e.g. `example.com/new/thing` for
+/// `example.com/new/thing/v2`. Paths without a valid suffix are returned as is.
+pub fn base_module_path(path: &str) -> &str {
+    split_path_version(path).map_or(path, |(base, _)| base)
+}
+
+/// Why a module path is invalid, see [`validate_module_path`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathError {
+    Empty,
+    LeadingDash,
+    DoubleSlash,
+    TrailingSlash,
+    EmptyElement,
+    /// An element made of dots only, e.g. `..`
+    DotsElement,
+    LeadingDot,
+    TrailingDot,
+    InvalidChar(char),
+    /// An element whose part before the first dot is a reserved Windows name, e.g. `aux.go`
+    WindowsReservedName,
+    MissingDotInFirstElement,
+    /// The first element only allows lowercase ASCII letters, digits, `-` and `.`
+    InvalidCharInFirstElement(char),
+    /// A malformed major version suffix, e.g. `/v1`, `/v02` or `/v2.0`
+    InvalidVersion,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty string"),
+            Self::LeadingDash => write!(f, "leading dash"),
+            Self::DoubleSlash => write!(f, "double slash"),
+            Self::TrailingSlash => write!(f, "trailing slash"),
+            Self::EmptyElement => write!(f, "empty path element"),
+            Self::DotsElement => write!(f, "invalid path element"),
+            Self::LeadingDot => write!(f, "leading dot in path element"),
+            Self::TrailingDot => write!(f, "trailing dot in path element"),
+            Self::InvalidChar(c) => write!(f, "invalid char {c:?}"),
+            Self::WindowsReservedName => {
+                write!(f, "disallowed as path element component on Windows")
+            }
+            Self::MissingDotInFirstElement => write!(f, "missing dot in first path element"),
+            Self::InvalidCharInFirstElement(c) => {
+                write!(f, "invalid char {c:?} in first path element")
+            }
+            Self::InvalidVersion => write!(f, "invalid version"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PathError {}
+
+const WINDOWS_RESERVED: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// `import` relaxes the rules the way Go's `importPath` kind does: leading dots and `+` are
+/// allowed in elements
+fn check_element(elem: &str, import: bool) -> Result<(), PathError> {
+    if elem.is_empty() {
+        return Err(PathError::EmptyElement);
+    }
+    if elem.bytes().all(|c| c == b'.') {
+        return Err(PathError::DotsElement);
+    }
+    if !import && elem.starts_with('.') {
+        return Err(PathError::LeadingDot);
+    }
+    if elem.ends_with('.') {
+        return Err(PathError::TrailingDot);
+    }
+    if let Some(c) = elem
+        .chars()
+        .find(|&c| !(c.is_ascii_alphanumeric() || "-._~".contains(c) || import && c == '+'))
+    {
+        return Err(PathError::InvalidChar(c));
+    }
+    let short = elem.split('.').next().unwrap_or(elem);
+    if WINDOWS_RESERVED
+        .iter()
+        .any(|i| i.eq_ignore_ascii_case(short))
+    {
+        return Err(PathError::WindowsReservedName);
+    }
+    Ok(())
+}
+
+/// Whether the major version suffix of `path`, if any, is well formed, following Go's
+/// `module.SplitPathVersion`
+fn valid_path_version(path: &str) -> bool {
+    let b = path.as_bytes();
+    if let Some(rest) = path.strip_prefix("gopkg.in/") {
+        let rest = rest.strip_suffix("-unstable").unwrap_or(rest);
+        let Some((_, n)) = rest.rsplit_once(".v") else {
+            return false;
+        };
+        return !n.is_empty()
+            && n.bytes().all(|c| c.is_ascii_digit())
+            && (!n.starts_with('0') || n == "0");
+    }
+    let mut i = b.len();
+    while i > 0 && (b[i - 1].is_ascii_digit() || b[i - 1] == b'.') {
+        i -= 1;
+    }
+    if i <= 1 || i == b.len() || b[i - 1] != b'v' || b[i - 2] != b'/' {
+        return true;
+    }
+    let n = &path[i..];
+    !(n.contains('.') || n.starts_with('0') || n == "1")
+}
+
+fn check_path(path: &str, import: bool) -> Result<(), PathError> {
+    if path.is_empty() {
+        return Err(PathError::Empty);
+    }
+    if path.starts_with('-') {
+        return Err(PathError::LeadingDash);
+    }
+    if path.contains("//") {
+        return Err(PathError::DoubleSlash);
+    }
+    if path.ends_with('/') {
+        return Err(PathError::TrailingSlash);
+    }
+    path.split('/')
+        .try_for_each(|elem| check_element(elem, import))
+}
+
+/// Check `path` the way Go's `module.CheckPath` does for the module paths of a go.mod file:
+///
+/// - elements are non-empty, made of ASCII letters, digits and `-._~`, and can't start or
+///   end with a dot, nor be a reserved Windows name like `con` or `aux.txt`
+/// - the first element is lowercase and contains a dot, e.g. `example.com`
+/// - a major version suffix is `/v2` or above, see [`major_version_suffix`]
+pub fn validate_module_path(path: &str) -> Result<(), PathError> {
+    check_path(path, false)?;
+    let first = path.split('/').next().unwrap_or(path);
+    if !first.contains('.') {
+        return Err(PathError::MissingDotInFirstElement);
+    }
+    if let Some(c) = first
+        .chars()
+        .find(|&c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.'))
+    {
+        return Err(PathError::InvalidCharInFirstElement(c));
+    }
+    if !valid_path_version(path) {
+        return Err(PathError::InvalidVersion);
+    }
+    Ok(())
+}
+
+/// Check `path` the way Go's `module.CheckImportPath` does, which is what the `module`
+/// directive is held to: unlike [`validate_module_path`], the first element needs no dot, so
+/// `module hello` is fine, and elements may start with a dot or contain `+`
+pub fn validate_import_path(path: &str) -> Result<(), PathError> {
+    check_path(path, true)
+}
+
+/// A module path checked by [`validate_module_path`], e.g. from `&*spec.value.0` of a require
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModulePath<'a>(&'a str);
+
+impl<'a> ModulePath<'a> {
+    pub fn new(path: &'a str) -> Result<Self, PathError> {
+        validate_module_path(path).map(|_| Self(path))
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+
+    /// See [`base_module_path`]
+    pub fn base(&self) -> &'a str {
+        base_module_path(self.0)
+    }
+
+    /// See [`major_version_suffix`]
+    pub fn major(&self) -> Option<u64> {
+        major_version_suffix(self.0)
+    }
+
+    /// Whether `prefix` is this path or one of its parents, element by element, so
+    /// `example.com/m` matches `example.com/m/sub` but not `example.com/mod`
+    pub fn prefix_matches(&self, prefix: &str) -> bool {
+        let prefix = prefix.trim_end_matches('/');
+        self.0
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for ModulePath<'a> {
+    type Error = PathError;
+
+    fn try_from(path: &'a str) -> Result<Self, PathError> {
+        Self::new(path)
+    }
+}
+
+impl Deref for ModulePath<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.0
+    }
+}
+
+impl AsRef<str> for ModulePath<'_> {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+impl fmt::Display for ModulePath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        base_module_path, major_version_suffix, validate_import_path, validate_module_path,
+        ModulePath, PathError,
+    };
+
+    #[test]
+    fn test_major_version_suffix() {
+        for (path, major, base) in [
+            ("example.com/new/thing/v2", Some(2), "example.com/new/thing"),
+            (
+                "example.com/new/thing/v10",
+                Some(10),
+                "example.com/new/thing",
+            ),
+            ("example.com/new/thing", None, "example.com/new/thing"),
+            ("example.com/new/thing/v1", None, "example.com/new/thing/v1"),
+            ("example.com/new/thing/v0", None, "example.com/new/thing/v0"),
+            (
+                "example.com/new/thing/v02",
+                None,
+                "example.com/new/thing/v02",
+            ),
+            (
+                "example.com/new/thing/v2x",
+                None,
+                "example.com/new/thing/v2x",
+            ),
+            ("example.com/new/thing.v2", None, "example.com/new/thing.v2"),
+            ("gopkg.in/yaml.v3", Some(3), "gopkg.in/yaml"),
+            ("gopkg.in/yaml.v1", Some(1), "gopkg.in/yaml"),
+            ("gopkg.in/check.v1/sub", None, "gopkg.in/check.v1/sub"),
+            ("gopkg.in/yaml", None, "gopkg.in/yaml"),
+            ("gopkg.in/.v2", None, "gopkg.in/.v2"),
+        ] {
+            assert_eq!(major_version_suffix(path), major, "{path}");
+            assert_eq!(base_module_path(path), base, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_validate_module_path() {
+        for path in [
+            "example.com/m",
+            "example.com/Upper/m",
+            "example.com/m/v2",
+            "gopkg.in/yaml.v3",
+            "gopkg.in/check.v0",
+            "example.com/a_b~c/d-e.f",
+        ] {
+            assert_eq!(validate_module_path(path), Ok(()), "{path}");
+        }
+        for (path, e) in [
+            ("", PathError::Empty),
+            ("-example.com", PathError::LeadingDash),
+            ("example.com//m", PathError::DoubleSlash),
+            ("example.com/m/", PathError::TrailingSlash),
+            ("/example.com", PathError::EmptyElement),
+            ("example.com/../m", PathError::DotsElement),
+            ("example.com/.m", PathError::LeadingDot),
+            ("example.com/m.", PathError::TrailingDot),
+            ("example.com/m n", PathError::InvalidChar(' ')),
+            ("example.com/aux", PathError::WindowsReservedName),
+            ("example.com/Con.txt", PathError::WindowsReservedName),
+            ("example.com/lpt1/m", PathError::WindowsReservedName),
+            ("example/m", PathError::MissingDotInFirstElement),
+            ("Example.com/m", PathError::InvalidCharInFirstElement('E')),
+            ("example_x.com/m", PathError::InvalidCharInFirstElement('_')),
+            ("example.com/m/v1", PathError::InvalidVersion),
+            ("example.com/m/v02", PathError::InvalidVersion),
+            ("example.com/m/v2.0", PathError::InvalidVersion),
+            ("gopkg.in/yaml", PathError::InvalidVersion),
+        ] {
+            assert_eq!(validate_module_path(path), Err(e), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_validate_import_path() {
+        for path in [
+            "hello",
+            "m",
+            "Hello/World",
+            "example.com/m/v1",
+            "a/.b",
+            "c++/lib",
+        ] {
+            assert_eq!(validate_import_path(path), Ok(()), "{path}");
+        }
+        for (path, e) in [
+            ("", PathError::Empty),
+            ("-m", PathError::LeadingDash),
+            ("m//n", PathError::DoubleSlash),
+            ("m/", PathError::TrailingSlash),
+            ("m/..", PathError::DotsElement),
+            ("m./n", PathError::TrailingDot),
+            ("m n", PathError::InvalidChar(' ')),
+            ("nul", PathError::WindowsReservedName),
+        ] {
+            assert_eq!(validate_import_path(path), Err(e), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_module_path() {
+        let path = ModulePath::new("example.com/new/thing/v2").unwrap();
+        assert_eq!(path.base(), "example.com/new/thing");
+        assert_eq!(path.major(), Some(2));
+        assert!(path.starts_with("example.com"));
+        assert!(path.prefix_matches("example.com/new"));
+        assert!(path.prefix_matches("example.com/new/thing/v2"));
+        assert!(path.prefix_matches("example.com/new/"));
+        assert!(!path.prefix_matches("example.com/ne"));
+        assert_eq!(path.to_string(), "example.com/new/thing/v2");
+        assert_eq!(
+            ModulePath::try_from("example.com/m/v1"),
+            Err(PathError::InvalidVersion)
+        );
+        assert_eq!(ModulePath::new(""), Err(PathError::Empty));
+    }
+}
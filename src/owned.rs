@@ -0,0 +1,1005 @@
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt;
+
+use crate::{
+    format::push_identifier, needs_quoting, query::is_indirect, version::version_cmp, Context,
+    Directive, DirectiveKind, GoMod, Identifier, Location, Range, ReplaceSpec, Replacement,
+    RetractSpec,
+};
+
+/// Owned counterpart of [`Context`]. `range` is `None` for nodes built in code.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContextOwned<T> {
+    pub range: Option<Range>,
+    pub comments: Vec<String>,
+    /// Whether the last of `comments` is on the line of the node, see [`Context::suffix_comment`]
+    pub suffix_comment: bool,
+    pub value: T,
+}
+
+/// Owned counterpart of [`RetractSpec`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RetractSpecOwned {
+    Version(String),
+    Range((String, String)),
+}
+
+/// Owned counterpart of [`Replacement`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ReplacementOwned {
+    FilePath(String),
+    Module((String, String)),
+}
+
+/// Owned counterpart of [`ReplaceSpec`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReplaceSpecOwned {
+    pub module_path: String,
+    pub version: Option<String>,
+    pub replacement: ReplacementOwned,
+}
+
+/// Owned counterpart of [`Directive`], identifiers are stored as their resolved value
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DirectiveOwned {
+    Module {
+        module_path: String,
+    },
+    Go {
+        version: String,
+    },
+    Require {
+        specs: Vec<ContextOwned<(String, String)>>,
+    },
+    Toolchain {
+        name: String,
+    },
+    Godebug {
+        specs: Vec<ContextOwned<(String, String)>>,
+    },
+    Replace {
+        specs: Vec<ContextOwned<ReplaceSpecOwned>>,
+    },
+    Exclude {
+        specs: Vec<ContextOwned<(String, String)>>,
+    },
+    Retract {
+        specs: Vec<ContextOwned<RetractSpecOwned>>,
+    },
+    Use {
+        specs: Vec<ContextOwned<String>>,
+    },
+    Ignore {
+        specs: Vec<ContextOwned<String>>,
+    },
+}
+
+/// A go.mod tree owning its strings, so it can be edited and outlive the parsed text.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct GoModOwned {
+    pub directives: Vec<ContextOwned<DirectiveOwned>>,
+}
+
+fn pair((a, b): &(Identifier, Identifier)) -> (String, String) {
+    (a.into(), b.into())
+}
+
+fn specs<T, U>(specs: &[Context<T>], f: impl Fn(&T) -> U) -> Vec<ContextOwned<U>> {
+    specs.iter().map(|i| i.to_owned_with(&f)).collect()
+}
+
+impl<'a, T> Context<'a, T> {
+    fn to_owned_with<U>(&self, f: impl Fn(&T) -> U) -> ContextOwned<U> {
+        ContextOwned {
+            range: Some(self.range.clone()),
+            comments: self.comments.iter().map(|&i| i.into()).collect(),
+            suffix_comment: self.suffix_comment,
+            value: f(&self.value),
+        }
+    }
+}
+
+impl From<&Replacement<'_>> for ReplacementOwned {
+    fn from(replacement: &Replacement) -> Self {
+        match replacement {
+            Replacement::FilePath(path) => Self::FilePath(path.into()),
+            Replacement::Module(module) => Self::Module(pair(module)),
+        }
+    }
+}
+
+impl From<&ReplaceSpec<'_>> for ReplaceSpecOwned {
+    fn from(spec: &ReplaceSpec) -> Self {
+        Self {
+            module_path: (&*spec.module_path).into(),
+            version: spec.version.as_deref().map(Into::into),
+            replacement: (&spec.replacement).into(),
+        }
+    }
+}
+
+impl From<&RetractSpec<'_>> for RetractSpecOwned {
+    fn from(spec: &RetractSpec) -> Self {
+        match spec {
+            RetractSpec::Version(version) => Self::Version(version.into()),
+            RetractSpec::Range(range) => Self::Range(pair(range)),
+        }
+    }
+}
+
+impl From<&Directive<'_>> for DirectiveOwned {
+    fn from(directive: &Directive) -> Self {
+        match directive {
+            Directive::Module { module_path } => Self::Module {
+                module_path: module_path.into(),
+            },
+            Directive::Go { version } => Self::Go {
+                version: version.into(),
+            },
+            Directive::Toolchain { name } => Self::Toolchain { name: name.into() },
+            Directive::Require { specs: s, .. } => Self::Require {
+                specs: specs(s, pair),
+            },
+            Directive::Exclude { specs: s, .. } => Self::Exclude {
+                specs: specs(s, pair),
+            },
+            Directive::Godebug { specs: s, .. } => Self::Godebug {
+                specs: specs(s, |&(k, v): &(&str, &str)| (k.into(), v.into())),
+            },
+            Directive::Replace { specs: s, .. } => Self::Replace {
+                specs: specs(s, |i: &ReplaceSpec| i.into()),
+            },
+            Directive::Retract { specs: s, .. } => Self::Retract {
+                specs: specs(s, |i: &RetractSpec| i.into()),
+            },
+            Directive::Use { specs: s, .. } => Self::Use {
+                specs: specs(s, |i: &Identifier| i.into()),
+            },
+            Directive::Ignore { specs: s, .. } => Self::Ignore {
+                specs: specs(s, |i: &Identifier| i.into()),
+            },
+        }
+    }
+}
+
+impl From<Directive<'_>> for DirectiveOwned {
+    fn from(directive: Directive) -> Self {
+        (&directive).into()
+    }
+}
+
+impl From<&GoMod<'_>> for GoModOwned {
+    fn from(gomod: &GoMod) -> Self {
+        Self {
+            directives: specs(&gomod.directives, |i: &Directive| i.into()),
+        }
+    }
+}
+
+impl<T> ContextOwned<T> {
+    fn clear_comments(&mut self) {
+        self.comments.clear();
+        self.suffix_comment = false;
+    }
+
+    fn normalize(&mut self, comments: bool) {
+        self.range = None;
+        if !comments {
+            self.comments.clear();
+            self.suffix_comment = false;
+        }
+    }
+}
+
+// The owned tree of `gomod` without ranges, and without comments unless `comments`
+fn normalized(gomod: &GoMod, comments: bool) -> GoModOwned {
+    let mut ret = GoModOwned::from(gomod);
+    for directive in &mut ret.directives {
+        directive.normalize(comments);
+        match &mut directive.value {
+            DirectiveOwned::Require { specs }
+            | DirectiveOwned::Exclude { specs }
+            | DirectiveOwned::Godebug { specs } => {
+                specs.iter_mut().for_each(|i| i.normalize(comments))
+            }
+            DirectiveOwned::Replace { specs } => {
+                specs.iter_mut().for_each(|i| i.normalize(comments))
+            }
+            DirectiveOwned::Retract { specs } => {
+                specs.iter_mut().for_each(|i| i.normalize(comments))
+            }
+            DirectiveOwned::Use { specs } | DirectiveOwned::Ignore { specs } => {
+                specs.iter_mut().for_each(|i| i.normalize(comments))
+            }
+            DirectiveOwned::Module { .. }
+            | DirectiveOwned::Go { .. }
+            | DirectiveOwned::Toolchain { .. } => {}
+        }
+    }
+    ret
+}
+
+impl GoMod<'_> {
+    /// Whether both files hold the same directives, specs and comments in the same order,
+    /// whatever their positions, quoting and layout: `"v1.0.0"` equals `v1.0.0`, and a
+    /// single-line `require` equals a block of that one spec. Header and trailing comments are
+    /// compared as well.
+    ///
+    /// See [`crate::diff`] to compare requires and replaces whatever their order.
+    pub fn semantic_eq(&self, other: &GoMod) -> bool {
+        self.header_comments == other.header_comments
+            && self.trailing_comments == other.trailing_comments
+            && normalized(self, true) == normalized(other, true)
+    }
+
+    /// Same as [`GoMod::semantic_eq`], ignoring comments entirely
+    pub fn semantic_eq_ignoring_comments(&self, other: &GoMod) -> bool {
+        normalized(self, false) == normalized(other, false)
+    }
+}
+
+impl From<&Identifier<'_>> for String {
+    fn from(identifier: &Identifier) -> Self {
+        (**identifier).into()
+    }
+}
+
+impl DirectiveOwned {
+    pub fn kind(&self) -> DirectiveKind {
+        match self {
+            Self::Module { .. } => DirectiveKind::Module,
+            Self::Go { .. } => DirectiveKind::Go,
+            Self::Require { .. } => DirectiveKind::Require,
+            Self::Toolchain { .. } => DirectiveKind::Toolchain,
+            Self::Godebug { .. } => DirectiveKind::Godebug,
+            Self::Replace { .. } => DirectiveKind::Replace,
+            Self::Exclude { .. } => DirectiveKind::Exclude,
+            Self::Retract { .. } => DirectiveKind::Retract,
+            Self::Use { .. } => DirectiveKind::Use,
+            Self::Ignore { .. } => DirectiveKind::Ignore,
+        }
+    }
+}
+
+/// Position of each kind in the layout written by the go command
+fn rank(kind: DirectiveKind) -> u8 {
+    match kind {
+        DirectiveKind::Module => 0,
+        DirectiveKind::Go => 1,
+        DirectiveKind::Toolchain => 2,
+        DirectiveKind::Godebug => 3,
+        DirectiveKind::Use => 4,
+        DirectiveKind::Require => 5,
+        DirectiveKind::Exclude => 6,
+        DirectiveKind::Replace => 7,
+        DirectiveKind::Retract => 8,
+        DirectiveKind::Ignore => 9,
+    }
+}
+
+impl GoModOwned {
+    /// Insert `directive` at `position`, or when `None`, right after the last directive of the
+    /// same kind, falling back to the order the go command writes them in (`module`, `go`,
+    /// `toolchain`, `godebug`, `use`, `require`, `exclude`, `replace`, `retract`).
+    ///
+    /// Return the index it was inserted at.
+    pub fn insert_directive(
+        &mut self,
+        directive: impl Into<DirectiveOwned>,
+        position: Option<usize>,
+    ) -> usize {
+        let directive = directive.into();
+        let kind = directive.kind();
+        let position = position.unwrap_or_else(|| {
+            match self.directives.iter().rposition(|i| i.value.kind() == kind) {
+                Some(i) => i + 1,
+                None => self
+                    .directives
+                    .iter()
+                    .position(|i| rank(i.value.kind()) > rank(kind))
+                    .unwrap_or(self.directives.len()),
+            }
+        });
+        let position = position.min(self.directives.len());
+        self.directives.insert(
+            position,
+            ContextOwned {
+                range: None,
+                comments: Vec::new(),
+                suffix_comment: false,
+                value: directive,
+            },
+        );
+        position
+    }
+
+    /// Sort the specs of each `require` directive the way `go mod tidy` does: by module path,
+    /// then by version precedence. Comments stay with their spec, and the sort is stable.
+    pub fn sort_requires(&mut self) {
+        for directive in &mut self.directives {
+            if let DirectiveOwned::Require { specs } = &mut directive.value {
+                specs.sort_by(|a, b| {
+                    let ((a, av), (b, bv)) = (&a.value, &b.value);
+                    a.cmp(b)
+                        .then_with(|| version_cmp(av, bv).unwrap_or_else(|| av.cmp(bv)))
+                });
+            }
+        }
+    }
+
+    /// Mark the require specs of `module_path` as indirect or not, like Go's `SetIndirect`.
+    ///
+    /// The flag lives in the suffix comment of a spec, the one on its line: it becomes
+    /// `// indirect`, or `// indirect; note` when there was a note, and back when unset.
+    /// Comments on the lines before the spec are left alone.
+    /// Return whether `module_path` is required.
+    pub fn set_indirect(&mut self, module_path: &str, indirect: bool) -> bool {
+        let mut found = false;
+        for directive in &mut self.directives {
+            let DirectiveOwned::Require { specs } = &mut directive.value else {
+                continue;
+            };
+            for spec in specs.iter_mut().filter(|i| i.value.0 == module_path) {
+                found = true;
+                let comments: Vec<_> = spec.comments.iter().map(String::as_str).collect();
+                if is_indirect(&comments, spec.suffix_comment) == indirect {
+                    continue;
+                }
+                let suffix = match spec.suffix_comment {
+                    true => spec.comments.last_mut(),
+                    false => None,
+                };
+                match (suffix, indirect) {
+                    // without a suffix comment the spec wasn't indirect
+                    (None, _) => {
+                        spec.comments.push(" indirect".into());
+                        spec.suffix_comment = true;
+                    }
+                    (Some(comment), true) => match comment.trim() {
+                        "" => *comment = " indirect".into(),
+                        note => *comment = format!(" indirect; {note}"),
+                    },
+                    (Some(comment), false) => {
+                        match comment.trim()["indirect".len()..]
+                            .trim_start_matches(';')
+                            .trim()
+                        {
+                            "" => {
+                                spec.comments.pop();
+                                spec.suffix_comment = false;
+                            }
+                            note => *comment = format!(" {note}"),
+                        }
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Remove the comments of all directives and specs, e.g. for a canonical form to hash.
+    ///
+    /// Comments Go reads as data are kept, so `go list -m -json` sees the same module:
+    ///
+    /// - `// indirect` on require specs, without a note after it
+    /// - the `Deprecated:` paragraph of the `module` directive
+    /// - retraction rationales, i.e. the comments of `retract` directives and specs
+    ///
+    /// Everything else, e.g. notes on specs or comments on block parens, is cosmetic.
+    pub fn strip_comments(&mut self) {
+        for directive in &mut self.directives {
+            let comments = &mut directive.comments;
+            match &mut directive.value {
+                DirectiveOwned::Module { .. } => {
+                    let deprecated = comments
+                        .iter()
+                        .position(|i| i.trim_start().starts_with("Deprecated:"));
+                    // the suffix comment, if any, is the last one and survives only when the
+                    // kept paragraph runs up to it
+                    let keeps_last = match deprecated {
+                        Some(start) => {
+                            let end = comments[start..]
+                                .iter()
+                                .position(|i| i.trim().is_empty())
+                                .map_or(comments.len(), |i| start + i);
+                            let keeps_last = end == comments.len();
+                            comments.truncate(end);
+                            comments.drain(..start);
+                            keeps_last
+                        }
+                        None => {
+                            comments.clear();
+                            false
+                        }
+                    };
+                    directive.suffix_comment &= keeps_last;
+                }
+                DirectiveOwned::Retract { .. } => {}
+                DirectiveOwned::Require { specs } => {
+                    comments.clear();
+                    for spec in specs {
+                        let indirect = is_indirect(
+                            &spec.comments.iter().map(String::as_str).collect::<Vec<_>>(),
+                            spec.suffix_comment,
+                        );
+                        spec.comments.clear();
+                        spec.suffix_comment = indirect;
+                        if indirect {
+                            spec.comments.push(" indirect".into());
+                        }
+                    }
+                }
+                DirectiveOwned::Exclude { specs } | DirectiveOwned::Godebug { specs } => {
+                    comments.clear();
+                    specs.iter_mut().for_each(ContextOwned::clear_comments);
+                }
+                DirectiveOwned::Replace { specs } => {
+                    comments.clear();
+                    specs.iter_mut().for_each(ContextOwned::clear_comments);
+                }
+                DirectiveOwned::Use { specs } | DirectiveOwned::Ignore { specs } => {
+                    comments.clear();
+                    specs.iter_mut().for_each(ContextOwned::clear_comments);
+                }
+                DirectiveOwned::Go { .. } | DirectiveOwned::Toolchain { .. } => {
+                    directive.clear_comments()
+                }
+            }
+        }
+    }
+
+    /// Replace module path `from` with `to` wherever it appears: the `module` directive, and
+    /// require, exclude and replace specs, on both sides of `=>`. Only exact matches are
+    /// renamed, so `from/v2` is left alone when renaming `from`.
+    ///
+    /// Return the number of paths renamed.
+    pub fn rename_module(&mut self, from: &str, to: &str) -> usize {
+        let mut count = 0;
+        let mut rename = |path: &mut String| {
+            if path == from {
+                *path = to.into();
+                count += 1;
+            }
+        };
+        for directive in &mut self.directives {
+            match &mut directive.value {
+                DirectiveOwned::Module { module_path } => rename(module_path),
+                DirectiveOwned::Require { specs } | DirectiveOwned::Exclude { specs } => {
+                    specs.iter_mut().for_each(|i| rename(&mut i.value.0))
+                }
+                DirectiveOwned::Replace { specs } => {
+                    for spec in specs {
+                        rename(&mut spec.value.module_path);
+                        if let ReplacementOwned::Module((path, _)) = &mut spec.value.replacement {
+                            rename(path);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        count
+    }
+}
+
+/// How to write `value` so it parses back the same: bare when possible, else quoted. Stricter
+/// than Go as a lone bracket or `=>` ends a bare token here.
+pub(crate) fn token(value: &str) -> String {
+    let identifier = if !needs_quoting(value)
+        && !value.contains("=>")
+        && !value.contains(['(', ')', ',', '[', ']'])
+    {
+        Identifier::Bare(value)
+    } else if value.contains(['\t', '\r', '\u{08}', '\u{0c}']) && !value.contains(['`', '\n']) {
+        Identifier::Raw(value)
+    } else {
+        Identifier::Interpreted(value.into())
+    };
+    let mut out = String::new();
+    push_identifier(&mut out, &identifier);
+    out
+}
+
+// godebug tokens can't hold escape sequences
+pub(crate) fn godebug_token(value: &str) -> String {
+    if !value.is_empty() && !value.contains([' ', '\t', '\r', '\n', ',', '"', '\'', '`', '=']) {
+        value.into()
+    } else if !value.contains(['"', '\\']) {
+        format!("\"{value}\"")
+    } else {
+        format!("`{value}`")
+    }
+}
+
+// The comment on the line of a node, and those on the lines before it
+fn split_suffix<T>(node: &ContextOwned<T>) -> (Option<&String>, &[String]) {
+    match node.comments.split_last() {
+        Some((suffix, before)) if node.suffix_comment => (Some(suffix), before),
+        _ => (None, &node.comments),
+    }
+}
+
+// Write ` //comment` after the tokens of a line, then end it
+fn end_line(f: &mut fmt::Formatter<'_>, suffix: Option<&String>) -> fmt::Result {
+    if let Some(comment) = suffix {
+        write!(f, " //{comment}")?;
+    }
+    writeln!(f)
+}
+
+type SpecLine<'s> = (String, Option<&'s String>, &'s [String]);
+
+fn spec_line<T>(spec: &ContextOwned<T>, f: impl Fn(&T) -> String) -> SpecLine<'_> {
+    let (suffix, before) = split_suffix(spec);
+    (f(&spec.value), suffix, before)
+}
+
+fn pair_line((a, b): &(String, String)) -> String {
+    format!("{} {}", token(a), token(b))
+}
+
+impl fmt::Display for GoModOwned {
+    /// Write the tree as go.mod text, a blank line between directives. A suffix comment, see
+    /// [`ContextOwned::suffix_comment`], is written on the line of its directive or spec and the
+    /// other comments on the lines before it, so parsing the text gives back the same tree.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, directive) in self.directives.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let (suffix, before) = split_suffix(directive);
+            for comment in before {
+                writeln!(f, "//{comment}")?;
+            }
+            let keyword = directive.value.kind().keyword();
+            let lines: Vec<_> = match &directive.value {
+                DirectiveOwned::Module { module_path: value }
+                | DirectiveOwned::Go { version: value }
+                | DirectiveOwned::Toolchain { name: value } => {
+                    write!(f, "{keyword} {}", token(value))?;
+                    end_line(f, suffix)?;
+                    continue;
+                }
+                DirectiveOwned::Require { specs } | DirectiveOwned::Exclude { specs } => {
+                    specs.iter().map(|i| spec_line(i, pair_line)).collect()
+                }
+                DirectiveOwned::Godebug { specs } => specs
+                    .iter()
+                    .map(|i| {
+                        spec_line(i, |(k, v)| {
+                            format!("{}={}", godebug_token(k), godebug_token(v))
+                        })
+                    })
+                    .collect(),
+                DirectiveOwned::Replace { specs } => specs
+                    .iter()
+                    .map(|i| {
+                        spec_line(i, |spec| {
+                            let mut line = token(&spec.module_path);
+                            if let Some(version) = &spec.version {
+                                line = format!("{line} {}", token(version));
+                            }
+                            match &spec.replacement {
+                                ReplacementOwned::FilePath(path) => {
+                                    format!("{line} => {}", token(path))
+                                }
+                                ReplacementOwned::Module(module) => {
+                                    format!("{line} => {}", pair_line(module))
+                                }
+                            }
+                        })
+                    })
+                    .collect(),
+                DirectiveOwned::Retract { specs } => specs
+                    .iter()
+                    .map(|i| {
+                        spec_line(i, |spec| match spec {
+                            RetractSpecOwned::Version(version) => token(version),
+                            RetractSpecOwned::Range((low, high)) => {
+                                format!("[{}, {}]", token(low), token(high))
+                            }
+                        })
+                    })
+                    .collect(),
+                DirectiveOwned::Use { specs } | DirectiveOwned::Ignore { specs } => {
+                    specs.iter().map(|i| spec_line(i, |i| token(i))).collect()
+                }
+            };
+            match &lines[..] {
+                [] => writeln!(f, "{keyword} ()")?,
+                [(tokens, suffix, [])] => {
+                    write!(f, "{keyword} {tokens}")?;
+                    end_line(f, *suffix)?;
+                }
+                _ => {
+                    writeln!(f, "{keyword} (")?;
+                    for (tokens, suffix, before) in &lines {
+                        for comment in before.iter() {
+                            writeln!(f, "\t//{comment}")?;
+                        }
+                        write!(f, "\t{tokens}")?;
+                        end_line(f, *suffix)?;
+                    }
+                    writeln!(f, ")")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn spec<'a, T>(value: T) -> Context<'a, T> {
+    Context {
+        range: (Location::default(), Location::default()),
+        source: None,
+        comments: Vec::new(),
+        suffix_comment: false,
+        value,
+    }
+}
+
+/// Single-line directives for building edits, their ranges are left to the default.
+impl<'a> Directive<'a> {
+    pub fn new_module(module_path: &'a str) -> Self {
+        Self::Module {
+            module_path: Identifier::Bare(module_path),
+        }
+    }
+
+    pub fn new_go(version: &'a str) -> Self {
+        Self::Go {
+            version: Identifier::Bare(version),
+        }
+    }
+
+    pub fn new_toolchain(name: &'a str) -> Self {
+        Self::Toolchain {
+            name: Identifier::Bare(name),
+        }
+    }
+
+    pub fn new_godebug(key: &'a str, value: &'a str) -> Self {
+        Self::Godebug {
+            specs: vec![spec((key, value))],
+            block: None,
+        }
+    }
+
+    pub fn new_require(module_path: &'a str, version: &'a str) -> Self {
+        Self::Require {
+            specs: vec![spec((
+                Identifier::Bare(module_path),
+                Identifier::Bare(version),
+            ))],
+            group_starts: Vec::new(),
+            block: None,
+        }
+    }
+
+    pub fn new_exclude(module_path: &'a str, version: &'a str) -> Self {
+        Self::Exclude {
+            specs: vec![spec((
+                Identifier::Bare(module_path),
+                Identifier::Bare(version),
+            ))],
+            block: None,
+        }
+    }
+
+    /// `replacement` is a file path when `replacement_version` is `None`
+    pub fn new_replace(
+        module_path: &'a str,
+        version: Option<&'a str>,
+        replacement: &'a str,
+        replacement_version: Option<&'a str>,
+    ) -> Self {
+        let replacement = match replacement_version {
+            Some(v) => Replacement::Module((Identifier::Bare(replacement), Identifier::Bare(v))),
+            None => Replacement::FilePath(Identifier::Bare(replacement)),
+        };
+        Self::Replace {
+            specs: vec![spec(ReplaceSpec {
+                module_path: Identifier::Bare(module_path),
+                version: version.map(Identifier::Bare),
+                arrow: Location::default(),
+                replacement,
+            })],
+            block: None,
+        }
+    }
+
+    pub fn new_retract(version: &'a str) -> Self {
+        Self::Retract {
+            specs: vec![spec(RetractSpec::Version(Identifier::Bare(version)))],
+            block: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::{parse_gomod, Directive, DirectiveKind};
+
+    use super::{DirectiveOwned, GoModOwned};
+
+    #[test]
+    fn test_insert_directive() {
+        let mut gomod: GoModOwned = {
+            let text = alloc::string::String::from(
+                "module example.com/m\n\ngo 1.21\n\nrequire example.com/a v1.0.0\n",
+            );
+            let gomod = parse_gomod(&text).unwrap();
+            (&gomod).into()
+        };
+        assert_eq!(
+            gomod.directives[0].value,
+            DirectiveOwned::Module {
+                module_path: "example.com/m".into()
+            }
+        );
+        assert_eq!(gomod.directives[2].range.as_ref().unwrap().0.line, 5);
+
+        assert_eq!(
+            gomod.insert_directive(Directive::new_require("example.com/b", "v1.0.0"), None),
+            3
+        );
+        assert_eq!(
+            gomod.insert_directive(Directive::new_toolchain("go1.22.1"), None),
+            2
+        );
+        assert_eq!(
+            gomod.insert_directive(Directive::new_retract("v1.0.0"), None),
+            5
+        );
+        assert_eq!(
+            gomod.insert_directive(
+                Directive::new_replace("example.com/a", None, "../a", None),
+                None
+            ),
+            5
+        );
+        assert_eq!(
+            gomod.insert_directive(Directive::new_godebug("panicnil", "1"), Some(100)),
+            7
+        );
+        assert_eq!(
+            gomod
+                .directives
+                .iter()
+                .map(|i| i.value.kind())
+                .collect::<Vec<_>>(),
+            vec![
+                DirectiveKind::Module,
+                DirectiveKind::Go,
+                DirectiveKind::Toolchain,
+                DirectiveKind::Require,
+                DirectiveKind::Require,
+                DirectiveKind::Replace,
+                DirectiveKind::Retract,
+                DirectiveKind::Godebug,
+            ]
+        );
+        assert!(gomod.directives[3].range.is_some());
+        assert!(gomod.directives[4].range.is_none());
+    }
+
+    #[test]
+    fn test_sort_requires() {
+        let text = r#"module example.com/m
+
+require (
+    example.com/foo/v2 v2.0.0
+    // about b
+    example.com/b v1.10.0 // indirect
+    example.com/foo v1.0.0
+    example.com/b v1.9.0
+)
+"#;
+        let mut gomod: GoModOwned = (&parse_gomod(text).unwrap()).into();
+        gomod.sort_requires();
+        let DirectiveOwned::Require { specs } = &gomod.directives[1].value else {
+            unreachable!()
+        };
+        assert_eq!(
+            specs
+                .iter()
+                .map(|i| (i.value.0.as_str(), i.value.1.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("example.com/b", "v1.9.0"),
+                ("example.com/b", "v1.10.0"),
+                ("example.com/foo", "v1.0.0"),
+                ("example.com/foo/v2", "v2.0.0"),
+            ]
+        );
+        assert_eq!(specs[1].comments, vec![" about b", " indirect"]);
+    }
+
+    #[test]
+    fn test_display() {
+        let text = r#"// about m
+module example.com/m
+require (
+    example.com/a v1.0.0 // indirect
+    // about b
+    example.com/b "v1 beta" // c
+)
+replace example.com/a => ../a
+retract [v1.0.0, v1.0.1]
+require ()
+"#;
+        let gomod: GoModOwned = (&parse_gomod(text).unwrap()).into();
+        assert_eq!(
+            gomod.to_string(),
+            r#"// about m
+module example.com/m
+
+require (
+	example.com/a v1.0.0 // indirect
+	// about b
+	example.com/b "v1 beta" // c
+)
+
+replace example.com/a => ../a
+
+retract [v1.0.0, v1.0.1]
+
+require ()
+"#
+        );
+    }
+
+    #[test]
+    fn test_set_indirect() {
+        let text = r#"module example.com/m
+
+require (
+    example.com/a v1.0.0
+    example.com/b v1.0.0 // pinned for CVE
+    example.com/c v1.0.0 // indirect
+    example.com/d v1.0.0 // indirect; pinned
+    // about e
+    example.com/e v1.0.0
+    // indirect
+    example.com/f v1.0.0
+)
+"#;
+        let mut gomod: GoModOwned = (&parse_gomod(text).unwrap()).into();
+        assert!(gomod.set_indirect("example.com/f", false));
+        for path in ["example.com/a", "example.com/b", "example.com/e"] {
+            assert!(gomod.set_indirect(path, true));
+        }
+        for path in ["example.com/c", "example.com/d"] {
+            assert!(gomod.set_indirect(path, false));
+        }
+        assert!(!gomod.set_indirect("example.com/x", true));
+        assert_eq!(
+            gomod.to_string(),
+            r#"module example.com/m
+
+require (
+	example.com/a v1.0.0 // indirect
+	example.com/b v1.0.0 // indirect; pinned for CVE
+	example.com/c v1.0.0
+	example.com/d v1.0.0 // pinned
+	// about e
+	example.com/e v1.0.0 // indirect
+	// indirect
+	example.com/f v1.0.0
+)
+"#
+        );
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let text = r#"// About the module.
+//
+// Deprecated: use example.com/m/v2
+module example.com/m
+
+go 1.21 // language
+
+// direct deps
+require (
+	example.com/a v1.0.0 // pinned
+	// transitive
+	example.com/b v1.0.0 // indirect; via a
+) // end
+
+replace example.com/a => ../a // local
+
+// broken release
+retract v1.0.0
+"#;
+        let mut gomod: GoModOwned = (&parse_gomod(text).unwrap()).into();
+        gomod.strip_comments();
+        assert_eq!(
+            gomod.to_string(),
+            r#"// Deprecated: use example.com/m/v2
+module example.com/m
+
+go 1.21
+
+require (
+	example.com/a v1.0.0
+	example.com/b v1.0.0 // indirect
+)
+
+replace example.com/a => ../a
+
+// broken release
+retract v1.0.0
+"#
+        );
+    }
+
+    #[test]
+    fn test_strip_comments_module_suffix() {
+        for (text, want, suffix_comment) in [
+            ("module m // note\n", "module m\n", false),
+            (
+                "// Deprecated: use n\n//\n// more\nmodule m // note\n",
+                "// Deprecated: use n\nmodule m\n",
+                false,
+            ),
+            (
+                "// About m.\nmodule m // Deprecated: use n\n",
+                "module m // Deprecated: use n\n",
+                true,
+            ),
+        ] {
+            let mut gomod: GoModOwned = (&parse_gomod(text).unwrap()).into();
+            gomod.strip_comments();
+            assert_eq!(gomod.to_string(), want, "{text}");
+            assert_eq!(gomod.directives[0].suffix_comment, suffix_comment, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_rename_module() {
+        let text = r#"module example.com/old
+
+require (
+	example.com/old/v2 v2.0.0
+	example.com/old v1.0.0 // indirect
+)
+
+exclude example.com/old v0.9.0
+
+replace example.com/old v1.0.0 => example.com/old v1.0.1
+"#;
+        let mut gomod: GoModOwned = (&parse_gomod(text).unwrap()).into();
+        assert_eq!(gomod.rename_module("example.com/old", "example.com/new"), 5);
+        assert_eq!(
+            gomod.to_string(),
+            text.replace("example.com/old", "example.com/new")
+                .replace("example.com/new/v2", "example.com/old/v2")
+        );
+        assert_eq!(gomod.rename_module("example.com/old", "example.com/new"), 0);
+    }
+
+    #[test]
+    fn test_semantic_eq() {
+        let a = parse_gomod(
+            "// header\n\nmodule example.com/m\n\ngo 1.21\n\nrequire example.com/a v1.0.0 // indirect\n",
+        )
+        .unwrap();
+        let b = parse_gomod(
+            "// header\n\nmodule \"example.com/m\"\ngo 1.21\nrequire (\n    example.com/a `v1.0.0` // indirect\n)\n",
+        )
+        .unwrap();
+        assert_ne!(a, b);
+        assert!(a.semantic_eq(&b));
+        assert!(a.semantic_eq_ignoring_comments(&b));
+        let c =
+            parse_gomod("module example.com/m\ngo 1.21\nrequire example.com/a v1.0.0\n").unwrap();
+        assert!(!a.semantic_eq(&c));
+        assert!(a.semantic_eq_ignoring_comments(&c));
+        let d =
+            parse_gomod("module example.com/m\ngo 1.21\nrequire example.com/a v1.0.1\n").unwrap();
+        assert!(!c.semantic_eq_ignoring_comments(&d));
+    }
+}
@@ -1,28 +1,58 @@
-use crate::{Identifier, Span, Sundry};
+use alloc::{string::String, vec::Vec};
+
+use crate::{Context, Directive, DirectiveSet, Identifier, Location, Span, Sundry};
 
 use super::GoMod;
 use nom::{
     branch::alt,
     bytes::complete::{escaped_transform, is_a, is_not, tag, take, take_while, take_while1},
-    character::{
-        complete::{char, one_of},
-        is_alphanumeric,
-    },
-    combinator::{eof, peek, recognize, verify},
-    error::ParseError,
-    multi::{fold_many0, fold_many1, many0, many_till},
-    sequence::{delimited, pair, preceded, terminated},
-    IResult, Parser,
+    character::complete::char,
+    combinator::{all_consuming, eof, recognize, verify},
+    error::{Error, ErrorKind},
+    multi::{fold_many0, fold_many1},
+    sequence::{delimited, pair, preceded, terminated, tuple},
+    Err, IResult, InputTake, Parser, Slice,
 };
+use nom_locate::position;
 
 mod exclude_directive;
 mod go_directive;
 mod godebug_directive;
+mod ignore_directive;
 mod module_directive;
 mod replace_directive;
 mod require_directive;
 mod retract_directive;
 mod toolchain_directive;
+mod use_directive;
+
+pub(crate) fn location(span: &Span) -> Location {
+    let base = usize::from(span.extra.zero_based_positions);
+    Location {
+        line: span.location_line() - base as u32,
+        column: if let Some(width) = span.extra.tab_width {
+            let before = &span.get_line_beginning()[..span.get_column() - 1];
+            display_width(core::str::from_utf8(before).unwrap_or_default(), width) + 1
+        } else if span.extra.utf8_columns {
+            span.get_utf8_column()
+        } else {
+            span.get_column()
+        } - base,
+        offset: span.location_offset(),
+    }
+}
+
+/// Columns taken by `s` on screen, a tab advancing to the next multiple of `tab_width`
+pub(crate) fn display_width(s: &str, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    s.chars().fold(0, |col, c| {
+        if c == '\t' {
+            (col / tab_width + 1) * tab_width
+        } else {
+            col + 1
+        }
+    })
+}
 
 fn delims0(input: Span) -> IResult<Span, Span> {
     take_while(|c| c == ' ' || c == '\t' || c == '\r')(input)
@@ -30,36 +60,62 @@ fn delims0(input: Span) -> IResult<Span, Span> {
 fn delims1(input: Span) -> IResult<Span, Span> {
     is_a(" \t\r")(input)
 }
-fn quoted<'a, E: ParseError<Span<'a>>, F>(
-    f: F,
-) -> impl FnMut(Span<'a>) -> IResult<Span<'a>, Span<'a>, E>
-where
-    F: Parser<Span<'a>, Span<'a>, E> + Copy,
-{
-    alt((
-        f,
-        delimited(char('"'), f, char('"')),
-        delimited(char('`'), f, char('`')),
-    ))
+
+// go.mod only has `//` line comments. Fail right at a `/*` instead of backtracking to some
+// unrelated position, see ParseError::BlockCommentUnsupported
+fn reject_block_comment(input: Span) -> IResult<Span, Sundry> {
+    let (input, _) = delims0(input)?;
+    if input.fragment().starts_with("/*") {
+        return Err(Err::Failure(Error::new(input, ErrorKind::Tag)));
+    }
+    Err(Err::Error(Error::new(input, ErrorKind::Tag)))
+}
+
+// The text after `//` byte for byte, up to the line ending, `\r\n` included
+fn comment_text(input: Span) -> IResult<Span, Span> {
+    let (input, text) = take_while(|c| c != '\n')(input)?;
+    match text.fragment().strip_suffix('\r') {
+        Some(stripped) if input.fragment().starts_with('\n') => {
+            Ok((input, text.slice(..stripped.len())))
+        }
+        _ => Ok((input, text)),
+    }
+}
+
+// Whether a comment starts at `input`, see ParseOptions::hash_comments
+pub(crate) fn starts_comment(input: &Span) -> bool {
+    input.starts_with("//") || (input.extra.hash_comments && input.starts_with('#'))
+}
+fn comment_start(input: Span) -> IResult<Span, Span> {
+    if input.extra.hash_comments && input.starts_with('#') {
+        return tag("#")(input);
+    }
+    tag("//")(input)
 }
 
 // include trailing newline or eof
 fn parse_inline_comment(input: Span) -> IResult<Span, Sundry> {
     alt((
-        delimited(
-            pair(delims0, tag("//")),
-            take_while(|c| c != '\n'),
-            char('\n'),
-        )
-        .map(|i| Sundry::Comment(i)),
-        terminated(delims0, char('\n')).map(|i| Sundry::Empty(i)),
-        delimited(pair(delims0, tag("//")), take_while(|c| c != '\n'), eof)
-            .map(|i| Sundry::Comment(i)),
-        terminated(delims1, eof).map(|i| Sundry::Empty(i)),
+        reject_block_comment,
+        delimited(pair(delims0, comment_start), comment_text, char('\n')).map(Sundry::Comment),
+        terminated(delims0, char('\n')).map(Sundry::Empty),
+        delimited(pair(delims0, comment_start), comment_text, eof).map(Sundry::Comment),
+        terminated(delims1, eof).map(Sundry::Empty),
         eof.map(|_| Sundry::EOF),
     ))(input)
 }
-fn parse_multiline_comments(input: Span) -> IResult<Span, Vec<Sundry>> {
+// Like parse_inline_comment after a value, but a token left on the line fails with an
+// `ErrorKind::Not` failure at the token, see ParseError::UnexpectedTrailingToken
+fn parse_line_end(input: Span) -> IResult<Span, Sundry> {
+    match parse_inline_comment(input) {
+        Err(Err::Error(_)) => {
+            let (rest, _) = delims0(input)?;
+            Err(Err::Failure(Error::new(rest, ErrorKind::Not)))
+        }
+        ret => ret,
+    }
+}
+pub(crate) fn parse_multiline_comments(input: Span) -> IResult<Span, Vec<Sundry>> {
     fold_many0(
         verify(parse_inline_comment, |i| !matches!(i, &Sundry::EOF)),
         Vec::new,
@@ -70,6 +126,52 @@ fn parse_multiline_comments(input: Span) -> IResult<Span, Vec<Sundry>> {
     )(input)
 }
 
+/// Comment lines before the first directive up to the last blank line preceding it, i.e. a
+/// file banner rather than comments about that directive
+pub(crate) fn parse_header(input: Span<'_>) -> IResult<Span<'_>, Vec<&str>> {
+    let (mut rest, mut cur) = (input, input);
+    let (mut header, mut pending) = (Vec::new(), Vec::new());
+    while let Ok((next, item)) = parse_inline_comment(cur) {
+        match item {
+            Sundry::Comment(c) => pending.push(c.into_fragment()),
+            Sundry::Empty(_) => {
+                header.append(&mut pending);
+                rest = next;
+            }
+            Sundry::EOF => break,
+        }
+        cur = next;
+    }
+    Ok((rest, header))
+}
+
+// `()` on a single line, e.g. `require ()`, followed by an optional comment. Return where
+// the `)` is.
+fn parse_empty_block(input: Span) -> IResult<Span, (Location, Sundry)> {
+    let (input, (_, close)) = pair(tuple((delims0, char('('), delims0)), position)(input)?;
+    let (input, comment) = preceded(char(')'), parse_inline_comment)(input)?;
+    Ok((input, (location(&close), comment)))
+}
+
+// comments left at the end of a block, then `)`, its location and same-line comment
+fn parse_block_end(input: Span<'_>) -> IResult<Span<'_>, (Vec<&str>, Location, Option<&str>)> {
+    let (input, multi_comments) = parse_multiline_comments(input)?;
+    let comments: Vec<_> = multi_comments
+        .into_iter()
+        .filter_map(|i| match i {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        })
+        .collect();
+    let (input, close_paren) = preceded(delims0, position)(input)?;
+    let (input, comment) = preceded(char(')'), parse_inline_comment)(input)?;
+    let close = match comment {
+        Sundry::Comment(c) => Some(c.into_fragment()),
+        _ => None,
+    };
+    Ok((input, (comments, location(&close_paren), close)))
+}
+
 // https://go.dev/ref/mod#go-mod-file-lexical
 //
 // Identifiers are sequences of non-whitespace characters, such as module paths or semantic versions.
@@ -87,26 +189,35 @@ fn parse_multiline_comments(input: Span) -> IResult<Span, Vec<Sundry>> {
 fn parse_identifier(input: Span) -> IResult<Span, Identifier> {
     alt((
         parse_raw_string.map(|i| Identifier::Raw(i.into_fragment())),
-        parse_interpreted_string.map(|i| Identifier::Interpreted(i)),
-        verify(
-            recognize(many_till(
-                take(1usize),
-                peek(alt((
-                    tag("//"),
-                    tag("=>"),
-                    recognize(one_of(" \t\n\r(),[]")),
-                    eof,
-                ))),
-            )),
-            |i: &Span| !i.is_empty(),
-        )
-        .map(|i: Span| Identifier::Raw(i.into_fragment())),
+        parse_interpreted_string.map(Identifier::Interpreted),
+        parse_bare_identifier.map(|i| Identifier::Bare(i.into_fragment())),
     ))(input)
 }
+// up to whitespace, a paren, bracket or comma, `//` or `=>`. Scanned in one pass rather than
+// char by char with combinators, which allocates per char on long tokens. Like Go, a token
+// starting with a quote is a string, so an unterminated one isn't taken as bare.
+fn parse_bare_identifier(input: Span) -> IResult<Span, Span> {
+    let s = input.fragment();
+    if s.starts_with(['"', '`']) {
+        return Err(Err::Error(Error::new(input, ErrorKind::TakeTill1)));
+    }
+    let end = s
+        .char_indices()
+        .find(|&(i, c)| {
+            " \t\n\r(),[]".contains(c) || s[i..].starts_with("//") || s[i..].starts_with("=>")
+        })
+        .map_or(s.len(), |(i, _)| i);
+    if end == 0 {
+        return Err(Err::Error(Error::new(input, ErrorKind::TakeTill1)));
+    }
+    Ok(input.take_split(end))
+}
+// Like Go's go.mod lexer, `\x` stands for `x` whatever the character, so `\n` is `n`. The
+// escaped character is taken whole, even when it's longer than a byte.
 fn parse_interpreted_string(input: Span) -> IResult<Span, String> {
     delimited(
         char('"'),
-        escaped_transform(is_not("\n\r\t\u{08}\u{0c}\"\\"), '\\', take(1u8)),
+        escaped_transform(is_not("\n\r\t\u{08}\u{0c}\"\\"), '\\', take(1usize)),
         char('"'),
     )(input)
 }
@@ -115,42 +226,148 @@ fn parse_raw_string(input: Span) -> IResult<Span, Span> {
 }
 
 fn parse_module_path_fragment(input: Span) -> IResult<Span, Span> {
-    take_while1(|c| is_alphanumeric(c as u8) || c == '-' || c == '_' || c == '.' || c == '~')(input)
+    take_while1(|c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' || c == '~')(
+        input,
+    )
 }
 fn parse_module_path(input: Span) -> IResult<Span, Span> {
     recognize(pair(
         parse_module_path_fragment,
-        many0(preceded(char('/'), parse_module_path_fragment)),
+        fold_many0(
+            preceded(char('/'), parse_module_path_fragment),
+            || (),
+            |_, _| (),
+        ),
+    ))(input)
+}
+
+fn is_module_path(s: &str) -> bool {
+    all_consuming(parse_module_path)(Span::new_extra(s, Default::default())).is_ok()
+}
+// module paths are identifiers too, so they can also be written as (escaped) strings
+pub(crate) fn parse_module_path_identifier(input: Span) -> IResult<Span, Identifier> {
+    alt((
+        parse_module_path.map(|i| Identifier::Bare(i.into_fragment())),
+        delimited(char('`'), parse_module_path, char('`'))
+            .map(|i| Identifier::Raw(i.into_fragment())),
+        verify(parse_interpreted_string, |s: &String| is_module_path(s))
+            .map(Identifier::Interpreted),
     ))(input)
 }
 
+// Module paths only allow ASCII letters, digits and `-._~/`, like Go. A bare path running into
+// another character, e.g. the `:` of `localhost:8080/m` or a non-ASCII letter, fails right at
+// it rather than being truncated before it. Replacement paths don't go through this, `C:\dir` being a file path.
+pub(crate) fn parse_spec_module_path(input: Span) -> IResult<Span, Identifier> {
+    let (rest, path) = parse_module_path_identifier(input)?;
+    let s = rest.fragment();
+    let truncated = matches!(path, Identifier::Bare(_))
+        && !s.is_empty()
+        && !s.starts_with([' ', '\t', '\r', '\n', '(', ')'])
+        && !starts_comment(&rest)
+        && !s.starts_with("=>");
+    if truncated {
+        // `a/Ł` stops before the `/`, the char to blame is the one after it
+        let at = match s.strip_prefix('/') {
+            Some(after) if after.starts_with(|c: char| !c.is_whitespace() && c != '/') => {
+                rest.slice(1..)
+            }
+            _ => rest,
+        };
+        return Err(Err::Failure(Error::new(at, ErrorKind::Char)));
+    }
+    Ok((rest, path))
+}
+
+/// Byte offsets of the version token of the `path version` spec starting at `input`
+pub(crate) fn spec_version_range(input: Span) -> IResult<Span, (usize, usize)> {
+    let (input, _) = pair(parse_module_path_identifier, delims1)(input)?;
+    let start = input.location_offset();
+    let (input, _) = parse_identifier(input)?;
+    Ok((input, (start, input.location_offset())))
+}
+
+pub fn parse_directive(input: Span) -> IResult<Span, Context<Directive>> {
+    let (rest, mut directive) = alt((
+        go_directive::parse_go_directive,
+        module_directive::parse_module_directive,
+        exclude_directive::parse_exclude_directive,
+        godebug_directive::parse_godebug_directive,
+        replace_directive::parse_replace_directive,
+        require_directive::parse_require_directive,
+        retract_directive::parse_retract_directive,
+        toolchain_directive::parse_toolchain_directive,
+        use_directive::parse_use_directive,
+        ignore_directive::parse_ignore_directive,
+    ))(input)?;
+    if !input
+        .extra
+        .allowed_directives
+        .contains(DirectiveSet::of(&directive.value))
+    {
+        let start = input.slice(directive.range.0.offset - input.location_offset()..);
+        let (keyword, _) = delims0(start)?;
+        return Err(Err::Failure(Error::new(keyword, ErrorKind::Verify)));
+    }
+    if input.extra.preserve_source {
+        let (start, end) = &directive.range;
+        let base = input.location_offset();
+        directive.source = Some(&input.fragment()[start.offset - base..end.offset - base]);
+    }
+    Ok((rest, directive))
+}
+
+/// Only the end of the text may follow the trailing comments, anything else fails with an
+/// `ErrorKind::Eof` failure at its first character, see `ParseError::TrailingContent`
+pub(crate) fn parse_end(input: Span) -> IResult<Span, ()> {
+    let (rest, _) = delims0(input)?;
+    if !rest.is_empty() {
+        return Err(Err::Failure(Error::new(rest, ErrorKind::Eof)));
+    }
+    Ok((input, ()))
+}
+
+/// Build the result of parsing `start` up to `end`, `header` being the banner before the first
+/// directive and `trailing` what follows the last one
+pub(crate) fn gomod<'a>(
+    start: Span<'a>,
+    end: Span<'a>,
+    header: Vec<&'a str>,
+    directives: Vec<Context<'a, Directive<'a>>>,
+    trailing: Vec<Sundry<'a>>,
+) -> GoMod<'a> {
+    let consumed = &start.fragment()[..end.location_offset() - start.location_offset()];
+    GoMod {
+        header_comments: header,
+        directives,
+        trailing_comments: trailing
+            .into_iter()
+            .filter_map(|i| match i {
+                Sundry::Comment(c) => Some(c.into_fragment()),
+                _ => None,
+            })
+            .collect(),
+        end: location(&end),
+        ends_with_newline: consumed.ends_with('\n'),
+    }
+}
+
 pub fn parse_gomod(input: Span) -> IResult<Span, GoMod> {
-    let (input, ret) = fold_many1(
-        alt((
-            go_directive::parse_go_directive,
-            module_directive::parse_module_directive,
-            exclude_directive::parse_exclude_directive,
-            godebug_directive::parse_godebug_directive,
-            replace_directive::parse_replace_directive,
-            require_directive::parse_require_directive,
-            retract_directive::parse_retract_directive,
-            toolchain_directive::parse_toolchain_directive,
-        )),
-        Vec::new,
-        |mut acc, directive| {
-            acc.push(directive);
-            acc
-        },
-    )(input)?;
-    let (input, _) = parse_multiline_comments(input)?;
-    Ok((input, ret))
+    let (rest, header) = parse_header(input)?;
+    let (rest, ret) = fold_many1(parse_directive, Vec::new, |mut acc, directive| {
+        acc.push(directive);
+        acc
+    })(rest)?;
+    let (rest, trailing) = parse_multiline_comments(rest)?;
+    let (rest, _) = parse_end(rest)?;
+    Ok((rest, gomod(input, rest, header, ret, trailing)))
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        Context, Directive, Identifier, Location, ReplaceSpec, Replacement, RetractSpec, Span,
-        Sundry,
+        Block, Context, Directive, DirectiveSet, Identifier, Location, ParseOptions, QuoteStyle,
+        ReplaceSpec, Replacement, RetractSpec, Span, Sundry,
     };
 
     use super::{parse_gomod, parse_identifier, parse_inline_comment};
@@ -158,35 +375,266 @@ mod tests {
     #[test]
     fn test_inline_comment() {
         for s in ["// sdfsfs\n", "// sdfsfs", "  // sdfsfs\n", "  // sdfsfs"] {
-            let (input, ret) = parse_inline_comment(Span::new(s)).unwrap();
+            let (input, ret) =
+                parse_inline_comment(Span::new_extra(s, Default::default())).unwrap();
             assert!(matches!(ret, Sundry::Comment(i) if i.into_fragment() == " sdfsfs"));
             assert_eq!(input.into_fragment(), "");
         }
         for s in ["//", "//\n", "  //", "  //\n"] {
-            let (input, ret) = parse_inline_comment(Span::new(s)).unwrap();
+            let (input, ret) =
+                parse_inline_comment(Span::new_extra(s, Default::default())).unwrap();
             assert_eq!(input.into_fragment(), "");
             assert!(matches!(ret, Sundry::Comment(i) if i.fragment().is_empty()));
         }
         for s in ["  ", "\n", "  \n"] {
-            let (input, ret) = parse_inline_comment(Span::new(s)).unwrap();
+            let (input, ret) =
+                parse_inline_comment(Span::new_extra(s, Default::default())).unwrap();
             assert_eq!(input.into_fragment(), "");
             assert!(matches!(ret, Sundry::Empty(_)));
         }
-        let (input, ret) = parse_inline_comment(Span::new("")).unwrap();
+        let (input, ret) = parse_inline_comment(Span::new_extra("", Default::default())).unwrap();
         assert_eq!(input.into_fragment(), "");
         assert!(matches!(ret, Sundry::EOF));
+        for (s, comment) in [
+            ("//go:build x  \n", "go:build x  "),
+            ("//  a\t \r\n", "  a\t "),
+            ("// a\r", " a\r"),
+            ("// a\rb\n", " a\rb"),
+        ] {
+            let (_, ret) = parse_inline_comment(Span::new_extra(s, Default::default())).unwrap();
+            assert!(
+                matches!(ret, Sundry::Comment(i) if *i.fragment() == comment),
+                "{s:?}"
+            );
+        }
     }
 
     #[test]
     fn test_identifier() {
-        for s in [r#"`v1.0.0`"#, "v1.0.0", r#""v1.0.0""#] {
-            let (input, ret) = parse_identifier(Span::new(s)).unwrap();
+        for (s, style) in [
+            (r#"`v1.0.0`"#, QuoteStyle::Raw),
+            ("v1.0.0", QuoteStyle::Bare),
+            (r#""v1.0.0""#, QuoteStyle::Interpreted),
+        ] {
+            let (input, ret) = parse_identifier(Span::new_extra(s, Default::default())).unwrap();
             assert_eq!(&ret as &str, "v1.0.0");
+            assert_eq!(ret.quote_style(), style);
             assert_eq!(input.into_fragment(), "");
         }
-        let (input, ret) = parse_identifier(Span::new(r#""abc\n\r\f\"dd""#)).unwrap();
+        let (input, ret) =
+            parse_identifier(Span::new_extra(r#""abc\n\r\f\"dd""#, Default::default())).unwrap();
         assert_eq!(&ret as &str, "abcnrf\"dd");
         assert_eq!(input.into_fragment(), "");
+        for (s, value) in [(r#""a\€b""#, "a€b"), (r#""\é\日""#, "é日")] {
+            let (input, ret) = parse_identifier(Span::new_extra(s, Default::default())).unwrap();
+            assert_eq!(&ret as &str, value);
+            assert_eq!(input.into_fragment(), "");
+        }
+        // `//` and `=>` only end bare identifiers
+        for (s, value, rest) in [
+            ("`a//b=>c` // d", "a//b=>c", " // d"),
+            (r#""a//b=>c" => d"#, "a//b=>c", " => d"),
+            ("a//b", "a", "//b"),
+            ("a=>b", "a", "=>b"),
+        ] {
+            let (input, ret) = parse_identifier(Span::new_extra(s, Default::default())).unwrap();
+            assert_eq!(&ret as &str, value);
+            assert_eq!(input.into_fragment(), rest);
+        }
+    }
+
+    #[test]
+    fn test_block_comment() {
+        for (s, offset) in [
+            ("/* banner */\nmodule example.com/m", 0),
+            ("module example.com/m /* c */\n", 21),
+            ("module example.com/m\n\n  /* c */\ngo 1.21", 24),
+            ("require (\n    example.com/a v1.0.0 /* c */\n)", 35),
+            ("require (\n    example.com/a v1.0.0\n\t/* c */\n)", 36),
+        ] {
+            match parse_gomod(Span::new_extra(s, Default::default())) {
+                Err(nom::Err::Failure(e)) => {
+                    assert_eq!(e.input.location_offset(), offset, "{s}");
+                    assert!(e.input.fragment().starts_with("/*"));
+                }
+                ret => panic!("{s}: {ret:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_trailing_newline() {
+        for s in [
+            "module example.com/m",
+            "module (\n    example.com/m\n)",
+            "go 1.21",
+            "toolchain go1.21.0",
+            "require example.com/a v1.0.0",
+            "require (\n    example.com/a v1.0.0\n)",
+            "exclude example.com/a v1.0.0",
+            "exclude (\n    example.com/a v1.0.0\n)",
+            "replace example.com/a => ../a",
+            "replace example.com/a v1.0.0 => example.com/b v1.0.0",
+            "replace (\n    example.com/a => ../a\n)",
+            "retract v1.0.0",
+            "retract [v1.0.0, v1.1.0]",
+            "retract (\n    v1.0.0\n)",
+            "godebug panicnil=1",
+            "godebug (\n    panicnil=1\n)",
+            "go 1.21 // comment",
+            "go 1.21 \t",
+        ] {
+            let (input, ret) = parse_gomod(Span::new_extra(s, Default::default())).unwrap();
+            assert_eq!(input.into_fragment(), "", "{s}");
+            assert_eq!(ret.len(), 1, "{s}");
+            assert_eq!(ret[0].range.1.offset, s.len(), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_empty_blocks() {
+        for verb in ["require", "exclude", "replace", "retract", "godebug"] {
+            for block in [
+                "(\n)\n",
+                "(\n\n)\n",
+                "()\n",
+                "( ) // c\n",
+                "(\n    // c\n\t)",
+            ] {
+                let s = alloc::format!("{verb} {block}");
+                let (input, ret) = parse_gomod(Span::new_extra(&s, Default::default())).unwrap();
+                assert_eq!(input.into_fragment(), "", "{s}");
+                assert_eq!(ret[0].range.1.offset, s.len(), "{s}");
+                assert_eq!(
+                    ret[0].comments.len(),
+                    usize::from(block.contains("//")),
+                    "{s}"
+                );
+                let empty = match &ret[0].value {
+                    Directive::Require { specs, .. } | Directive::Exclude { specs, .. } => {
+                        specs.is_empty()
+                    }
+                    Directive::Replace { specs, .. } => specs.is_empty(),
+                    Directive::Retract { specs, .. } => specs.is_empty(),
+                    Directive::Godebug { specs, .. } => specs.is_empty(),
+                    _ => false,
+                };
+                assert!(empty, "{s}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_block_end() {
+        for (verb, spec) in [
+            ("module", "example.com/m"),
+            ("require", "example.com/a v1.0.0"),
+            ("exclude", "example.com/a v1.0.0"),
+            ("replace", "example.com/a => ../a"),
+            ("retract", "v1.0.0"),
+            ("godebug", "panicnil=1"),
+        ] {
+            for close in [")", "\t)", "    )", " \t )"] {
+                let s = alloc::format!("{verb} (\n\t{spec}\n\t// last\n{close} // end\n");
+                let (input, ret) = parse_gomod(Span::new_extra(&s, Default::default())).unwrap();
+                assert_eq!(input.into_fragment(), "", "{s}");
+                assert_eq!(ret[0].comments, vec![" last", " end"], "{s}");
+                assert_eq!(ret[0].range.1.offset, s.len(), "{s}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_comment_indentation() {
+        let options = ParseOptions {
+            allowed_directives: DirectiveSet::GO_MOD.union(DirectiveSet::USE),
+            ..Default::default()
+        };
+        for (verb, spec) in [
+            ("module", "example.com/m"),
+            ("require", "example.com/a v1.0.0"),
+            ("exclude", "example.com/a v1.0.0"),
+            ("replace", "example.com/a => ../a"),
+            ("retract", "v1.0.0"),
+            ("godebug", "panicnil=1"),
+            ("use", "./a"),
+        ] {
+            let mut comments = alloc::vec::Vec::new();
+            for indent in ["\t", "    ", " \t ", "\t  \t"] {
+                let s = alloc::format!(
+                    "{verb} (\n{indent}// a\n{indent}//\n{indent}{spec} // b\n{indent}//\tc \n)\n"
+                );
+                let (input, ret) = parse_gomod(Span::new_extra(&s, options)).unwrap();
+                assert_eq!(input.into_fragment(), "", "{s}");
+                let mut all = ret[0].comments.clone();
+                match &ret[0].value {
+                    Directive::Require { specs, .. } | Directive::Exclude { specs, .. } => {
+                        all.extend(specs.iter().flat_map(|i| i.comments.clone()))
+                    }
+                    Directive::Replace { specs, .. } => {
+                        all.extend(specs.iter().flat_map(|i| i.comments.clone()))
+                    }
+                    Directive::Retract { specs, .. } => {
+                        all.extend(specs.iter().flat_map(|i| i.comments.clone()))
+                    }
+                    Directive::Godebug { specs, .. } => {
+                        all.extend(specs.iter().flat_map(|i| i.comments.clone()))
+                    }
+                    Directive::Use { specs, .. } => {
+                        all.extend(specs.iter().flat_map(|i| i.comments.clone()))
+                    }
+                    _ => {}
+                }
+                comments.push(
+                    all.into_iter()
+                        .map(alloc::string::String::from)
+                        .collect::<alloc::vec::Vec<_>>(),
+                );
+            }
+            assert!(
+                comments.windows(2).all(|i| i[0] == i[1]),
+                "{verb}: {comments:?}"
+            );
+            let mut first = comments[0].clone();
+            first.sort();
+            assert_eq!(first, ["", "\tc ", " a", " b"], "{verb}");
+        }
+    }
+
+    #[test]
+    fn test_header() {
+        let parse = |s| {
+            parse_gomod(Span::new_extra(s, Default::default()))
+                .unwrap()
+                .1
+        };
+        let ret = parse(
+            "// Copyright
+// License
+
+// about m
+module m
+",
+        );
+        assert_eq!(ret.header_comments, vec![" Copyright", " License"]);
+        assert_eq!(ret[0].comments, vec![" about m"]);
+        let ret = parse(
+            "// a
+
+// b
+
+module m
+",
+        );
+        assert_eq!(ret.header_comments, vec![" a", " b"]);
+        assert!(ret[0].comments.is_empty());
+        let ret = parse(
+            "// about m
+module m
+",
+        );
+        assert!(ret.header_comments.is_empty());
+        assert_eq!(ret[0].comments, vec![" about m"]);
     }
 
     #[test]
@@ -204,183 +652,295 @@ require (
 exclude example.com/old/thing v1.2.3
 replace example.com/bad/thing v1.4.5 => example.com/good/thing v1.4.5
 retract [v1.9.0, v1.9.5]"#;
-        let (input, ret) = parse_gomod(Span::new(s)).unwrap();
+        let (input, ret) = parse_gomod(Span::new_extra(s, Default::default())).unwrap();
         assert_eq!(input.into_fragment(), "");
         assert_eq!(
-            ret,
+            ret.directives,
             vec![
                 Context {
                     range: (
-                        Location { line: 2, offset: 1 },
+                        Location {
+                            line: 2,
+                            column: 1,
+                            offset: 1
+                        },
                         Location {
                             line: 3,
+                            column: 1,
                             offset: 29
                         }
                     ),
+                    source: None,
                     comments: vec![],
+                    suffix_comment: false,
                     value: Directive::Module {
-                        module_path: "example.com/my/thing"
+                        module_path: Identifier::Bare("example.com/my/thing")
                     }
                 },
                 Context {
                     range: (
                         Location {
                             line: 4,
+                            column: 1,
                             offset: 30
                         },
                         Location {
                             line: 5,
+                            column: 1,
                             offset: 38
                         }
                     ),
+                    source: None,
                     comments: vec![],
+                    suffix_comment: false,
                     value: Directive::Go {
-                        version: Identifier::Raw("1.12")
+                        version: Identifier::Bare("1.12")
                     }
                 },
                 Context {
                     range: (
                         Location {
                             line: 6,
+                            column: 1,
                             offset: 39
                         },
                         Location {
                             line: 10,
+                            column: 1,
                             offset: 122
                         }
                     ),
+                    source: None,
                     comments: vec![],
+                    suffix_comment: false,
                     value: Directive::Require {
                         specs: vec![
                             Context {
                                 range: (
                                     Location {
                                         line: 7,
+                                        column: 5,
                                         offset: 53
                                     },
                                     Location {
                                         line: 8,
+                                        column: 1,
                                         offset: 84
                                     }
                                 ),
+                                source: None,
                                 comments: vec![],
-                                value: ("example.com/other/thing", Identifier::Raw("v1.0.2"))
+                                suffix_comment: false,
+                                value: (
+                                    Identifier::Bare("example.com/other/thing"),
+                                    Identifier::Bare("v1.0.2")
+                                )
                             },
                             Context {
                                 range: (
                                     Location {
                                         line: 8,
+                                        column: 5,
                                         offset: 88
                                     },
                                     Location {
                                         line: 9,
+                                        column: 1,
                                         offset: 120
                                     }
                                 ),
+                                source: None,
                                 comments: vec![],
-                                value: ("example.com/new/thing/v2", Identifier::Raw("v2.3.4"))
+                                suffix_comment: false,
+                                value: (
+                                    Identifier::Bare("example.com/new/thing/v2"),
+                                    Identifier::Bare("v2.3.4")
+                                )
                             }
-                        ]
+                        ],
+                        group_starts: vec![],
+                        block: Some(Block {
+                            close_paren: Location {
+                                line: 9,
+                                column: 1,
+                                offset: 120
+                            },
+                            ..Default::default()
+                        }),
                     }
                 },
                 Context {
                     range: (
                         Location {
                             line: 11,
+                            column: 1,
                             offset: 123
                         },
                         Location {
                             line: 12,
+                            column: 1,
                             offset: 160
                         }
                     ),
+                    source: None,
                     comments: vec![],
+                    suffix_comment: false,
                     value: Directive::Exclude {
                         specs: vec![Context {
                             range: (
                                 Location {
                                     line: 11,
+                                    column: 9,
                                     offset: 131
                                 },
                                 Location {
                                     line: 12,
+                                    column: 1,
                                     offset: 160
                                 }
                             ),
+                            source: None,
                             comments: vec![],
-                            value: ("example.com/old/thing", Identifier::Raw("v1.2.3"))
-                        }]
+                            suffix_comment: false,
+                            value: (
+                                Identifier::Bare("example.com/old/thing"),
+                                Identifier::Bare("v1.2.3")
+                            )
+                        }],
+                        block: None,
                     }
                 },
                 Context {
                     range: (
                         Location {
                             line: 12,
+                            column: 1,
                             offset: 160
                         },
                         Location {
                             line: 13,
+                            column: 1,
                             offset: 230
                         }
                     ),
+                    source: None,
                     comments: vec![],
+                    suffix_comment: false,
                     value: Directive::Replace {
                         specs: vec![Context {
                             range: (
                                 Location {
                                     line: 12,
+                                    column: 9,
                                     offset: 168
                                 },
                                 Location {
                                     line: 13,
+                                    column: 1,
                                     offset: 230
                                 }
                             ),
+                            source: None,
                             comments: vec![],
+                            suffix_comment: false,
                             value: ReplaceSpec {
-                                module_path: "example.com/bad/thing",
-                                version: Some(Identifier::Raw("v1.4.5")),
+                                module_path: Identifier::Bare("example.com/bad/thing"),
+                                version: Some(Identifier::Bare("v1.4.5")),
+                                arrow: Location {
+                                    line: 12,
+                                    column: 38,
+                                    offset: 197
+                                },
                                 replacement: Replacement::Module((
-                                    "example.com/good/thing",
-                                    Identifier::Raw("v1.4.5")
+                                    Identifier::Bare("example.com/good/thing"),
+                                    Identifier::Bare("v1.4.5")
                                 ))
                             }
-                        }]
+                        }],
+                        block: None,
                     }
                 },
                 Context {
                     range: (
                         Location {
                             line: 13,
+                            column: 1,
                             offset: 230
                         },
                         Location {
                             line: 13,
+                            column: 25,
                             offset: 254
                         }
                     ),
+                    source: None,
                     comments: vec![],
+                    suffix_comment: false,
                     value: Directive::Retract {
                         specs: vec![Context {
                             range: (
                                 Location {
                                     line: 13,
+                                    column: 9,
                                     offset: 238
                                 },
                                 Location {
                                     line: 13,
+                                    column: 25,
                                     offset: 254
                                 }
                             ),
+                            source: None,
                             comments: vec![],
+                            suffix_comment: false,
                             value: RetractSpec::Range((
-                                Identifier::Raw("v1.9.0"),
-                                Identifier::Raw("v1.9.5")
+                                Identifier::Bare("v1.9.0"),
+                                Identifier::Bare("v1.9.5")
                             ))
-                        }]
+                        }],
+                        block: None,
                     }
                 }
             ]
         );
     }
+
+    // cargo test --release -- --ignored
+    #[test]
+    #[ignore]
+    fn test_stress_long_tokens() {
+        let path = format!("example.com{}", "/a".repeat(50_000));
+        let s = format!("module {path}\n\nrequire {path} v1.0.0\n");
+        let gomod = crate::parse_gomod(&s).unwrap();
+        assert_eq!(gomod.singletons().module_path, Some(&*path));
+        let version = "v".repeat(1 << 20);
+        let s = format!("module example.com/m\n\ngo {version} // c\n");
+        let gomod = crate::parse_gomod(&s).unwrap();
+        assert_eq!(gomod.singletons().go_version, Some(&*version));
+        let s = format!("module example.com/m\n\nretract \"{version}\"\n");
+        let gomod = crate::parse_gomod(&s).unwrap();
+        assert_eq!(gomod.directives.len(), 2);
+    }
+
+    // Line numbers are tracked by nom_locate as the input is consumed, so they stay right on
+    // large inputs. benches/many_lines.rs checks that parsing is linear in the number of lines.
+    // cargo test --release -- --ignored
+    #[test]
+    #[ignore]
+    fn test_stress_many_lines() {
+        let lines = 400_000;
+        let mut s = String::from("module example.com/m\n\nrequire (\n");
+        for i in 0..lines / 2 {
+            s.push_str(&format!("\texample.com/m{i} v1.0.{i} // c\n"));
+        }
+        s.push_str(")\n");
+        for i in 0..lines / 2 {
+            s.push_str(&format!("exclude example.com/m{i} v0.0.{i}\n"));
+        }
+        let gomod = crate::parse_gomod(&s).unwrap();
+        assert_eq!(gomod.len(), 2 + lines / 2);
+        let last = gomod.last().unwrap();
+        assert_eq!(last.range.0.line as usize, lines + 4);
+        assert_eq!(last.range.1.offset, s.len());
+    }
 }
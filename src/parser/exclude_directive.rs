@@ -1,6 +1,9 @@
+use alloc::{vec, vec::Vec};
+
 use nom::{
     bytes::complete::tag,
     character::complete::char,
+    combinator::opt,
     error::Error,
     multi::fold_many0,
     sequence::{pair, preceded, tuple},
@@ -9,38 +12,39 @@ use nom::{
 use nom_locate::position;
 
 use crate::{
-    parser::{parse_identifier, parse_module_path},
-    Context, Directive, Identifier, Location, Span, Sundry,
+    parser::{parse_identifier, parse_spec_module_path},
+    Block, Context, Directive, Identifier, Span, Sundry,
 };
 
-use super::{delims0, delims1, parse_inline_comment, parse_multiline_comments, quoted};
+use super::{
+    delims0, delims1, location, parse_block_end, parse_empty_block, parse_inline_comment,
+    parse_line_end, parse_multiline_comments,
+};
 
-fn parse_exclude_spec(input: Span) -> IResult<Span, Context<(&str, Identifier)>> {
+fn parse_exclude_spec(
+    input: Span<'_>,
+) -> IResult<Span<'_>, Context<'_, (Identifier<'_>, Identifier<'_>)>> {
     let (input, pos) = position(input)?;
-    let start = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
+    let start = location(&pos);
     let (input, (path, version, comment)) = tuple((
-        quoted(parse_module_path),
+        parse_spec_module_path,
         preceded(delims1, parse_identifier),
-        parse_inline_comment,
+        parse_line_end,
     ))(input)?;
     let mut comments = vec![];
     if let Sundry::Comment(c) = comment {
         comments.push(c.into_fragment());
     }
     let (input, pos) = position(input)?;
-    let end = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
+    let end = location(&pos);
     Ok((
         input,
         Context {
             range: (start, end),
+            source: None,
+            suffix_comment: !comments.is_empty(),
             comments,
-            value: (path.into_fragment(), version),
+            value: (path, version),
         },
     ))
 }
@@ -53,20 +57,33 @@ pub fn parse_exclude_directive(input: Span) -> IResult<Span, Context<Directive>>
         _ => None,
     }));
     let (input, tmp) = preceded(delims0, tag("exclude"))(input)?;
-    let start = Location {
-        line: tmp.location_line(),
-        offset: tmp.location_offset(),
-    };
+    let start = location(&tmp);
     let mut specs = vec![];
-    let input = if let Ok((input, spec)) = preceded(delims1, parse_exclude_spec)(input) {
+    let mut block = None;
+    let input = if let (input, Some(spec)) = opt(preceded(delims1, parse_exclude_spec))(input)? {
         specs.push(spec);
         input
-    } else if let Ok((input, comment)) =
-        preceded(pair(delims0, char('(')), parse_inline_comment)(input)
+    } else if let (input, Some((close_paren, comment))) = opt(parse_empty_block)(input)? {
+        let close_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(close_comment);
+        block = Some(Block {
+            open_comment: None,
+            end_comments: vec![],
+            close_comment,
+            close_paren,
+        });
+        input
+    } else if let (input, Some(comment)) =
+        opt(preceded(pair(delims0, char('(')), parse_inline_comment))(input)?
     {
-        if let Sundry::Comment(c) = comment {
-            comments.push(c.into_fragment());
-        }
+        let open_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(open_comment);
         let (input, ret) = fold_many0(
             pair(
                 parse_multiline_comments,
@@ -89,40 +106,38 @@ pub fn parse_exclude_directive(input: Span) -> IResult<Span, Context<Directive>>
                 acc
             },
         )(input)?;
-        specs.extend(ret.into_iter());
-        let (input, multi_comments) = parse_multiline_comments(input)?;
-        comments.extend(multi_comments.into_iter().filter_map(|i| match i {
-            Sundry::Comment(c) => Some(c.into_fragment()),
-            _ => None,
-        }));
-        let (input, comment) = preceded(pair(delims0, char(')')), parse_inline_comment)(input)?;
-        if let Sundry::Comment(c) = comment {
-            comments.push(c.into_fragment());
-        }
+        specs.extend(ret);
+        let (input, (end_comments, close_paren, close_comment)) = parse_block_end(input)?;
+        comments.extend(end_comments.iter().chain(&close_comment));
+        block = Some(Block {
+            open_comment,
+            end_comments,
+            close_comment,
+            close_paren,
+        });
         input
     } else {
         return Err(Err::Error(Error::new(input, nom::error::ErrorKind::Alt)));
     };
     let (input, pos) = position(input)?;
-    let end = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
+    let end = location(&pos);
     Ok((
         input,
         Context {
             range: (start, end),
+            source: None,
             comments,
-            value: Directive::Exclude { specs },
+            suffix_comment: false,
+            value: Directive::Exclude { specs, block },
         },
     ))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Context, Directive, Identifier, Location, Span};
+    use crate::{Block, Context, Directive, Identifier, Location, Span};
 
-    use super::parse_exclude_directive;
+    use super::{parse_exclude_directive, parse_exclude_spec};
 
     #[test]
     fn test_exclude() {
@@ -134,7 +149,7 @@ mod tests {
     golang.org/x/text v1.6.7
     // end specs
  ) // end exclude"#;
-        let (input, ret) = parse_exclude_directive(Span::new(s)).unwrap();
+        let (input, ret) = parse_exclude_directive(Span::new_extra(s, Default::default())).unwrap();
         assert_eq!("", input.into_fragment());
         assert_eq!(
             ret,
@@ -142,52 +157,116 @@ mod tests {
                 range: (
                     Location {
                         line: 3,
+                        column: 9,
                         offset: 34
                     },
                     Location {
                         line: 8,
+                        column: 18,
                         offset: 175
                     }
                 ),
+                source: None,
                 comments: vec![
                     " start exclude",
                     " start specs",
                     " end specs",
                     " end exclude",
                 ],
+                suffix_comment: false,
                 value: Directive::Exclude {
                     specs: vec![
                         Context {
                             range: (
                                 Location {
                                     line: 4,
+                                    column: 5,
                                     offset: 63
                                 },
                                 Location {
                                     line: 5,
+                                    column: 1,
                                     offset: 102
                                 }
                             ),
+                            source: None,
                             comments: vec![" indirect"],
-                            value: ("golang.org/x/crypto", Identifier::Raw("v1.4.5"))
+                            suffix_comment: true,
+                            value: (
+                                Identifier::Bare("golang.org/x/crypto"),
+                                Identifier::Bare("v1.4.5")
+                            )
                         },
                         Context {
                             range: (
                                 Location {
                                     line: 6,
+                                    column: 5,
                                     offset: 116
                                 },
                                 Location {
                                     line: 7,
+                                    column: 1,
                                     offset: 141
                                 }
                             ),
+                            source: None,
                             comments: vec![" mm"],
-                            value: ("golang.org/x/text", Identifier::Raw("v1.6.7"))
+                            suffix_comment: false,
+                            value: (
+                                Identifier::Bare("golang.org/x/text"),
+                                Identifier::Bare("v1.6.7")
+                            )
                         },
-                    ]
+                    ],
+                    block: Some(Block {
+                        open_comment: Some(" start specs"),
+                        end_comments: vec![" end specs"],
+                        close_comment: Some(" end exclude"),
+                        close_paren: Location {
+                            line: 8,
+                            column: 2,
+                            offset: 159
+                        },
+                    }),
                 }
             }
         );
     }
+
+    #[test]
+    fn test_quoted_path() {
+        for (s, path, version) in [
+            (
+                r#""example.com/x" v1.0.0"#,
+                Identifier::Interpreted("example.com/x".into()),
+                Identifier::Bare("v1.0.0"),
+            ),
+            (
+                r#"`example.com/x` "v1.0.0""#,
+                Identifier::Raw("example.com/x"),
+                Identifier::Interpreted("v1.0.0".into()),
+            ),
+            (
+                r#""example.com/y" `v1.0.0` // c"#,
+                Identifier::Interpreted("example.com/y".into()),
+                Identifier::Raw("v1.0.0"),
+            ),
+        ] {
+            let (input, ret) = parse_exclude_spec(Span::new_extra(s, Default::default())).unwrap();
+            assert_eq!("", input.into_fragment());
+            assert_eq!(ret.value, (path, version), "{s}");
+        }
+        assert!(parse_exclude_spec(Span::new_extra(
+            r#""not a path" v1.0.0"#,
+            Default::default()
+        ))
+        .is_err());
+        let s = "exclude (\n    \"example.com/x\" v1.0.0\n)\n";
+        let (_, ret) = parse_exclude_directive(Span::new_extra(s, Default::default())).unwrap();
+        assert!(matches!(
+            ret.value,
+            Directive::Exclude { specs, .. } if *specs[0].value.0 == *"example.com/x"
+        ));
+    }
 }
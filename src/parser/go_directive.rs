@@ -1,3 +1,5 @@
+use alloc::vec;
+
 use nom::{
     bytes::complete::tag,
     sequence::{delimited, tuple},
@@ -5,9 +7,11 @@ use nom::{
 };
 use nom_locate::position;
 
-use crate::{Context, Directive, Location, Span, Sundry};
+use crate::{Context, Directive, Span, Sundry};
 
-use super::{delims0, delims1, parse_identifier, parse_inline_comment, parse_multiline_comments};
+use super::{
+    delims0, delims1, location, parse_identifier, parse_line_end, parse_multiline_comments,
+};
 
 pub fn parse_go_directive(input: Span) -> IResult<Span, Context<Directive>> {
     let mut comments = vec![];
@@ -20,8 +24,9 @@ pub fn parse_go_directive(input: Span) -> IResult<Span, Context<Directive>> {
     let (input, (_, ver, comment)) = tuple((
         delimited(delims0, tag("go"), delims1),
         parse_identifier,
-        parse_inline_comment,
+        parse_line_end,
     ))(input)?;
+    let suffix_comment = matches!(comment, Sundry::Comment(_));
     if let Sundry::Comment(c) = comment {
         comments.push(*c.fragment());
     }
@@ -29,17 +34,10 @@ pub fn parse_go_directive(input: Span) -> IResult<Span, Context<Directive>> {
     Ok((
         input,
         Context {
+            source: None,
             comments,
-            range: (
-                Location {
-                    line: start.location_line(),
-                    offset: start.location_offset(),
-                },
-                Location {
-                    line: end.location_line(),
-                    offset: end.location_offset(),
-                },
-            ),
+            range: (location(&start), location(&end)),
+            suffix_comment,
             value: Directive::Go { version: ver },
         },
     ))
@@ -57,7 +55,7 @@ mod tests {
 // heheda
 go "1.4.5\"rc1" // inline
 "#;
-        let (input, ret) = parse_go_directive(Span::new(s)).unwrap();
+        let (input, ret) = parse_go_directive(Span::new_extra(s, Default::default())).unwrap();
         assert_eq!("", *input.fragment());
         assert_eq!(
             ret,
@@ -65,14 +63,18 @@ go "1.4.5\"rc1" // inline
                 range: (
                     Location {
                         line: 3,
+                        column: 1,
                         offset: 11
                     },
                     Location {
                         line: 4,
+                        column: 1,
                         offset: 37
                     }
                 ),
+                source: None,
                 comments: vec![" heheda", " inline"],
+                suffix_comment: true,
                 value: Directive::Go {
                     version: Identifier::Interpreted("1.4.5\"rc1".to_string())
                 }
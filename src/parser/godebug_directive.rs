@@ -1,6 +1,10 @@
+use alloc::{vec, vec::Vec};
+
 use nom::{
+    branch::alt,
     bytes::complete::{is_not, tag},
     character::complete::char,
+    combinator::opt,
     error::Error,
     multi::fold_many0,
     sequence::{delimited, pair, preceded},
@@ -8,38 +12,47 @@ use nom::{
 };
 use nom_locate::position;
 
-use crate::{parser::parse_multiline_comments, Context, Directive, Location, Span, Sundry};
+use crate::{parser::parse_multiline_comments, Block, Context, Directive, Span, Sundry};
 
-use super::{delims0, delims1, parse_inline_comment, quoted};
+use super::{
+    delims0, delims1, location, parse_block_end, parse_empty_block, parse_inline_comment,
+    parse_line_end,
+};
 
 fn parse_godebug_chars(input: Span) -> IResult<Span, Span> {
     is_not(" \t\r\n,\"'`=")(input)
 }
 
-fn parse_godebug_spec(input: Span) -> IResult<Span, Context<(&str, &str)>> {
+// a key or value, either bare or quoted. Quoted ones may hold `=` or spaces, but not escape
+// sequences since specs borrow the parsed text.
+fn parse_godebug_token(input: Span) -> IResult<Span, Span> {
+    alt((
+        parse_godebug_chars,
+        delimited(char('"'), is_not("\"\\\n"), char('"')),
+        delimited(char('`'), is_not("`\n"), char('`')),
+    ))(input)
+}
+
+fn parse_godebug_spec(input: Span<'_>) -> IResult<Span<'_>, Context<'_, (&str, &str)>> {
     let (input, pos) = position(input)?;
-    let start = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
-    let (input, key) = quoted(parse_godebug_chars)(input)?;
+    let start = location(&pos);
+    let (input, key) = parse_godebug_token(input)?;
     let (input, (value, comment)) = preceded(
         delimited(delims0, char('='), delims0),
-        pair(quoted(parse_godebug_chars), parse_inline_comment),
+        pair(parse_godebug_token, parse_line_end),
     )(input)?;
     let mut comments = vec![];
     if let Sundry::Comment(c) = comment {
         comments.push(c.into_fragment());
     }
     let (input, pos) = position(input)?;
-    let end = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
+    let end = location(&pos);
     Ok((
         input,
         Context {
             range: (start, end),
+            source: None,
+            suffix_comment: !comments.is_empty(),
             comments,
             value: (key.into_fragment(), value.into_fragment()),
         },
@@ -54,20 +67,33 @@ pub fn parse_godebug_directive(input: Span) -> IResult<Span, Context<Directive>>
         _ => None,
     }));
     let (input, tmp) = preceded(delims0, tag("godebug"))(input)?;
-    let start = Location {
-        line: tmp.location_line(),
-        offset: tmp.location_offset(),
-    };
+    let start = location(&tmp);
     let mut specs = vec![];
-    let input = if let Ok((input, spec)) = preceded(delims1, parse_godebug_spec)(input) {
+    let mut block = None;
+    let input = if let (input, Some(spec)) = opt(preceded(delims1, parse_godebug_spec))(input)? {
         specs.push(spec);
         input
-    } else if let Ok((input, comment)) =
-        preceded(pair(delims0, char('(')), parse_inline_comment)(input)
+    } else if let (input, Some((close_paren, comment))) = opt(parse_empty_block)(input)? {
+        let close_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(close_comment);
+        block = Some(Block {
+            open_comment: None,
+            end_comments: vec![],
+            close_comment,
+            close_paren,
+        });
+        input
+    } else if let (input, Some(comment)) =
+        opt(preceded(pair(delims0, char('(')), parse_inline_comment))(input)?
     {
-        if let Sundry::Comment(c) = comment {
-            comments.push(c.into_fragment());
-        }
+        let open_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(open_comment);
         let (input, ret) = fold_many0(
             pair(
                 parse_multiline_comments,
@@ -90,38 +116,36 @@ pub fn parse_godebug_directive(input: Span) -> IResult<Span, Context<Directive>>
                 acc
             },
         )(input)?;
-        specs.extend(ret.into_iter());
-        let (input, multi_comments) = parse_multiline_comments(input)?;
-        comments.extend(multi_comments.into_iter().filter_map(|i| match i {
-            Sundry::Comment(c) => Some(c.into_fragment()),
-            _ => None,
-        }));
-        let (input, comment) = preceded(preceded(delims0, char(')')), parse_inline_comment)(input)?;
-        if let Sundry::Comment(c) = comment {
-            comments.push(c.into_fragment());
-        }
+        specs.extend(ret);
+        let (input, (end_comments, close_paren, close_comment)) = parse_block_end(input)?;
+        comments.extend(end_comments.iter().chain(&close_comment));
+        block = Some(Block {
+            open_comment,
+            end_comments,
+            close_comment,
+            close_paren,
+        });
         input
     } else {
         return Err(Err::Error(Error::new(input, nom::error::ErrorKind::Alt)));
     };
     let (input, pos) = position(input)?;
-    let end = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
+    let end = location(&pos);
     Ok((
         input,
         Context {
             range: (start, end),
+            source: None,
             comments,
-            value: Directive::Godebug { specs },
+            suffix_comment: false,
+            value: Directive::Godebug { specs, block },
         },
     ))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Context, Directive, Location, Span};
+    use crate::{Block, Context, Directive, Location, Span};
 
     use super::parse_godebug_directive;
 
@@ -140,7 +164,7 @@ mod tests {
     // jkl
  ) // end godebug
 "#;
-        let (input, ret) = parse_godebug_directive(Span::new(s)).unwrap();
+        let (input, ret) = parse_godebug_directive(Span::new_extra(s, Default::default())).unwrap();
         assert_eq!("", input.into_fragment());
         assert_eq!(
             ret,
@@ -148,13 +172,16 @@ mod tests {
                 range: (
                     Location {
                         line: 4,
+                        column: 9,
                         offset: 50
                     },
                     Location {
                         line: 13,
+                        column: 1,
                         offset: 196
                     }
                 ),
+                source: None,
                 comments: vec![
                     " hehe",
                     " start godebug",
@@ -162,39 +189,80 @@ mod tests {
                     " jkl",
                     " end godebug"
                 ],
+                suffix_comment: false,
                 value: Directive::Godebug {
                     specs: vec![
                         Context {
                             range: (
                                 Location {
                                     line: 7,
+                                    column: 5,
                                     offset: 101
                                 },
                                 Location {
                                     line: 8,
+                                    column: 1,
                                     offset: 123
                                 }
                             ),
+                            source: None,
                             comments: vec![" abc", "", " spec1"],
+                            suffix_comment: true,
                             value: ("panicnil", "1")
                         },
                         Context {
                             range: (
                                 Location {
                                     line: 10,
+                                    column: 5,
                                     offset: 139
                                 },
                                 Location {
                                     line: 11,
+                                    column: 1,
                                     offset: 167
                                 }
                             ),
+                            source: None,
                             comments: vec![" ghi", " spec2"],
+                            suffix_comment: true,
                             value: ("asynctimerchan", "0")
                         },
-                    ]
+                    ],
+                    block: Some(Block {
+                        open_comment: Some(" start specs"),
+                        end_comments: vec![" jkl"],
+                        close_comment: Some(" end godebug"),
+                        close_paren: Location {
+                            line: 12,
+                            column: 2,
+                            offset: 179
+                        },
+                    }),
                 }
             }
         );
     }
+
+    #[test]
+    fn test_quoted_equals() {
+        for (s, spec) in [
+            (r#"godebug "x=y"=z"#, ("x=y", "z")),
+            (r#"godebug x="a=b""#, ("x", "a=b")),
+            ("godebug `k=1`=`v 2`", ("k=1", "v 2")),
+        ] {
+            let (input, ret) =
+                parse_godebug_directive(Span::new_extra(s, Default::default())).unwrap();
+            assert_eq!(*input.fragment(), "");
+            match ret.value {
+                Directive::Godebug { specs, .. } => assert_eq!(specs[0].value, spec),
+                _ => unreachable!(),
+            }
+        }
+        assert!(parse_godebug_directive(Span::new_extra(
+            r#"godebug "x\"y"=z"#,
+            Default::default()
+        ))
+        .is_err());
+    }
 }
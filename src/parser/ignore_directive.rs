@@ -0,0 +1,223 @@
+use alloc::{vec, vec::Vec};
+
+use nom::{
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::opt,
+    error::Error,
+    multi::fold_many0,
+    sequence::{pair, preceded},
+    Err, IResult,
+};
+use nom_locate::position;
+
+use crate::{parser::parse_identifier, Block, Context, Directive, Identifier, Span, Sundry};
+
+use super::{
+    delims0, delims1, location, parse_block_end, parse_empty_block, parse_inline_comment,
+    parse_line_end, parse_multiline_comments,
+};
+
+// a directory or pattern the go command skips, e.g. `./node_modules` or `static`
+fn parse_ignore_spec(input: Span<'_>) -> IResult<Span<'_>, Context<'_, Identifier<'_>>> {
+    let (input, pos) = position(input)?;
+    let start = location(&pos);
+    let (input, (path, comment)) = pair(parse_identifier, parse_line_end)(input)?;
+    let mut comments = vec![];
+    if let Sundry::Comment(c) = comment {
+        comments.push(c.into_fragment());
+    }
+    let (input, pos) = position(input)?;
+    let end = location(&pos);
+    Ok((
+        input,
+        Context {
+            range: (start, end),
+            source: None,
+            suffix_comment: !comments.is_empty(),
+            comments,
+            value: path,
+        },
+    ))
+}
+
+pub fn parse_ignore_directive(input: Span) -> IResult<Span, Context<Directive>> {
+    let mut comments = vec![];
+    let (input, multi_comments) = parse_multiline_comments(input)?;
+    comments.extend(multi_comments.into_iter().filter_map(|i| match i {
+        Sundry::Comment(c) => Some(c.into_fragment()),
+        _ => None,
+    }));
+    let (input, tmp) = preceded(delims0, tag("ignore"))(input)?;
+    let start = location(&tmp);
+    let mut specs = vec![];
+    let mut block = None;
+    let input = if let (input, Some(spec)) = opt(preceded(delims1, parse_ignore_spec))(input)? {
+        specs.push(spec);
+        input
+    } else if let (input, Some((close_paren, comment))) = opt(parse_empty_block)(input)? {
+        let close_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(close_comment);
+        block = Some(Block {
+            open_comment: None,
+            end_comments: vec![],
+            close_comment,
+            close_paren,
+        });
+        input
+    } else if let (input, Some(comment)) =
+        opt(preceded(pair(delims0, char('(')), parse_inline_comment))(input)?
+    {
+        let open_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(open_comment);
+        let (input, ret) = fold_many0(
+            pair(
+                parse_multiline_comments,
+                preceded(delims0, parse_ignore_spec),
+            ),
+            Vec::new,
+            |mut acc, (multi_comments, mut spec)| {
+                let mut multi_comments = multi_comments
+                    .into_iter()
+                    .filter_map(|i| match i {
+                        Sundry::Comment(c) => Some(c.into_fragment()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                if !multi_comments.is_empty() {
+                    multi_comments.extend_from_slice(&spec.comments[..]);
+                    spec.comments = multi_comments;
+                }
+                acc.push(spec);
+                acc
+            },
+        )(input)?;
+        specs.extend(ret);
+        let (input, (end_comments, close_paren, close_comment)) = parse_block_end(input)?;
+        comments.extend(end_comments.iter().chain(&close_comment));
+        block = Some(Block {
+            open_comment,
+            end_comments,
+            close_comment,
+            close_paren,
+        });
+        input
+    } else {
+        return Err(Err::Error(Error::new(input, nom::error::ErrorKind::Alt)));
+    };
+    let (input, pos) = position(input)?;
+    let end = location(&pos);
+    Ok((
+        input,
+        Context {
+            range: (start, end),
+            source: None,
+            comments,
+            suffix_comment: false,
+            value: Directive::Ignore { specs, block },
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Block, Context, Directive, Identifier, Location, Span};
+
+    use super::parse_ignore_directive;
+
+    #[test]
+    fn test_ignore() {
+        let s = r#"
+ignore ( // skipped
+    ./node_modules // js
+    // assets
+    "static files"
+)
+"#;
+        let (input, ret) = parse_ignore_directive(Span::new_extra(s, Default::default())).unwrap();
+        assert_eq!("", input.into_fragment());
+        assert_eq!(ret.comments, vec![" skipped"]);
+        let Directive::Ignore { specs, block } = ret.value else {
+            panic!("not an ignore directive");
+        };
+        assert_eq!(
+            block,
+            Some(Block {
+                open_comment: Some(" skipped"),
+                end_comments: vec![],
+                close_comment: None,
+                close_paren: Location {
+                    line: 6,
+                    column: 1,
+                    offset: 79
+                },
+            })
+        );
+        assert_eq!(
+            specs
+                .iter()
+                .map(|i| (&i.value, &i.comments))
+                .collect::<Vec<_>>(),
+            vec![
+                (&Identifier::Bare("./node_modules"), &vec![" js"]),
+                (
+                    &Identifier::Interpreted("static files".into()),
+                    &vec![" assets"]
+                ),
+            ]
+        );
+
+        let (_, ret) = parse_ignore_directive(Span::new_extra(
+            "ignore ./dir // generated\n",
+            Default::default(),
+        ))
+        .unwrap();
+        assert_eq!(
+            ret,
+            Context {
+                range: (
+                    Location {
+                        line: 1,
+                        column: 1,
+                        offset: 0
+                    },
+                    Location {
+                        line: 2,
+                        column: 1,
+                        offset: 26
+                    }
+                ),
+                source: None,
+                comments: vec![],
+                suffix_comment: false,
+                value: Directive::Ignore {
+                    specs: vec![Context {
+                        range: (
+                            Location {
+                                line: 1,
+                                column: 8,
+                                offset: 7
+                            },
+                            Location {
+                                line: 2,
+                                column: 1,
+                                offset: 26
+                            }
+                        ),
+                        source: None,
+                        comments: vec![" generated"],
+                        suffix_comment: true,
+                        value: Identifier::Bare("./dir"),
+                    }],
+                    block: None,
+                }
+            }
+        );
+    }
+}
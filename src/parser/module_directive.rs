@@ -1,8 +1,11 @@
-use crate::{Context, Directive, Location, Span, Sundry};
+use alloc::vec;
+
+use crate::{Context, Directive, Span, Sundry};
 
 use nom::{
     bytes::complete::tag,
     character::complete::char,
+    combinator::opt,
     error::{Error, ErrorKind},
     sequence::{pair, preceded},
     Err, IResult,
@@ -10,7 +13,8 @@ use nom::{
 use nom_locate::position;
 
 use super::{
-    delims0, delims1, parse_inline_comment, parse_module_path, parse_multiline_comments, quoted,
+    delims0, delims1, location, parse_block_end, parse_inline_comment, parse_line_end,
+    parse_multiline_comments, parse_spec_module_path,
 };
 
 pub fn parse_module_directive(input: Span) -> IResult<Span, Context<Directive>> {
@@ -21,35 +25,30 @@ pub fn parse_module_directive(input: Span) -> IResult<Span, Context<Directive>>
         _ => None,
     }));
     let (input, tmp) = preceded(delims0, tag("module"))(input)?;
-    let start = Location {
-        line: tmp.location_line(),
-        offset: tmp.location_offset(),
-    };
-    if let Ok((input, (path, comment))) = preceded(
+    let start = location(&tmp);
+    if let (input, Some((path, comment))) = opt(preceded(
         delims1,
-        pair(quoted(parse_module_path), parse_inline_comment),
-    )(input)
+        pair(parse_spec_module_path, parse_line_end),
+    ))(input)?
     {
+        let suffix_comment = matches!(comment, Sundry::Comment(_));
         if let Sundry::Comment(c) = comment {
             comments.push(*c.fragment());
         }
         let (input, pos) = position(input)?;
-        let end = Location {
-            line: pos.location_line(),
-            offset: pos.location_offset(),
-        };
+        let end = location(&pos);
         return Ok((
             input,
             Context {
                 range: (start, end),
+                source: None,
                 comments,
-                value: Directive::Module {
-                    module_path: path.fragment(),
-                },
+                suffix_comment,
+                value: Directive::Module { module_path: path },
             },
         ));
-    } else if let Ok((input, comment)) =
-        preceded(pair(delims0, char('(')), parse_inline_comment)(input)
+    } else if let (input, Some(comment)) =
+        opt(preceded(pair(delims0, char('(')), parse_inline_comment))(input)?
     {
         if let Sundry::Comment(c) = comment {
             comments.push(c.fragment());
@@ -59,35 +58,23 @@ pub fn parse_module_directive(input: Span) -> IResult<Span, Context<Directive>>
             Sundry::Comment(c) => Some(c.fragment()),
             _ => None,
         }));
-        let (input, (path, comment)) = preceded(
-            delims0,
-            pair(quoted(parse_module_path), parse_inline_comment),
-        )(input)?;
-        if let Sundry::Comment(c) = comment {
-            comments.push(c.fragment());
-        }
-        let (input, multi_comments) = parse_multiline_comments(input)?;
-        comments.extend(multi_comments.iter().filter_map(|i| match i {
-            Sundry::Comment(c) => Some(c.fragment()),
-            _ => None,
-        }));
-        let (input, comment) = preceded(pair(delims0, char(')')), parse_inline_comment)(input)?;
+        let (input, (path, comment)) =
+            preceded(delims0, pair(parse_spec_module_path, parse_line_end))(input)?;
         if let Sundry::Comment(c) = comment {
             comments.push(c.fragment());
         }
+        let (input, (end_comments, _, close_comment)) = parse_block_end(input)?;
+        comments.extend(end_comments.iter().chain(&close_comment));
         let (input, pos) = position(input)?;
-        let end = Location {
-            line: pos.location_line(),
-            offset: pos.location_offset(),
-        };
+        let end = location(&pos);
         return Ok((
             input,
             Context {
                 range: (start, end),
+                source: None,
                 comments,
-                value: Directive::Module {
-                    module_path: path.fragment(),
-                },
+                suffix_comment: false,
+                value: Directive::Module { module_path: path },
             },
         ));
     }
@@ -96,7 +83,7 @@ pub fn parse_module_directive(input: Span) -> IResult<Span, Context<Directive>>
 
 #[cfg(test)]
 mod tests {
-    use crate::{Context, Directive, Location, Span};
+    use crate::{Context, Directive, Identifier, Location, Span};
 
     use super::parse_module_directive;
 
@@ -112,7 +99,7 @@ module (
     // ghi
    ) // trailing
 "#;
-        let (input, ret) = parse_module_directive(Span::new(s)).unwrap();
+        let (input, ret) = parse_module_directive(Span::new_extra(s, Default::default())).unwrap();
         assert_eq!(*input.fragment(), "");
         assert_eq!(
             ret,
@@ -120,13 +107,16 @@ module (
                 range: (
                     Location {
                         line: 4,
-                        offset: 43,
+                        column: 1,
+                        offset: 43
                     },
                     Location {
                         line: 10,
-                        offset: 127,
+                        column: 1,
+                        offset: 127
                     },
                 ),
+                source: None,
                 comments: vec![
                     " heheda",
                     " Deprecated: use *** instead.",
@@ -136,10 +126,35 @@ module (
                     " ghi",
                     " trailing"
                 ],
+                suffix_comment: false,
                 value: Directive::Module {
-                    module_path: "rsdf/sf-f/s8._~"
+                    module_path: Identifier::Raw("rsdf/sf-f/s8._~")
                 }
             }
         );
     }
+
+    #[test]
+    fn test_module_escaped() {
+        for (s, path) in [
+            (
+                r#"module "foo\tbar""#,
+                Identifier::Interpreted("footbar".to_string()),
+            ),
+            (
+                r#"module "foo/bar""#,
+                Identifier::Interpreted("foo/bar".to_string()),
+            ),
+        ] {
+            let (input, ret) =
+                parse_module_directive(Span::new_extra(s, Default::default())).unwrap();
+            assert_eq!(*input.fragment(), "");
+            assert_eq!(ret.value, Directive::Module { module_path: path });
+        }
+        assert!(parse_module_directive(Span::new_extra(
+            r#"module "foo\ bar""#,
+            Default::default()
+        ))
+        .is_err());
+    }
 }
@@ -1,62 +1,70 @@
+use alloc::{vec, vec::Vec};
+
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::char,
+    combinator::{opt, peek, verify},
     error::Error,
     multi::fold_many0,
-    sequence::{delimited, pair, preceded, separated_pair, tuple},
+    sequence::{delimited, pair, preceded, separated_pair},
     Err, IResult, Parser,
 };
 use nom_locate::position;
 
 use crate::{
-    parser::{parse_identifier, parse_module_path},
-    Context, Directive, Location, ReplaceSpec, Replacement, Span, Sundry,
+    parser::{parse_identifier, parse_module_path_identifier, parse_spec_module_path},
+    Block, Context, Directive, Identifier, ReplaceSpec, Replacement, Span, Sundry,
 };
 
-use super::{delims0, delims1, parse_inline_comment, parse_multiline_comments, quoted};
+use super::{
+    delims0, delims1, location, parse_block_end, parse_empty_block, parse_inline_comment,
+    parse_line_end, parse_multiline_comments,
+};
 
 fn parse_replace_spec(input: Span) -> IResult<Span, Context<ReplaceSpec>> {
     let (input, pos) = position(input)?;
-    let start = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
-    let (input, path) = quoted(parse_module_path)(input)?;
+    let start = location(&pos);
+    let (input, path) = parse_spec_module_path(input)?;
     let (input, version) = alt((
-        delimited(delims0, tag("=>"), delims0).map(|_| None),
-        delimited(
-            delims1,
-            parse_identifier,
-            tuple((delims0, tag("=>"), delims0)),
-        )
-        .map(|i| Some(i)),
+        peek(preceded(delims0, tag("=>"))).map(|_| None),
+        preceded(delims1, parse_identifier).map(Some),
     ))(input)?;
+    let (input, arrow) = delimited(delims0, position, pair(tag("=>"), delims0))(input)?;
+    let arrow = location(&arrow);
+    // like Go, a directory replacement can't have a version, so `../local v1` is an error
+    // rather than a module replacement
     let (input, (replacement, comment)) = pair(
         alt((
-            separated_pair(quoted(parse_module_path), delims1, parse_identifier)
-                .map(|(p, v)| Replacement::Module((p.into_fragment(), v))),
-            parse_identifier.map(|i| Replacement::FilePath(i)),
+            separated_pair(
+                verify(parse_module_path_identifier, |p: &Identifier| {
+                    !p.is_file_path()
+                }),
+                delims1,
+                parse_identifier,
+            )
+            .map(|(p, v)| Replacement::Module((p, v))),
+            parse_identifier.map(Replacement::FilePath),
         )),
-        parse_inline_comment,
+        parse_line_end,
     )(input)?;
     let mut comments = vec![];
     if let Sundry::Comment(c) = comment {
         comments.push(c.into_fragment());
     }
     let (input, pos) = position(input)?;
-    let end = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
+    let end = location(&pos);
     Ok((
         input,
         Context {
             range: (start, end),
+            source: None,
+            suffix_comment: !comments.is_empty(),
             comments,
             value: ReplaceSpec {
-                module_path: path.into_fragment(),
-                version: version.map(|i| i),
+                module_path: path,
+                version,
+                arrow,
                 replacement,
             },
         },
@@ -71,20 +79,33 @@ pub fn parse_replace_directive(input: Span) -> IResult<Span, Context<Directive>>
         _ => None,
     }));
     let (input, tmp) = preceded(delims0, tag("replace"))(input)?;
-    let start = Location {
-        line: tmp.location_line(),
-        offset: tmp.location_offset(),
-    };
+    let start = location(&tmp);
     let mut specs = vec![];
-    let input = if let Ok((input, spec)) = preceded(delims1, parse_replace_spec)(input) {
+    let mut block = None;
+    let input = if let (input, Some(spec)) = opt(preceded(delims1, parse_replace_spec))(input)? {
         specs.push(spec);
         input
-    } else if let Ok((input, comment)) =
-        preceded(pair(delims0, char('(')), parse_inline_comment)(input)
+    } else if let (input, Some((close_paren, comment))) = opt(parse_empty_block)(input)? {
+        let close_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(close_comment);
+        block = Some(Block {
+            open_comment: None,
+            end_comments: vec![],
+            close_comment,
+            close_paren,
+        });
+        input
+    } else if let (input, Some(comment)) =
+        opt(preceded(pair(delims0, char('(')), parse_inline_comment))(input)?
     {
-        if let Sundry::Comment(c) = comment {
-            comments.push(c.into_fragment());
-        }
+        let open_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(open_comment);
         let (input, ret) = fold_many0(
             pair(
                 parse_multiline_comments,
@@ -107,63 +128,73 @@ pub fn parse_replace_directive(input: Span) -> IResult<Span, Context<Directive>>
                 acc
             },
         )(input)?;
-        specs.extend(ret.into_iter());
-        let (input, multi_comments) = parse_multiline_comments(input)?;
-        comments.extend(multi_comments.into_iter().filter_map(|i| match i {
-            Sundry::Comment(c) => Some(c.into_fragment()),
-            _ => None,
-        }));
-        let (input, comment) = preceded(pair(delims0, char(')')), parse_inline_comment)(input)?;
-        if let Sundry::Comment(c) = comment {
-            comments.push(c.into_fragment());
-        }
+        specs.extend(ret);
+        let (input, (end_comments, close_paren, close_comment)) = parse_block_end(input)?;
+        comments.extend(end_comments.iter().chain(&close_comment));
+        block = Some(Block {
+            open_comment,
+            end_comments,
+            close_comment,
+            close_paren,
+        });
         input
     } else {
         return Err(Err::Error(Error::new(input, nom::error::ErrorKind::Alt)));
     };
     let (input, pos) = position(input)?;
-    let end = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
+    let end = location(&pos);
     Ok((
         input,
         Context {
             range: (start, end),
+            source: None,
             comments,
-            value: Directive::Replace { specs },
+            suffix_comment: false,
+            value: Directive::Replace { specs, block },
         },
     ))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Context, Directive, Identifier, Location, ReplaceSpec, Replacement, Span};
+    use crate::{Block, Context, Directive, Identifier, Location, ReplaceSpec, Replacement, Span};
 
     use super::{parse_replace_directive, parse_replace_spec};
 
     #[test]
     fn test_replace_spec() {
         let s = "golang.org/x/net v1.2.3 => example.com/fork/net v1.4.5 // sfsdff";
-        let (input, ret) = parse_replace_spec(Span::new(s)).unwrap();
+        let (input, ret) = parse_replace_spec(Span::new_extra(s, Default::default())).unwrap();
         assert_eq!("", input.into_fragment());
         assert_eq!(
             ret,
             Context {
                 range: (
-                    Location { line: 1, offset: 0 },
                     Location {
                         line: 1,
+                        column: 1,
+                        offset: 0
+                    },
+                    Location {
+                        line: 1,
+                        column: 65,
                         offset: 64
                     }
                 ),
+                source: None,
                 comments: vec![" sfsdff"],
+                suffix_comment: true,
                 value: ReplaceSpec {
-                    module_path: "golang.org/x/net",
-                    version: Some(Identifier::Raw("v1.2.3")),
+                    module_path: Identifier::Bare("golang.org/x/net"),
+                    version: Some(Identifier::Bare("v1.2.3")),
+                    arrow: Location {
+                        line: 1,
+                        column: 25,
+                        offset: 24
+                    },
                     replacement: Replacement::Module((
-                        "example.com/fork/net",
-                        Identifier::Raw("v1.4.5")
+                        Identifier::Bare("example.com/fork/net"),
+                        Identifier::Bare("v1.4.5")
                     ))
                 }
             }
@@ -183,7 +214,7 @@ mod tests {
     golang.org/x/net => ./fork/net //dd
     // trailing comments
 ) // end specs"#;
-        let (input, ret) = parse_replace_directive(Span::new(s)).unwrap();
+        let (input, ret) = parse_replace_directive(Span::new_extra(s, Default::default())).unwrap();
         assert_eq!("", input.into_fragment());
         assert_eq!(
             ret,
@@ -191,39 +222,52 @@ mod tests {
                 range: (
                     Location {
                         line: 3,
+                        column: 9,
                         offset: 34
                     },
                     Location {
                         line: 11,
+                        column: 15,
                         offset: 323
                     }
                 ),
+                source: None,
                 comments: vec![
                     " start replace",
                     " start specs",
                     " trailing comments",
                     " end specs",
                 ],
+                suffix_comment: false,
                 value: Directive::Replace {
                     specs: vec![
                         Context {
                             range: (
                                 Location {
                                     line: 4,
+                                    column: 5,
                                     offset: 63
                                 },
                                 Location {
                                     line: 5,
+                                    column: 1,
                                     offset: 123
                                 }
                             ),
+                            source: None,
                             comments: vec!["aa"],
+                            suffix_comment: true,
                             value: ReplaceSpec {
-                                module_path: "golang.org/x/net",
-                                version: Some(Identifier::Raw("v1.2.3")),
+                                module_path: Identifier::Bare("golang.org/x/net"),
+                                version: Some(Identifier::Bare("v1.2.3")),
+                                arrow: Location {
+                                    line: 4,
+                                    column: 29,
+                                    offset: 87
+                                },
                                 replacement: Replacement::Module((
-                                    "example.com/fork/net",
-                                    Identifier::Raw("v1.4.5")
+                                    Identifier::Bare("example.com/fork/net"),
+                                    Identifier::Bare("v1.4.5")
                                 ))
                             }
                         },
@@ -231,20 +275,29 @@ mod tests {
                             range: (
                                 Location {
                                     line: 6,
+                                    column: 5,
                                     offset: 137
                                 },
                                 Location {
                                     line: 7,
+                                    column: 1,
                                     offset: 192
                                 }
                             ),
+                            source: None,
                             comments: vec![" bb", " bbb"],
+                            suffix_comment: true,
                             value: ReplaceSpec {
-                                module_path: "golang.org/x/net",
+                                module_path: Identifier::Bare("golang.org/x/net"),
                                 version: None,
+                                arrow: Location {
+                                    line: 6,
+                                    column: 22,
+                                    offset: 154
+                                },
                                 replacement: Replacement::Module((
-                                    "example.com/fork/net",
-                                    Identifier::Raw("v1.4.5")
+                                    Identifier::Bare("example.com/fork/net"),
+                                    Identifier::Bare("v1.4.5")
                                 ))
                             }
                         },
@@ -252,41 +305,161 @@ mod tests {
                             range: (
                                 Location {
                                     line: 8,
+                                    column: 5,
                                     offset: 201
                                 },
                                 Location {
                                     line: 9,
+                                    column: 1,
                                     offset: 244
                                 }
                             ),
+                            source: None,
                             comments: vec!["cc"],
+                            suffix_comment: true,
                             value: ReplaceSpec {
-                                module_path: "golang.org/x/net",
-                                version: Some(Identifier::Raw("v1.2.3")),
-                                replacement: Replacement::FilePath(Identifier::Raw("./fork/net"))
+                                module_path: Identifier::Bare("golang.org/x/net"),
+                                version: Some(Identifier::Bare("v1.2.3")),
+                                arrow: Location {
+                                    line: 8,
+                                    column: 29,
+                                    offset: 225
+                                },
+                                replacement: Replacement::FilePath(Identifier::Bare("./fork/net"))
                             }
                         },
                         Context {
                             range: (
                                 Location {
                                     line: 9,
+                                    column: 5,
                                     offset: 248
                                 },
                                 Location {
                                     line: 10,
+                                    column: 1,
                                     offset: 284
                                 }
                             ),
+                            source: None,
                             comments: vec!["dd"],
+                            suffix_comment: true,
                             value: ReplaceSpec {
-                                module_path: "golang.org/x/net",
+                                module_path: Identifier::Bare("golang.org/x/net"),
                                 version: None,
-                                replacement: Replacement::FilePath(Identifier::Raw("./fork/net"))
+                                arrow: Location {
+                                    line: 9,
+                                    column: 22,
+                                    offset: 265
+                                },
+                                replacement: Replacement::FilePath(Identifier::Bare("./fork/net"))
                             }
                         },
-                    ]
+                    ],
+                    block: Some(Block {
+                        open_comment: Some(" start specs"),
+                        end_comments: vec![" trailing comments"],
+                        close_comment: Some(" end specs"),
+                        close_paren: Location {
+                            line: 11,
+                            column: 1,
+                            offset: 309
+                        },
+                    }),
                 }
             }
         );
     }
+
+    #[test]
+    fn test_replace_file_path() {
+        for (s, path) in [
+            (r"golang.org/x/net => ..\fork\net", r"..\fork\net"),
+            (
+                r"golang.org/x/net v1.2.3 => C:\work\net // local",
+                r"C:\work\net",
+            ),
+            ("golang.org/x/net => /abs/net", "/abs/net"),
+        ] {
+            let (input, ret) = parse_replace_spec(Span::new_extra(s, Default::default())).unwrap();
+            assert_eq!("", input.into_fragment());
+            match ret.value.replacement {
+                Replacement::FilePath(p) => {
+                    assert_eq!(p, Identifier::Bare(path));
+                    assert!(p.is_file_path());
+                }
+                _ => panic!("{s}"),
+            }
+        }
+        for s in [
+            "golang.org/x/net v1.2.3 => ../local v1.2.3",
+            "golang.org/x/net => ./local v1.2.3",
+            "golang.org/x/net => /abs/net v1.2.3",
+        ] {
+            assert!(
+                parse_replace_spec(Span::new_extra(s, Default::default())).is_err(),
+                "{s}"
+            );
+        }
+        let (_, ret) = parse_replace_spec(Span::new_extra(
+            "golang.org/x/net => other.com/mod v1.0.0",
+            Default::default(),
+        ))
+        .unwrap();
+        assert_eq!(
+            ret.value.replacement,
+            Replacement::Module((
+                Identifier::Bare("other.com/mod"),
+                Identifier::Bare("v1.0.0")
+            ))
+        );
+        assert!(!Identifier::Bare("example.com/fork/net").is_file_path());
+        assert!(!Identifier::Bare(".hidden").is_file_path());
+        assert!(Identifier::Bare("..").is_file_path());
+    }
+
+    #[test]
+    fn test_replace_quoted_path() {
+        let s = r#""golang.org/x/net" v1.2.3 => `example.com/fork/net` "v1.4.5""#;
+        let (input, ret) = parse_replace_spec(Span::new_extra(s, Default::default())).unwrap();
+        assert_eq!("", input.into_fragment());
+        assert_eq!(
+            ret.value.module_path,
+            Identifier::Interpreted("golang.org/x/net".into())
+        );
+        assert_eq!(
+            ret.value.replacement,
+            Replacement::Module((
+                Identifier::Raw("example.com/fork/net"),
+                Identifier::Interpreted("v1.4.5".into())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_replace_quoted_separators() {
+        let s = "example.com/a \"v1=>x\" => `../a//b=>c` // d\n";
+        let (input, ret) = parse_replace_spec(Span::new_extra(s, Default::default())).unwrap();
+        assert_eq!("", input.into_fragment());
+        assert_eq!(
+            ret.value.version,
+            Some(Identifier::Interpreted("v1=>x".into()))
+        );
+        assert_eq!(
+            ret.value.replacement,
+            Replacement::FilePath(Identifier::Raw("../a//b=>c"))
+        );
+        assert_eq!(ret.comments, vec![" d"]);
+        let s = "`example.com/a` `v1//x` => example.com/b \"v1//y\"// d\n";
+        let (input, ret) = parse_replace_spec(Span::new_extra(s, Default::default())).unwrap();
+        assert_eq!("", input.into_fragment());
+        assert_eq!(
+            ret.value.replacement,
+            Replacement::Module((
+                Identifier::Bare("example.com/b"),
+                Identifier::Interpreted("v1//y".into())
+            ))
+        );
+        assert_eq!(ret.comments, vec![" d"]);
+    }
 }
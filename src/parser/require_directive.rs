@@ -1,46 +1,52 @@
+use alloc::{vec, vec::Vec};
+
 use nom::{
     bytes::complete::tag,
     character::complete::char,
-    error::Error,
+    combinator::opt,
+    error::{Error, ErrorKind},
     multi::fold_many0,
-    sequence::{pair, preceded, tuple},
+    sequence::{pair, preceded},
     Err, IResult,
 };
 use nom_locate::position;
 
 use crate::{
-    parser::{parse_identifier, parse_module_path},
-    Context, Directive, Identifier, Location, Span, Sundry,
+    parser::{parse_identifier, parse_spec_module_path},
+    Block, Context, Directive, Identifier, Span, Sundry,
 };
 
-use super::{delims0, delims1, parse_inline_comment, parse_multiline_comments, quoted};
+use super::{
+    delims0, delims1, location, parse_block_end, parse_empty_block, parse_inline_comment,
+    parse_line_end, parse_multiline_comments, starts_comment,
+};
 
-fn parse_require_spec(input: Span) -> IResult<Span, Context<(&str, Identifier)>> {
+fn parse_require_spec(
+    input: Span<'_>,
+) -> IResult<Span<'_>, Context<'_, (Identifier<'_>, Identifier<'_>)>> {
     let (input, pos) = position(input)?;
-    let start = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
-    let (input, (path, version, comment)) = tuple((
-        quoted(parse_module_path),
-        preceded(delims1, parse_identifier),
-        parse_inline_comment,
-    ))(input)?;
+    let start = location(&pos);
+    let (rest, path) = parse_spec_module_path(input)?;
+    let (after, _) = delims0(rest)?;
+    if after.is_empty() || after.fragment().starts_with(['\n', ')']) || starts_comment(&after) {
+        return Err(Err::Failure(Error::new(input, ErrorKind::Complete)));
+    }
+    let (input, (version, comment)) =
+        pair(preceded(delims1, parse_identifier), parse_line_end)(rest)?;
     let mut comments = vec![];
     if let Sundry::Comment(c) = comment {
         comments.push(c.into_fragment());
     }
     let (input, pos) = position(input)?;
-    let end = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
+    let end = location(&pos);
     Ok((
         input,
         Context {
             range: (start, end),
+            source: None,
+            suffix_comment: !comments.is_empty(),
             comments,
-            value: (path.into_fragment(), version),
+            value: (path, version),
         },
     ))
 }
@@ -53,20 +59,34 @@ pub fn parse_require_directive(input: Span) -> IResult<Span, Context<Directive>>
         _ => None,
     }));
     let (input, tmp) = preceded(delims0, tag("require"))(input)?;
-    let start = Location {
-        line: tmp.location_line(),
-        offset: tmp.location_offset(),
-    };
+    let start = location(&tmp);
     let mut specs = vec![];
-    let input = if let Ok((input, spec)) = preceded(delims1, parse_require_spec)(input) {
+    let mut block = None;
+    let mut group_starts = vec![];
+    let input = if let (input, Some(spec)) = opt(preceded(delims1, parse_require_spec))(input)? {
         specs.push(spec);
         input
-    } else if let Ok((input, comment)) =
-        preceded(pair(delims0, char('(')), parse_inline_comment)(input)
+    } else if let (input, Some((close_paren, comment))) = opt(parse_empty_block)(input)? {
+        let close_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(close_comment);
+        block = Some(Block {
+            open_comment: None,
+            end_comments: vec![],
+            close_comment,
+            close_paren,
+        });
+        input
+    } else if let (input, Some(comment)) =
+        opt(preceded(pair(delims0, char('(')), parse_inline_comment))(input)?
     {
-        if let Sundry::Comment(c) = comment {
-            comments.push(c.into_fragment());
-        }
+        let open_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(open_comment);
         let (input, ret) = fold_many0(
             pair(
                 parse_multiline_comments,
@@ -74,6 +94,9 @@ pub fn parse_require_directive(input: Span) -> IResult<Span, Context<Directive>>
             ),
             Vec::new,
             |mut acc, (multi_comments, mut spec)| {
+                if !acc.is_empty() && multi_comments.iter().any(|i| matches!(i, Sundry::Empty(_))) {
+                    group_starts.push(acc.len());
+                }
                 let mut multi_comments = multi_comments
                     .into_iter()
                     .filter_map(|i| match i {
@@ -89,40 +112,42 @@ pub fn parse_require_directive(input: Span) -> IResult<Span, Context<Directive>>
                 acc
             },
         )(input)?;
-        specs.extend(ret.into_iter());
-        let (input, multi_comments) = parse_multiline_comments(input)?;
-        comments.extend(multi_comments.into_iter().filter_map(|i| match i {
-            Sundry::Comment(c) => Some(c.into_fragment()),
-            _ => None,
-        }));
-        let (input, comment) = preceded(pair(delims0, char(')')), parse_inline_comment)(input)?;
-        if let Sundry::Comment(c) = comment {
-            comments.push(c.into_fragment());
-        }
+        specs.extend(ret);
+        let (input, (end_comments, close_paren, close_comment)) = parse_block_end(input)?;
+        comments.extend(end_comments.iter().chain(&close_comment));
+        block = Some(Block {
+            open_comment,
+            end_comments,
+            close_comment,
+            close_paren,
+        });
         input
     } else {
         return Err(Err::Error(Error::new(input, nom::error::ErrorKind::Alt)));
     };
     let (input, pos) = position(input)?;
-    let end = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
+    let end = location(&pos);
     Ok((
         input,
         Context {
             range: (start, end),
+            source: None,
             comments,
-            value: Directive::Require { specs },
+            suffix_comment: false,
+            value: Directive::Require {
+                specs,
+                group_starts,
+                block,
+            },
         },
     ))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Context, Directive, Identifier, Location, Span};
+    use crate::{Block, Context, Directive, Identifier, Location, Span};
 
-    use super::parse_require_directive;
+    use super::{parse_require_directive, parse_require_spec};
 
     #[test]
     fn test_require() {
@@ -135,7 +160,7 @@ mod tests {
     // end specs
  ) // end require
 "#;
-        let (input, ret) = parse_require_directive(Span::new(s)).unwrap();
+        let (input, ret) = parse_require_directive(Span::new_extra(s, Default::default())).unwrap();
         assert_eq!("", input.into_fragment());
         assert_eq!(
             ret,
@@ -143,52 +168,173 @@ mod tests {
                 range: (
                     Location {
                         line: 3,
+                        column: 9,
                         offset: 34
                     },
                     Location {
                         line: 9,
+                        column: 1,
                         offset: 176
                     }
                 ),
+                source: None,
                 comments: vec![
                     " start require",
                     " start specs",
                     " end specs",
                     " end require",
                 ],
+                suffix_comment: false,
                 value: Directive::Require {
                     specs: vec![
                         Context {
                             range: (
                                 Location {
                                     line: 4,
+                                    column: 5,
                                     offset: 63
                                 },
                                 Location {
                                     line: 5,
+                                    column: 1,
                                     offset: 102
                                 }
                             ),
+                            source: None,
                             comments: vec![" indirect"],
-                            value: ("golang.org/x/crypto", Identifier::Raw("v1.4.5"))
+                            suffix_comment: true,
+                            value: (
+                                Identifier::Bare("golang.org/x/crypto"),
+                                Identifier::Bare("v1.4.5")
+                            )
                         },
                         Context {
                             range: (
                                 Location {
                                     line: 6,
+                                    column: 5,
                                     offset: 116
                                 },
                                 Location {
                                     line: 7,
+                                    column: 1,
                                     offset: 141
                                 }
                             ),
+                            source: None,
                             comments: vec![" mm"],
-                            value: ("golang.org/x/text", Identifier::Raw("v1.6.7"))
+                            suffix_comment: false,
+                            value: (
+                                Identifier::Bare("golang.org/x/text"),
+                                Identifier::Bare("v1.6.7")
+                            )
                         },
-                    ]
+                    ],
+                    group_starts: vec![],
+                    block: Some(Block {
+                        open_comment: Some(" start specs"),
+                        end_comments: vec![" end specs"],
+                        close_comment: Some(" end require"),
+                        close_paren: Location {
+                            line: 8,
+                            column: 2,
+                            offset: 159
+                        },
+                    }),
                 }
             }
         );
     }
+
+    #[test]
+    fn test_require_groups() {
+        let s = r#"require (
+
+    example.com/a v1.0.0
+    example.com/b v1.0.0
+
+
+    example.com/c v1.0.0 // indirect
+
+    // comment
+    example.com/d v1.0.0 // indirect
+)
+"#;
+        let (_, ret) = parse_require_directive(Span::new_extra(s, Default::default())).unwrap();
+        let Directive::Require {
+            specs,
+            group_starts,
+            ..
+        } = ret.value
+        else {
+            panic!("not a require directive");
+        };
+        assert_eq!(specs.len(), 4);
+        assert_eq!(group_starts, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_quoted_path() {
+        for (s, path, version) in [
+            (
+                r#""example.com/x" v1.0.0"#,
+                Identifier::Interpreted("example.com/x".into()),
+                Identifier::Bare("v1.0.0"),
+            ),
+            (
+                r#"`example.com/x` "v1.0.0""#,
+                Identifier::Raw("example.com/x"),
+                Identifier::Interpreted("v1.0.0".into()),
+            ),
+            (
+                r#""example.com/y" `v1.0.0` // c"#,
+                Identifier::Interpreted("example.com/y".into()),
+                Identifier::Raw("v1.0.0"),
+            ),
+        ] {
+            let (input, ret) = parse_require_spec(Span::new_extra(s, Default::default())).unwrap();
+            assert_eq!("", input.into_fragment());
+            assert_eq!(ret.value, (path, version), "{s}");
+        }
+        assert!(parse_require_spec(Span::new_extra(
+            r#""not a path" v1.0.0"#,
+            Default::default()
+        ))
+        .is_err());
+        let s = "require (\n    \"example.com/x\" v1.0.0\n)\n";
+        let (_, ret) = parse_require_directive(Span::new_extra(s, Default::default())).unwrap();
+        assert!(matches!(
+            ret.value,
+            Directive::Require { specs, .. } if *specs[0].value.0 == *"example.com/x"
+        ));
+    }
+
+    #[test]
+    fn test_aligned_specs() {
+        // version columns aligned with tabs, and with mixed spaces and tabs
+        let s = "require (\n\tgithub.com/davecgh/go-spew\t\tv1.1.1 // indirect\n\tgolang.org/x/sys\t\t\tv0.15.0\n\tgopkg.in/yaml.v3 \t  \tv3.0.1\n)\n";
+        let (input, ret) = parse_require_directive(Span::new_extra(s, Default::default())).unwrap();
+        assert_eq!("", input.into_fragment());
+        let Directive::Require { specs, .. } = ret.value else {
+            panic!("not a require directive");
+        };
+        let lines: Vec<_> = s.split_inclusive('\n').collect();
+        let expected = [
+            ("github.com/davecgh/go-spew", "v1.1.1"),
+            ("golang.org/x/sys", "v0.15.0"),
+            ("gopkg.in/yaml.v3", "v3.0.1"),
+        ];
+        assert_eq!(specs.len(), expected.len());
+        for (i, (spec, (path, version))) in specs.iter().zip(expected).enumerate() {
+            assert_eq!(
+                spec.value,
+                (Identifier::Bare(path), Identifier::Bare(version))
+            );
+            // the range covers the line after the leading tab, up to the next line
+            let line_start = lines[..=i].iter().map(|i| i.len()).sum::<usize>();
+            assert_eq!(spec.range.0.offset, line_start + 1);
+            assert_eq!(spec.range.1.offset, line_start + lines[i + 1].len());
+            assert_eq!(spec.range.1.column, 1);
+        }
+    }
 }
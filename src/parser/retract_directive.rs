@@ -1,7 +1,10 @@
+use alloc::{vec, vec::Vec};
+
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::char,
+    combinator::opt,
     error::Error,
     multi::fold_many0,
     sequence::{delimited, pair, preceded, separated_pair, tuple},
@@ -9,16 +12,16 @@ use nom::{
 };
 use nom_locate::position;
 
-use crate::{parser::parse_identifier, Context, Directive, Location, RetractSpec, Span, Sundry};
+use crate::{parser::parse_identifier, Block, Context, Directive, RetractSpec, Span, Sundry};
 
-use super::{delims0, delims1, parse_inline_comment, parse_multiline_comments};
+use super::{
+    delims0, delims1, location, parse_block_end, parse_empty_block, parse_inline_comment,
+    parse_line_end, parse_multiline_comments,
+};
 
 fn parse_retract_spec(input: Span) -> IResult<Span, Context<RetractSpec>> {
     let (input, pos) = position(input)?;
-    let start = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
+    let start = location(&pos);
     let (input, (version, comment)) = pair(
         alt((
             delimited(
@@ -31,23 +34,22 @@ fn parse_retract_spec(input: Span) -> IResult<Span, Context<RetractSpec>> {
                 pair(delims0, char(']')),
             )
             .map(|(v1, v2)| RetractSpec::Range((v1, v2))),
-            parse_identifier.map(|i| RetractSpec::Version(i)),
+            parse_identifier.map(RetractSpec::Version),
         )),
-        parse_inline_comment,
+        parse_line_end,
     )(input)?;
     let mut comments = vec![];
     if let Sundry::Comment(c) = comment {
         comments.push(c.into_fragment());
     }
     let (input, pos) = position(input)?;
-    let end = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
+    let end = location(&pos);
     Ok((
         input,
         Context {
             range: (start, end),
+            source: None,
+            suffix_comment: !comments.is_empty(),
             comments,
             value: version,
         },
@@ -62,20 +64,33 @@ pub fn parse_retract_directive(input: Span) -> IResult<Span, Context<Directive>>
         _ => None,
     }));
     let (input, tmp) = preceded(delims0, tag("retract"))(input)?;
-    let start = Location {
-        line: tmp.location_line(),
-        offset: tmp.location_offset(),
-    };
+    let start = location(&tmp);
     let mut specs = vec![];
-    let input = if let Ok((input, spec)) = preceded(delims1, parse_retract_spec)(input) {
+    let mut block = None;
+    let input = if let (input, Some(spec)) = opt(preceded(delims1, parse_retract_spec))(input)? {
         specs.push(spec);
         input
-    } else if let Ok((input, comment)) =
-        preceded(pair(delims0, char('(')), parse_inline_comment)(input)
+    } else if let (input, Some((close_paren, comment))) = opt(parse_empty_block)(input)? {
+        let close_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(close_comment);
+        block = Some(Block {
+            open_comment: None,
+            end_comments: vec![],
+            close_comment,
+            close_paren,
+        });
+        input
+    } else if let (input, Some(comment)) =
+        opt(preceded(pair(delims0, char('(')), parse_inline_comment))(input)?
     {
-        if let Sundry::Comment(c) = comment {
-            comments.push(c.into_fragment());
-        }
+        let open_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(open_comment);
         let (input, ret) = fold_many0(
             pair(
                 parse_multiline_comments,
@@ -98,38 +113,36 @@ pub fn parse_retract_directive(input: Span) -> IResult<Span, Context<Directive>>
                 acc
             },
         )(input)?;
-        specs.extend(ret.into_iter());
-        let (input, multi_comments) = parse_multiline_comments(input)?;
-        comments.extend(multi_comments.into_iter().filter_map(|i| match i {
-            Sundry::Comment(c) => Some(c.into_fragment()),
-            _ => None,
-        }));
-        let (input, comment) = preceded(pair(delims0, char(')')), parse_inline_comment)(input)?;
-        if let Sundry::Comment(c) = comment {
-            comments.push(c.into_fragment());
-        }
+        specs.extend(ret);
+        let (input, (end_comments, close_paren, close_comment)) = parse_block_end(input)?;
+        comments.extend(end_comments.iter().chain(&close_comment));
+        block = Some(Block {
+            open_comment,
+            end_comments,
+            close_comment,
+            close_paren,
+        });
         input
     } else {
         return Err(Err::Error(Error::new(input, nom::error::ErrorKind::Alt)));
     };
     let (input, pos) = position(input)?;
-    let end = Location {
-        line: pos.location_line(),
-        offset: pos.location_offset(),
-    };
+    let end = location(&pos);
     Ok((
         input,
         Context {
             range: (start, end),
+            source: None,
             comments,
-            value: Directive::Retract { specs },
+            suffix_comment: false,
+            value: Directive::Retract { specs, block },
         },
     ))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Context, Directive, Identifier, Location, RetractSpec, Span};
+    use crate::{Block, Context, Directive, Identifier, Location, RetractSpec, Span};
 
     use super::parse_retract_directive;
 
@@ -144,7 +157,7 @@ mod tests {
     // end specs
 ) // end retract
 "#;
-        let (input, ret) = parse_retract_directive(Span::new(s)).unwrap();
+        let (input, ret) = parse_retract_directive(Span::new_extra(s, Default::default())).unwrap();
         assert_eq!("", input.into_fragment());
         assert_eq!(
             ret,
@@ -152,55 +165,99 @@ mod tests {
                 range: (
                     Location {
                         line: 3,
+                        column: 9,
                         offset: 34
                     },
                     Location {
                         line: 9,
+                        column: 1,
                         offset: 150
                     }
                 ),
+                source: None,
                 comments: vec![
                     " start retract",
                     " start specs",
                     " end specs",
                     " end retract",
                 ],
+                suffix_comment: false,
                 value: Directive::Retract {
                     specs: vec![
                         Context {
                             range: (
                                 Location {
                                     line: 4,
+                                    column: 5,
                                     offset: 63
                                 },
                                 Location {
                                     line: 5,
+                                    column: 1,
                                     offset: 77
                                 }
                             ),
+                            source: None,
                             comments: vec![" aaa"],
-                            value: RetractSpec::Version(Identifier::Raw("v1.0.0"))
+                            suffix_comment: true,
+                            value: RetractSpec::Version(Identifier::Bare("v1.0.0"))
                         },
                         Context {
                             range: (
                                 Location {
                                     line: 6,
+                                    column: 5,
                                     offset: 92
                                 },
                                 Location {
                                     line: 7,
+                                    column: 1,
                                     offset: 116
                                 }
                             ),
+                            source: None,
                             comments: vec![" bbb", " ccc"],
+                            suffix_comment: true,
                             value: RetractSpec::Range((
-                                Identifier::Raw("v1.0.0"),
-                                Identifier::Raw("v1.9.9")
+                                Identifier::Bare("v1.0.0"),
+                                Identifier::Bare("v1.9.9")
                             ))
                         },
-                    ]
+                    ],
+                    block: Some(Block {
+                        open_comment: Some(" start specs"),
+                        end_comments: vec![" end specs"],
+                        close_comment: Some(" end retract"),
+                        close_paren: Location {
+                            line: 8,
+                            column: 1,
+                            offset: 133
+                        },
+                    }),
                 }
             }
         );
     }
+
+    #[test]
+    fn test_retract_range_quoted() {
+        for s in [
+            "retract [ \"v1.0.0\" , \"v1.9.9\" ] // c\n",
+            "retract\t[\t`v1.0.0`\t,\t\"v1.9.9\"\t]\n",
+            "retract [\"v1.0.0\",v1.9.9]\n",
+            "retract (\n\t[  \"v1.0.0\"  ,  \"v1.9.9\"  ]  // c\n)\n",
+        ] {
+            let (input, ret) =
+                parse_retract_directive(Span::new_extra(s, Default::default())).unwrap();
+            assert_eq!("", input.into_fragment(), "{s:?}");
+            let Directive::Retract { specs, .. } = ret.value else {
+                unreachable!()
+            };
+            assert_eq!(specs.len(), 1);
+            let RetractSpec::Range((low, high)) = &specs[0].value else {
+                panic!("{s:?}")
+            };
+            assert_eq!((&**low, &**high), ("v1.0.0", "v1.9.9"));
+        }
+    }
 }
@@ -1,3 +1,5 @@
+use alloc::vec;
+
 use nom::{
     bytes::complete::tag,
     sequence::{delimited, pair, preceded},
@@ -5,9 +7,11 @@ use nom::{
 };
 use nom_locate::position;
 
-use crate::{Context, Directive, Location, Span, Sundry};
+use crate::{Context, Directive, Span, Sundry};
 
-use super::{delims0, delims1, parse_identifier, parse_inline_comment, parse_multiline_comments};
+use super::{
+    delims0, delims1, location, parse_identifier, parse_line_end, parse_multiline_comments,
+};
 
 pub fn parse_toolchain_directive(input: Span) -> IResult<Span, Context<Directive>> {
     let mut comments = vec![];
@@ -19,26 +23,20 @@ pub fn parse_toolchain_directive(input: Span) -> IResult<Span, Context<Directive
     let (input, start) = position(input)?;
     let (input, (name, comment)) = preceded(
         delimited(delims0, tag("toolchain"), delims1),
-        pair(parse_identifier, parse_inline_comment),
+        pair(parse_identifier, parse_line_end),
     )(input)?;
     let (input, end) = position(input)?;
+    let suffix_comment = matches!(comment, Sundry::Comment(_));
     if let Sundry::Comment(c) = comment {
         comments.push(*c.fragment());
     }
     Ok((
         input,
         Context {
+            source: None,
             comments,
-            range: (
-                Location {
-                    line: start.location_line(),
-                    offset: start.location_offset(),
-                },
-                Location {
-                    line: end.location_line(),
-                    offset: end.location_offset(),
-                },
-            ),
+            range: (location(&start), location(&end)),
+            suffix_comment,
             value: Directive::Toolchain { name },
         },
     ))
@@ -56,7 +54,8 @@ mod tests {
 // heheda
 toolchain go1.21.3+auto // inline
 "#;
-        let (input, ret) = parse_toolchain_directive(Span::new(s)).unwrap();
+        let (input, ret) =
+            parse_toolchain_directive(Span::new_extra(s, Default::default())).unwrap();
         assert_eq!("", *input.fragment());
         assert_eq!(
             ret,
@@ -64,16 +63,20 @@ toolchain go1.21.3+auto // inline
                 range: (
                     Location {
                         line: 3,
+                        column: 1,
                         offset: 11
                     },
                     Location {
                         line: 4,
+                        column: 1,
                         offset: 45
                     }
                 ),
+                source: None,
                 comments: vec![" heheda", " inline"],
+                suffix_comment: true,
                 value: Directive::Toolchain {
-                    name: Identifier::Raw("go1.21.3+auto")
+                    name: Identifier::Bare("go1.21.3+auto")
                 }
             }
         )
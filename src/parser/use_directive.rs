@@ -0,0 +1,245 @@
+use alloc::{vec, vec::Vec};
+
+use nom::{
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::opt,
+    error::Error,
+    multi::fold_many0,
+    sequence::{pair, preceded},
+    Err, IResult,
+};
+use nom_locate::position;
+
+use crate::{parser::parse_identifier, Block, Context, Directive, Identifier, Span, Sundry};
+
+use super::{
+    delims0, delims1, location, parse_block_end, parse_empty_block, parse_inline_comment,
+    parse_line_end, parse_multiline_comments,
+};
+
+// a directory of the workspace, e.g. `./tools`
+fn parse_use_spec(input: Span<'_>) -> IResult<Span<'_>, Context<'_, Identifier<'_>>> {
+    let (input, pos) = position(input)?;
+    let start = location(&pos);
+    let (input, (path, comment)) = pair(parse_identifier, parse_line_end)(input)?;
+    let mut comments = vec![];
+    if let Sundry::Comment(c) = comment {
+        comments.push(c.into_fragment());
+    }
+    let (input, pos) = position(input)?;
+    let end = location(&pos);
+    Ok((
+        input,
+        Context {
+            range: (start, end),
+            source: None,
+            suffix_comment: !comments.is_empty(),
+            comments,
+            value: path,
+        },
+    ))
+}
+
+pub fn parse_use_directive(input: Span) -> IResult<Span, Context<Directive>> {
+    let mut comments = vec![];
+    let (input, multi_comments) = parse_multiline_comments(input)?;
+    comments.extend(multi_comments.into_iter().filter_map(|i| match i {
+        Sundry::Comment(c) => Some(c.into_fragment()),
+        _ => None,
+    }));
+    let (input, tmp) = preceded(delims0, tag("use"))(input)?;
+    let start = location(&tmp);
+    let mut specs = vec![];
+    let mut block = None;
+    let input = if let (input, Some(spec)) = opt(preceded(delims1, parse_use_spec))(input)? {
+        specs.push(spec);
+        input
+    } else if let (input, Some((close_paren, comment))) = opt(parse_empty_block)(input)? {
+        let close_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(close_comment);
+        block = Some(Block {
+            open_comment: None,
+            end_comments: vec![],
+            close_comment,
+            close_paren,
+        });
+        input
+    } else if let (input, Some(comment)) =
+        opt(preceded(pair(delims0, char('(')), parse_inline_comment))(input)?
+    {
+        let open_comment = match comment {
+            Sundry::Comment(c) => Some(c.into_fragment()),
+            _ => None,
+        };
+        comments.extend(open_comment);
+        let (input, ret) = fold_many0(
+            pair(parse_multiline_comments, preceded(delims0, parse_use_spec)),
+            Vec::new,
+            |mut acc, (multi_comments, mut spec)| {
+                let mut multi_comments = multi_comments
+                    .into_iter()
+                    .filter_map(|i| match i {
+                        Sundry::Comment(c) => Some(c.into_fragment()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                if !multi_comments.is_empty() {
+                    multi_comments.extend_from_slice(&spec.comments[..]);
+                    spec.comments = multi_comments;
+                }
+                acc.push(spec);
+                acc
+            },
+        )(input)?;
+        specs.extend(ret);
+        let (input, (end_comments, close_paren, close_comment)) = parse_block_end(input)?;
+        comments.extend(end_comments.iter().chain(&close_comment));
+        block = Some(Block {
+            open_comment,
+            end_comments,
+            close_comment,
+            close_paren,
+        });
+        input
+    } else {
+        return Err(Err::Error(Error::new(input, nom::error::ErrorKind::Alt)));
+    };
+    let (input, pos) = position(input)?;
+    let end = location(&pos);
+    Ok((
+        input,
+        Context {
+            range: (start, end),
+            source: None,
+            comments,
+            suffix_comment: false,
+            value: Directive::Use { specs, block },
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Block, Context, Directive, Identifier, Location, Span};
+
+    use super::parse_use_directive;
+
+    #[test]
+    fn test_use() {
+        let s = r#"
+        // start use
+        use ( // start specs
+    ./a // local
+    // mm
+    "../b c"
+    // end specs
+ ) // end use
+"#;
+        let (input, ret) = parse_use_directive(Span::new_extra(s, Default::default())).unwrap();
+        assert_eq!("", input.into_fragment());
+        assert_eq!(
+            ret.comments,
+            vec![" start use", " start specs", " end specs", " end use"]
+        );
+        assert_eq!(
+            ret.range.0,
+            Location {
+                line: 3,
+                column: 9,
+                offset: 30
+            }
+        );
+        let Directive::Use { specs, block } = ret.value else {
+            panic!("not a use directive");
+        };
+        assert_eq!(
+            block,
+            Some(Block {
+                open_comment: Some(" start specs"),
+                end_comments: vec![" end specs"],
+                close_comment: Some(" end use"),
+                close_paren: Location {
+                    line: 8,
+                    column: 2,
+                    offset: 109
+                },
+            })
+        );
+        assert_eq!(
+            specs
+                .iter()
+                .map(|i| (&i.value, &i.comments))
+                .collect::<Vec<_>>(),
+            vec![
+                (&Identifier::Bare("./a"), &vec![" local"]),
+                (&Identifier::Interpreted("../b c".into()), &vec![" mm"]),
+            ]
+        );
+        assert_eq!(
+            specs[0].range,
+            (
+                Location {
+                    line: 4,
+                    column: 5,
+                    offset: 55
+                },
+                Location {
+                    line: 5,
+                    column: 1,
+                    offset: 68
+                }
+            )
+        );
+
+        let (_, ret) = parse_use_directive(Span::new_extra(
+            "use ./tools // tools\n",
+            Default::default(),
+        ))
+        .unwrap();
+        assert_eq!(
+            ret,
+            Context {
+                range: (
+                    Location {
+                        line: 1,
+                        column: 1,
+                        offset: 0
+                    },
+                    Location {
+                        line: 2,
+                        column: 1,
+                        offset: 21
+                    }
+                ),
+                source: None,
+                comments: vec![],
+                suffix_comment: false,
+                value: Directive::Use {
+                    specs: vec![Context {
+                        range: (
+                            Location {
+                                line: 1,
+                                column: 5,
+                                offset: 4
+                            },
+                            Location {
+                                line: 2,
+                                column: 1,
+                                offset: 21
+                            }
+                        ),
+                        source: None,
+                        comments: vec![" tools"],
+                        suffix_comment: true,
+                        value: Identifier::Bare("./tools"),
+                    }],
+                    block: None,
+                }
+            }
+        );
+    }
+}
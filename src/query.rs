@@ -0,0 +1,876 @@
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+
+use core::cmp::Ordering;
+
+use crate::{
+    version::{version_cmp, GoVersion},
+    Context, Directive, DirectiveKind, GoMod, Identifier, Range, ReplaceSpec, Replacement,
+    RetractSpec,
+};
+
+/// Where a module path appears in a go.mod file, see [`GoMod::module_paths`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModuleRole {
+    /// The path of the `module` directive
+    Main,
+    Required,
+    Excluded,
+    /// Left side of a `replace`
+    ReplacedFrom,
+    /// Right side of a `replace`, when it's a module rather than a file path
+    ReplacedTo,
+}
+
+/// The `module`, `go` and `toolchain` directives, which may each appear once, see
+/// [`GoMod::singletons`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SingletonView<'g, 'a> {
+    /// First `module` directive
+    pub module: Option<&'g Context<'a, Directive<'a>>>,
+    /// First `go` directive
+    pub go: Option<&'g Context<'a, Directive<'a>>>,
+    /// First `toolchain` directive
+    pub toolchain: Option<&'g Context<'a, Directive<'a>>>,
+    pub module_path: Option<&'g str>,
+    pub go_version: Option<&'g str>,
+    pub toolchain_name: Option<&'g str>,
+    /// Each kind appearing more than once, with the ranges of all its directives in source
+    /// order. Go rejects such files.
+    pub duplicates: Vec<(DirectiveKind, Vec<Range>)>,
+}
+
+/// The innermost node at an offset, see [`GoMod::node_at_offset`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeRef<'g, 'a> {
+    Directive(&'g Context<'a, Directive<'a>>),
+    /// The spec at `index` in the specs of `directive`
+    Spec {
+        directive: &'g Context<'a, Directive<'a>>,
+        index: usize,
+    },
+}
+
+fn spec_ranges<'g>(directive: &'g Directive) -> Vec<&'g Range> {
+    fn ranges<'g, T>(specs: &'g [Context<T>]) -> Vec<&'g Range> {
+        specs.iter().map(|i| &i.range).collect()
+    }
+    match directive {
+        Directive::Module { .. } | Directive::Go { .. } | Directive::Toolchain { .. } => Vec::new(),
+        Directive::Require { specs, .. } | Directive::Exclude { specs, .. } => ranges(specs),
+        Directive::Godebug { specs, .. } => ranges(specs),
+        Directive::Replace { specs, .. } => ranges(specs),
+        Directive::Retract { specs, .. } => ranges(specs),
+        Directive::Use { specs, .. } | Directive::Ignore { specs, .. } => ranges(specs),
+    }
+}
+
+/// Counts over a go.mod file, see [`GoMod::summary`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GoModSummary<'g> {
+    /// Require specs, of single-line and block directives alike
+    pub requires: usize,
+    /// Replace specs, of single-line and block directives alike
+    pub replaces: usize,
+    pub has_toolchain: bool,
+    /// Version of the first `go` directive
+    pub go_version: Option<&'g str>,
+    /// Directives written as a block, including empty ones
+    pub block_directives: usize,
+    pub single_line_directives: usize,
+}
+
+/// What a required module resolves to, see [`GoMod::effective_version`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EffectiveVersion<'g> {
+    /// Module path, or directory for a local replacement
+    pub path: &'g str,
+    /// `None` for a local replacement
+    pub version: Option<&'g str>,
+    /// The version in `require`, before any replacement
+    pub required: &'g str,
+    pub replaced: bool,
+    /// Whether the replacement is a local directory
+    pub local: bool,
+}
+
+/// A require behind the latest version of its module, see [`GoMod::outdated`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outdated<'g, 'l> {
+    pub path: &'g str,
+    pub current: &'g str,
+    pub latest: &'l str,
+    /// Location of the require spec
+    pub range: Range,
+}
+
+/// Like Go, whether the comment on the line of a require is `// indirect`, or starts with
+/// `// indirect;` followed by other notes. Comments on the lines before it don't count.
+pub(crate) fn is_indirect(comments: &[&str], suffix_comment: bool) -> bool {
+    let Some(comment) = comments.last().filter(|_| suffix_comment) else {
+        return false;
+    };
+    let mut words = comment.split_whitespace();
+    matches!(
+        (words.next(), words.next()),
+        (Some("indirect"), None) | (Some("indirect;"), Some(_))
+    )
+}
+
+impl<'a> GoMod<'a> {
+    /// Specs of all `require` directives, in source order
+    pub fn requires(&self) -> impl Iterator<Item = &Context<'a, (Identifier<'a>, Identifier<'a>)>> {
+        self.iter().flat_map(|i| match &i.value {
+            Directive::Require { specs, .. } => specs.iter(),
+            _ => [].iter(),
+        })
+    }
+
+    /// Requires without a `// indirect` comment, i.e. imported by the main module
+    pub fn direct_requires(
+        &self,
+    ) -> impl Iterator<Item = &Context<'a, (Identifier<'a>, Identifier<'a>)>> {
+        self.requires()
+            .filter(|i| !is_indirect(&i.comments, i.suffix_comment))
+    }
+
+    /// Requires marked `// indirect`, in single-line directives and blocks alike
+    pub fn indirect_requires(
+        &self,
+    ) -> impl Iterator<Item = &Context<'a, (Identifier<'a>, Identifier<'a>)>> {
+        self.requires()
+            .filter(|i| is_indirect(&i.comments, i.suffix_comment))
+    }
+
+    /// Requires whose version is lower than `latest(path)` by semver precedence, in source
+    /// order. Modules `latest` knows nothing about, and invalid versions, are skipped.
+    ///
+    /// `latest` is typically a map lookup, e.g. `|path| map.get(path).map(String::as_str)`.
+    pub fn outdated<'l>(&self, latest: impl Fn(&str) -> Option<&'l str>) -> Vec<Outdated<'_, 'l>> {
+        self.requires()
+            .filter_map(|i| {
+                let (path, current) = (&*i.value.0, &*i.value.1);
+                let latest = latest(path)?;
+                (version_cmp(current, latest) == Some(Ordering::Less)).then(|| Outdated {
+                    path,
+                    current,
+                    latest,
+                    range: i.range.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// The spec or directive whose range holds the byte `offset`, a spec being preferred to
+    /// its directive. Ranges are half-open, and comments before a directive or spec aren't in
+    /// its range.
+    pub fn node_at_offset(&self, offset: usize) -> Option<NodeRef<'_, 'a>> {
+        let contains = |range: &Range| (range.0.offset..range.1.offset).contains(&offset);
+        let directive = self.iter().find(|i| contains(&i.range))?;
+        Some(
+            match spec_ranges(&directive.value).into_iter().position(contains) {
+                Some(index) => NodeRef::Spec { directive, index },
+                None => NodeRef::Directive(directive),
+            },
+        )
+    }
+
+    /// Spec and directive counts, for stats
+    pub fn summary(&self) -> GoModSummary<'_> {
+        let singletons = self.singletons();
+        let block_directives = self.iter().filter(|i| i.value.block().is_some()).count();
+        GoModSummary {
+            requires: self.requires().count(),
+            replaces: self.replaces().count(),
+            has_toolchain: singletons.toolchain.is_some(),
+            go_version: singletons.go_version,
+            block_directives,
+            single_line_directives: self.len() - block_directives,
+        }
+    }
+
+    /// Specs of all `godebug` directives, in source order
+    pub fn godebugs(&self) -> impl Iterator<Item = &Context<'a, (&'a str, &'a str)>> {
+        self.iter().flat_map(|i| match &i.value {
+            Directive::Godebug { specs, .. } => specs.iter(),
+            _ => [].iter(),
+        })
+    }
+
+    /// `(key, value)` of all `godebug` specs in source order, a repeated key included each
+    /// time, see [`GoMod::duplicate_godebug_keys`]
+    pub fn godebug_settings(&self) -> Vec<(&'a str, &'a str)> {
+        self.godebugs().map(|i| i.value).collect()
+    }
+
+    /// Value of the `default` godebug key, e.g. `go1.21` for `godebug default=go1.21`, which sets
+    /// all godebug defaults to those of that Go release. The last one wins if set more than once.
+    pub fn godebug_default(&self) -> Option<&'a str> {
+        self.godebugs()
+            .filter(|i| i.value.0 == "default")
+            .last()
+            .map(|i| i.value.1)
+    }
+
+    /// Each `godebug` key set more than once, with the ranges of its specs in source order.
+    /// Keys are listed in the order they first appear.
+    pub fn duplicate_godebug_keys(&self) -> Vec<(&'a str, Vec<Range>)> {
+        let mut keys: Vec<(&'a str, Vec<Range>)> = Vec::new();
+        for spec in self.godebugs() {
+            match keys.iter_mut().find(|(key, _)| *key == spec.value.0) {
+                Some((_, ranges)) => ranges.push(spec.range.clone()),
+                None => keys.push((spec.value.0, vec![spec.range.clone()])),
+            }
+        }
+        keys.retain(|(_, ranges)| ranges.len() > 1);
+        keys
+    }
+
+    /// Specs of all `exclude` directives, in source order
+    pub fn excludes(&self) -> impl Iterator<Item = &Context<'a, (Identifier<'a>, Identifier<'a>)>> {
+        self.iter().flat_map(|i| match &i.value {
+            Directive::Exclude { specs, .. } => specs.iter(),
+            _ => [].iter(),
+        })
+    }
+
+    /// Versions of `module_path` excluded, in source order. A version excluded twice is listed
+    /// twice.
+    pub fn excluded_versions(&self, module_path: &str) -> Vec<&str> {
+        self.excludes()
+            .filter(|i| *i.value.0 == *module_path)
+            .map(|i| &*i.value.1)
+            .collect()
+    }
+
+    /// Specs of all `replace` directives, in source order
+    pub fn replaces(&self) -> impl Iterator<Item = &Context<'a, ReplaceSpec<'a>>> {
+        self.iter().flat_map(|i| match &i.value {
+            Directive::Replace { specs, .. } => specs.iter(),
+            _ => [].iter(),
+        })
+    }
+
+    /// Replaces pointing at a local directory, which shouldn't ship in a published module, see
+    /// [`ReplaceSpec::is_local`]. This includes a module replacement whose path is a file path,
+    /// as can be built in code.
+    pub fn local_replaces(&self) -> impl Iterator<Item = &Context<'a, ReplaceSpec<'a>>> {
+        self.replaces().filter(|i| i.value.is_local())
+    }
+
+    /// Replacement targets keyed by the module path and optional version on the left side of
+    /// each replace, so `m => ...` and `m v1.0.0 => ...` are both kept. Of duplicate replaces,
+    /// the last one wins, as in Go and [`GoMod::effective_version`].
+    pub fn replacement_map(&self) -> BTreeMap<(&str, Option<&str>), &Replacement<'a>> {
+        let mut map = BTreeMap::new();
+        for spec in self.replaces() {
+            let key = (&*spec.value.module_path, spec.value.version.as_deref());
+            map.insert(key, &spec.value.replacement);
+        }
+        map
+    }
+
+    /// Resolve the required version of `module_path` against the replaces, as Go does: a
+    /// replace of that exact version wins over one without a version on its left side.
+    ///
+    /// When required several times, the highest version is used. `None` if it isn't required.
+    pub fn effective_version(&self, module_path: &str) -> Option<EffectiveVersion<'_>> {
+        let (path, required) = self
+            .requires()
+            .filter(|i| *i.value.0 == *module_path)
+            .map(|i| (&*i.value.0, &*i.value.1))
+            .reduce(|a, b| match version_cmp(b.1, a.1) {
+                Some(Ordering::Greater) => b,
+                _ => a,
+            })?;
+        let replace = self
+            .replaces()
+            .map(|i| &i.value)
+            .filter(|i| *i.module_path == *module_path)
+            .filter(|i| i.version.as_deref().is_none_or(|v| v == required))
+            .max_by_key(|i| i.version.is_some());
+        Some(match replace {
+            Some(replace) => EffectiveVersion {
+                path: replace.target_path(),
+                version: replace.target_version(),
+                required,
+                replaced: true,
+                local: replace.is_local(),
+            },
+            None => EffectiveVersion {
+                path,
+                version: Some(required),
+                required,
+                replaced: false,
+                local: false,
+            },
+        })
+    }
+
+    /// Version of the first `go` directive, `None` if there's none or it's invalid
+    pub fn go_version(&self) -> Option<GoVersion<'_>> {
+        self.iter()
+            .find_map(|i| match &i.value {
+                Directive::Go { version } => Some(version),
+                _ => None,
+            })
+            .and_then(|i| GoVersion::parse(i))
+    }
+
+    /// Version of the first `toolchain` directive, e.g. `go1.21rc1`
+    pub fn toolchain_version(&self) -> Option<GoVersion<'_>> {
+        self.iter()
+            .find_map(|i| match &i.value {
+                Directive::Toolchain { name } => Some(name),
+                _ => None,
+            })
+            .and_then(|i| GoVersion::parse_toolchain(i))
+    }
+
+    /// Major, minor and patch of the first `go` directive, the patch defaulting to 0 as in
+    /// `go 1.21` or `go 1.21rc1`. See [`GoMod::go_version`] for the pre-release part.
+    pub fn go_version_parts(&self) -> Option<(u16, u16, u16)> {
+        self.go_version()
+            .map(|i| (i.major, i.minor, i.patch.unwrap_or(0)))
+    }
+
+    /// The highest Go version implied by the directives present: `toolchain` needs Go 1.21,
+    /// `godebug` Go 1.23 and `ignore` Go 1.25. `None` when no directive implies one.
+    pub fn implied_min_go(&self) -> Option<(u16, u16)> {
+        self.iter().filter_map(|i| i.value.kind().min_go()).max()
+    }
+
+    /// The `module`, `go` and `toolchain` directives, and those repeated
+    pub fn singletons(&self) -> SingletonView<'_, 'a> {
+        let mut view = SingletonView {
+            module: None,
+            go: None,
+            toolchain: None,
+            module_path: None,
+            go_version: None,
+            toolchain_name: None,
+            duplicates: Vec::new(),
+        };
+        for kind in [
+            DirectiveKind::Module,
+            DirectiveKind::Go,
+            DirectiveKind::Toolchain,
+        ] {
+            let ranges: Vec<_> = self
+                .iter()
+                .filter(|i| i.value.kind() == kind)
+                .map(|i| i.range.clone())
+                .collect();
+            if ranges.len() > 1 {
+                view.duplicates.push((kind, ranges));
+            }
+        }
+        for directive in self.iter() {
+            match &directive.value {
+                Directive::Module { module_path } if view.module.is_none() => {
+                    view.module = Some(directive);
+                    view.module_path = Some(module_path);
+                }
+                Directive::Go { version } if view.go.is_none() => {
+                    view.go = Some(directive);
+                    view.go_version = Some(version);
+                }
+                Directive::Toolchain { name } if view.toolchain.is_none() => {
+                    view.toolchain = Some(directive);
+                    view.toolchain_name = Some(name);
+                }
+                _ => {}
+            }
+        }
+        view
+    }
+
+    /// Every token of the file in source order, e.g. to check their [`Identifier::quote_style`]
+    /// against [`crate::needs_quoting`]. `godebug` keys and values aren't identifiers, so they
+    /// aren't included.
+    pub fn identifiers(&self) -> impl Iterator<Item = &Identifier<'a>> {
+        let mut ret = Vec::new();
+        for directive in self.iter() {
+            match &directive.value {
+                Directive::Module { module_path: i }
+                | Directive::Go { version: i }
+                | Directive::Toolchain { name: i } => ret.push(i),
+                Directive::Require { specs, .. } | Directive::Exclude { specs, .. } => {
+                    ret.extend(specs.iter().flat_map(|i| [&i.value.0, &i.value.1]))
+                }
+                Directive::Replace { specs, .. } => {
+                    for spec in specs {
+                        ret.push(&spec.value.module_path);
+                        ret.extend(&spec.value.version);
+                        match &spec.value.replacement {
+                            Replacement::FilePath(path) => ret.push(path),
+                            Replacement::Module((path, version)) => ret.extend([path, version]),
+                        }
+                    }
+                }
+                Directive::Retract { specs, .. } => {
+                    for spec in specs {
+                        match &spec.value {
+                            RetractSpec::Version(version) => ret.push(version),
+                            RetractSpec::Range((low, high)) => ret.extend([low, high]),
+                        }
+                    }
+                }
+                Directive::Use { specs, .. } | Directive::Ignore { specs, .. } => {
+                    ret.extend(specs.iter().map(|i| &i.value))
+                }
+                Directive::Godebug { .. } => {}
+            }
+        }
+        ret.into_iter()
+    }
+
+    /// Every module path referenced in the file, in source order.
+    ///
+    /// Paths are yielded as many times as they appear, de-duplicating is up to the caller.
+    pub fn module_paths(&self) -> impl Iterator<Item = (&str, ModuleRole)> {
+        let mut ret = Vec::new();
+        for directive in self.iter() {
+            match &directive.value {
+                Directive::Module { module_path } => ret.push((&**module_path, ModuleRole::Main)),
+                Directive::Require { specs, .. } => {
+                    ret.extend(specs.iter().map(|i| (&*i.value.0, ModuleRole::Required)))
+                }
+                Directive::Exclude { specs, .. } => {
+                    ret.extend(specs.iter().map(|i| (&*i.value.0, ModuleRole::Excluded)))
+                }
+                Directive::Replace { specs, .. } => {
+                    for spec in specs {
+                        ret.push((&*spec.value.module_path, ModuleRole::ReplacedFrom));
+                        if let Replacement::Module((path, _)) = &spec.value.replacement {
+                            ret.push((&**path, ModuleRole::ReplacedTo));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        ret.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{collections::BTreeMap, string::String};
+
+    use crate::{needs_quoting, parse_gomod, DirectiveKind, GoModSummary, QuoteStyle, Replacement};
+
+    use super::{EffectiveVersion, ModuleRole, NodeRef};
+
+    #[test]
+    fn test_module_paths() {
+        let gomod = parse_gomod(
+            r#"module example.com/m
+
+go 1.21
+
+require (
+    example.com/a v1.0.0
+    example.com/b v1.0.0
+)
+exclude example.com/a v0.9.0
+replace (
+    example.com/a => example.com/fork/a v1.0.1
+    example.com/b => ../b
+)
+retract v1.0.0
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            gomod.module_paths().collect::<Vec<_>>(),
+            vec![
+                ("example.com/m", ModuleRole::Main),
+                ("example.com/a", ModuleRole::Required),
+                ("example.com/b", ModuleRole::Required),
+                ("example.com/a", ModuleRole::Excluded),
+                ("example.com/a", ModuleRole::ReplacedFrom),
+                ("example.com/fork/a", ModuleRole::ReplacedTo),
+                ("example.com/b", ModuleRole::ReplacedFrom),
+            ]
+        );
+        assert_eq!(gomod.requires().count(), 2);
+        assert_eq!(gomod.excludes().count(), 1);
+        assert_eq!(gomod.replaces().count(), 2);
+        assert_eq!(
+            gomod
+                .local_replaces()
+                .map(|i| &*i.value.module_path)
+                .collect::<Vec<_>>(),
+            vec!["example.com/b"]
+        );
+    }
+
+    #[test]
+    fn test_go_version_parts() {
+        let parts = |s: &str| {
+            parse_gomod(&alloc::format!("module example.com/m\n\ngo {s}\n"))
+                .unwrap()
+                .go_version_parts()
+        };
+        assert_eq!(parts("1.21"), Some((1, 21, 0)));
+        assert_eq!(parts("1.21.3"), Some((1, 21, 3)));
+        assert_eq!(parts("1.21rc1"), Some((1, 21, 0)));
+        for v in ["1", "1.", "1.21.3.4", "v1.21", "1.x", "1.-2", "1.99999"] {
+            assert_eq!(parts(v), None, "{v}");
+        }
+        let gomod = parse_gomod("module example.com/m\n").unwrap();
+        assert_eq!(gomod.go_version_parts(), None);
+        let gomod =
+            parse_gomod("module example.com/m\n\ngo 1.22rc1\n\ntoolchain go1.22rc1\n").unwrap();
+        assert_eq!(gomod.go_version().unwrap().prerelease, Some("rc1"));
+        assert_eq!(gomod.toolchain_version(), gomod.go_version());
+    }
+
+    #[test]
+    fn test_singletons() {
+        let gomod =
+            parse_gomod("module example.com/m\ngo 1.21\nrequire example.com/a v1.0.0\ngo 1.22\n")
+                .unwrap();
+        let view = gomod.singletons();
+        assert_eq!(view.module_path, Some("example.com/m"));
+        assert_eq!(view.go_version, Some("1.21"));
+        assert_eq!(view.go.unwrap().range.0.line, 2);
+        assert_eq!(view.toolchain, None);
+        assert_eq!(
+            view.duplicates
+                .iter()
+                .map(|(kind, ranges)| (*kind, ranges.iter().map(|i| i.0.line).collect()))
+                .collect::<Vec<(_, Vec<_>)>>(),
+            vec![(DirectiveKind::Go, vec![2, 4])]
+        );
+        let gomod = parse_gomod("module example.com/m\ntoolchain go1.22.1\n").unwrap();
+        let view = gomod.singletons();
+        assert_eq!(view.toolchain_name, Some("go1.22.1"));
+        assert!(view.duplicates.is_empty());
+    }
+
+    #[test]
+    fn test_effective_version() {
+        let gomod = parse_gomod(
+            r#"module example.com/m
+
+require (
+    example.com/a v1.0.0
+    example.com/b v1.2.0
+    example.com/c v1.0.0
+    example.com/d v1.0.0
+)
+require example.com/a v1.1.0
+
+replace example.com/a v1.0.0 => example.com/fork/a v1.0.1
+replace example.com/b => ../b
+replace example.com/b v1.2.0 => example.com/fork/b v1.2.1
+replace example.com/c v0.9.0 => ../c
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            gomod.effective_version("example.com/a"),
+            Some(EffectiveVersion {
+                path: "example.com/a",
+                version: Some("v1.1.0"),
+                required: "v1.1.0",
+                replaced: false,
+                local: false,
+            })
+        );
+        assert_eq!(
+            gomod.effective_version("example.com/b"),
+            Some(EffectiveVersion {
+                path: "example.com/fork/b",
+                version: Some("v1.2.1"),
+                required: "v1.2.0",
+                replaced: true,
+                local: false,
+            })
+        );
+        assert!(!gomod.effective_version("example.com/c").unwrap().replaced);
+        assert_eq!(gomod.effective_version("example.com/x"), None);
+        let gomod = parse_gomod(
+            "module example.com/m\nrequire example.com/b v1.2.0\nreplace example.com/b => ../b\n",
+        )
+        .unwrap();
+        let v = gomod.effective_version("example.com/b").unwrap();
+        assert_eq!((v.path, v.version, v.local), ("../b", None, true));
+    }
+
+    #[test]
+    fn test_replacement_map() {
+        let gomod = parse_gomod(
+            r#"module example.com/m
+
+replace (
+    example.com/a => ../a
+    example.com/a v1.0.0 => example.com/fork/a v1.0.1
+    example.com/a => ../a2
+)
+"#,
+        )
+        .unwrap();
+        let map = gomod.replacement_map();
+        assert_eq!(map.len(), 2);
+        assert!(matches!(
+            map[&("example.com/a", None)],
+            Replacement::FilePath(p) if &**p == "../a2"
+        ));
+        assert!(matches!(
+            map[&("example.com/a", Some("v1.0.0"))],
+            Replacement::Module((p, v)) if &**p == "example.com/fork/a" && &**v == "v1.0.1"
+        ));
+    }
+
+    #[test]
+    fn test_replacement_map_duplicates() {
+        let gomod = parse_gomod(
+            r#"module example.com/m
+
+require (
+    example.com/a v1.0.0
+    example.com/b v1.0.0
+)
+
+replace example.com/a => ../a
+replace example.com/a => ../a2
+replace example.com/b v1.0.0 => example.com/fork/b v1.0.1
+replace example.com/b v1.0.0 => example.com/fork2/b v1.0.2
+"#,
+        )
+        .unwrap();
+        let map = gomod.replacement_map();
+        for (path, version) in [("example.com/a", None), ("example.com/b", Some("v1.0.0"))] {
+            let target = match map[&(path, version)] {
+                Replacement::FilePath(p) => (&**p, None),
+                Replacement::Module((p, v)) => (&**p, Some(&**v)),
+            };
+            let effective = gomod.effective_version(path).unwrap();
+            assert_eq!((effective.path, effective.version), target, "{path}");
+        }
+        assert_eq!(
+            gomod.effective_version("example.com/a").unwrap().path,
+            "../a2"
+        );
+        assert_eq!(
+            gomod.effective_version("example.com/b").unwrap().version,
+            Some("v1.0.2")
+        );
+    }
+
+    #[test]
+    fn test_direct_requires() {
+        let gomod = parse_gomod(
+            r#"module example.com/m
+
+require example.com/a v1.0.0
+require example.com/b v1.0.0 // indirect
+
+require (
+	example.com/c v1.0.0
+	example.com/d v1.0.0 // indirect
+	// about e
+	example.com/e v1.0.0 // indirect; needed by example.com/c
+	example.com/f v1.0.0 // not indirect
+	// indirect
+	example.com/g v1.0.0
+	example.com/h v1.0.0 // indirect;
+	example.com/i v1.0.0 //indirect;  ok
+)
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            gomod
+                .direct_requires()
+                .map(|i| &*i.value.0)
+                .collect::<Vec<_>>(),
+            vec![
+                "example.com/a",
+                "example.com/c",
+                "example.com/f",
+                "example.com/g",
+                "example.com/h"
+            ]
+        );
+        assert_eq!(
+            gomod
+                .indirect_requires()
+                .map(|i| &*i.value.0)
+                .collect::<Vec<_>>(),
+            vec![
+                "example.com/b",
+                "example.com/d",
+                "example.com/e",
+                "example.com/i"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let gomod = parse_gomod(
+            "module example.com/m\ngo \"1.21\"\nrequire example.com/a `v1.0.0`\nreplace example.com/a => \"../a b\"\nretract [v1.0.0, v1.0.1]\ngodebug panicnil=1\n",
+        )
+        .unwrap();
+        let tokens: Vec<_> = gomod
+            .identifiers()
+            .map(|i| (&**i, i.quote_style(), needs_quoting(i)))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                ("example.com/m", QuoteStyle::Bare, false),
+                ("1.21", QuoteStyle::Interpreted, false),
+                ("example.com/a", QuoteStyle::Bare, false),
+                ("v1.0.0", QuoteStyle::Raw, false),
+                ("example.com/a", QuoteStyle::Bare, false),
+                ("../a b", QuoteStyle::Interpreted, true),
+                ("v1.0.0", QuoteStyle::Bare, false),
+                ("v1.0.1", QuoteStyle::Bare, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_outdated() {
+        let gomod = parse_gomod(
+            r#"module example.com/m
+
+require (
+    example.com/a v1.2.0
+    example.com/b v1.10.0
+    example.com/c v2.0.0-rc.1+incompatible
+    example.com/d v1.0.0
+    example.com/e master
+)
+"#,
+        )
+        .unwrap();
+        let latest = BTreeMap::from([
+            ("example.com/a", String::from("v1.10.0")),
+            ("example.com/b", "v1.9.0".into()),
+            ("example.com/c", "v2.0.0+incompatible".into()),
+            ("example.com/e", "v1.0.0".into()),
+        ]);
+        let outdated = gomod.outdated(|path| latest.get(path).map(String::as_str));
+        assert_eq!(
+            outdated
+                .iter()
+                .map(|i| (i.path, i.current, i.latest))
+                .collect::<Vec<_>>(),
+            vec![
+                ("example.com/a", "v1.2.0", "v1.10.0"),
+                (
+                    "example.com/c",
+                    "v2.0.0-rc.1+incompatible",
+                    "v2.0.0+incompatible"
+                ),
+            ]
+        );
+        assert_eq!(outdated[0].range.0.line, 4);
+    }
+
+    #[test]
+    fn test_summary() {
+        let gomod = parse_gomod(
+            r#"module example.com/m
+
+go 1.21
+
+require example.com/a v1.0.0
+require (
+    example.com/b v1.0.0
+    example.com/c v1.0.0
+)
+require ()
+
+replace example.com/a => ../a
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            gomod.summary(),
+            GoModSummary {
+                requires: 3,
+                replaces: 1,
+                has_toolchain: false,
+                go_version: Some("1.21"),
+                block_directives: 2,
+                single_line_directives: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn test_godebug_settings() {
+        let gomod = parse_gomod(
+            r#"module example.com/m
+
+godebug panicnil=1
+godebug (
+    http2client=0
+    panicnil=0
+)
+godebug http2client=1
+godebug x509sha1=1
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            gomod.godebug_settings(),
+            vec![
+                ("panicnil", "1"),
+                ("http2client", "0"),
+                ("panicnil", "0"),
+                ("http2client", "1"),
+                ("x509sha1", "1"),
+            ]
+        );
+        let duplicates = gomod.duplicate_godebug_keys();
+        assert_eq!(
+            duplicates
+                .iter()
+                .map(|(key, ranges)| (*key, ranges.iter().map(|i| i.0.line).collect()))
+                .collect::<Vec<(_, Vec<_>)>>(),
+            vec![("panicnil", vec![3, 6]), ("http2client", vec![5, 8])]
+        );
+        assert_eq!(gomod.godebug_default(), None);
+        let gomod = parse_gomod(
+            "module example.com/m\n\ngodebug (\n\tdefault=go1.20\n\tpanicnil=1\n\tdefault=go1.21\n)\n",
+        )
+        .unwrap();
+        assert_eq!(gomod.godebug_default(), Some("go1.21"));
+    }
+
+    #[test]
+    fn test_excluded_versions() {
+        let gomod = parse_gomod(
+            "module m\n\nexclude (\n    foo v1.0.0\n    foo v1.1.0\n)\nexclude foo/v2 v2.0.0\nexclude foo v1.2.0\n",
+        )
+        .unwrap();
+        assert_eq!(gomod.excludes().count(), 4);
+        assert_eq!(
+            gomod.excluded_versions("foo"),
+            vec!["v1.0.0", "v1.1.0", "v1.2.0"]
+        );
+        assert_eq!(gomod.excluded_versions("foo/v2"), vec!["v2.0.0"]);
+        assert!(gomod.excluded_versions("bar").is_empty());
+    }
+
+    #[test]
+    fn test_node_at_offset() {
+        let text = "module example.com/m\n\n// about\nrequire (\n\texample.com/a v1.0.0\n\texample.com/b v1.0.0 // b\n)\n";
+        let gomod = parse_gomod(text).unwrap();
+        let at = |needle: &str| gomod.node_at_offset(text.find(needle).unwrap());
+        assert!(matches!(at("example.com/m"), Some(NodeRef::Directive(d)) if d.range.0.line == 1));
+        assert!(matches!(at("require"), Some(NodeRef::Directive(d)) if d.range.0.line == 4));
+        assert!(matches!(at("/a"), Some(NodeRef::Spec { index: 0, .. })));
+        assert!(matches!(at("// b"), Some(NodeRef::Spec { index: 1, .. })));
+        assert!(matches!(at(")"), Some(NodeRef::Directive(_))));
+        assert_eq!(at("// about"), None);
+        assert_eq!(gomod.node_at_offset(text.len()), None);
+    }
+}
@@ -0,0 +1,137 @@
+use alloc::vec::Vec;
+
+use nom::Slice;
+
+use crate::{directive_set::KEYWORDS, parser, GoMod, ParseError, ParseOptions, Span};
+
+fn is_directive_line(line: &str) -> bool {
+    let line = line.trim_start();
+    KEYWORDS.iter().any(|(k, _)| {
+        line.strip_prefix(k)
+            .is_some_and(|i| i.is_empty() || i.starts_with([' ', '\t', '\r', '\n', '(']))
+    })
+}
+
+/// Offset of the first line after the one holding `offset` that starts with a directive keyword
+fn next_directive_line(text: &str, offset: usize) -> Option<usize> {
+    let mut pos = offset + text[offset..].find('\n')? + 1;
+    for line in text[pos..].split_inclusive('\n') {
+        if is_directive_line(line) {
+            return Some(pos);
+        }
+        pos += line.len();
+    }
+    None
+}
+
+/// Parse as many directives as possible, for tooling that must keep working on broken files.
+///
+/// When a directive fails to parse, its error is recorded and parsing resumes at the first line
+/// starting with a directive keyword after the one holding the error. An error is only recorded
+/// once. The returned [`GoMod`] holds every directive parsed
+/// successfully, in source order.
+pub fn parse_gomod_recover(text: &str) -> (GoMod<'_>, Vec<ParseError>) {
+    let start = Span::new_extra(text, ParseOptions::default());
+    let (mut input, header) = parser::parse_header(start).unwrap_or((start, Vec::new()));
+    let mut directives = Vec::new();
+    let mut errors = Vec::new();
+    let mut trailing = Vec::new();
+    loop {
+        let e = match parser::parse_directive(input) {
+            Ok((rest, directive)) => {
+                directives.push(directive);
+                input = rest;
+                continue;
+            }
+            Err(e) => e,
+        };
+        if let Ok((rest, comments)) = parser::parse_multiline_comments(input) {
+            if rest.is_empty() && !directives.is_empty() {
+                input = rest;
+                trailing = comments;
+                break;
+            }
+        }
+        let error = ParseError::syntax(e, input);
+        let offset = error.located().map_or(0, |i| i.offset);
+        if !errors.contains(&error) {
+            errors.push(error);
+        }
+        match next_directive_line(text, offset.max(input.location_offset())) {
+            Some(next) => input = input.slice(next - input.location_offset()..),
+            None => break,
+        }
+    }
+    (
+        parser::gomod(start, input, header, directives, trailing),
+        errors,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_gomod, Directive, ParseError};
+
+    use super::parse_gomod_recover;
+
+    #[test]
+    fn test_recover() {
+        let text = r#"module example.com/m
+
+go 1.21 extra
+
+require (
+    example.com/a v1.0.0
+    example.com/b
+)
+
+toolchain go1.22.1
+/* block */
+replace example.com/a => ../a
+"#;
+        let (gomod, errors) = parse_gomod_recover(text);
+        assert_eq!(
+            gomod
+                .iter()
+                .map(|i| match i.value {
+                    Directive::Module { .. } => "module",
+                    Directive::Toolchain { .. } => "toolchain",
+                    Directive::Replace { .. } => "replace",
+                    _ => "other",
+                })
+                .collect::<Vec<_>>(),
+            vec!["module", "toolchain", "replace"]
+        );
+        assert_eq!(errors.len(), 3);
+        assert!(matches!(
+            errors[1],
+            ParseError::MissingVersion { line: 7, .. }
+        ));
+        assert!(matches!(
+            errors[2],
+            ParseError::BlockCommentUnsupported { line: 11, .. }
+        ));
+
+        let text =
+            "module m\n\ngo 1.21 extra\n\nrequire example.com/a v1.0.0\n\nreplace a => \"../a\n";
+        let (gomod, errors) = parse_gomod_recover(text);
+        assert_eq!(gomod.len(), 2);
+        assert!(matches!(
+            errors[..],
+            [
+                ParseError::UnexpectedTrailingToken { line: 3, .. },
+                ParseError::UnterminatedString {
+                    line: 7,
+                    offset: 68,
+                    ..
+                }
+            ]
+        ));
+
+        let valid = "module example.com/m\n\ngo 1.21\n// end\n";
+        let (gomod, errors) = parse_gomod_recover(valid);
+        assert!(errors.is_empty());
+        assert_eq!(gomod, parse_gomod(valid).unwrap());
+        assert_eq!(parse_gomod_recover("").1.len(), 1);
+    }
+}
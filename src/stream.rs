@@ -0,0 +1,84 @@
+use nom::Err;
+
+use crate::{parser, Context, Directive, ParseError, ParseOptions, Span};
+
+struct Directives<'a> {
+    input: Span<'a>,
+    parsed: bool,
+    done: bool,
+}
+
+impl<'a> Iterator for Directives<'a> {
+    type Item = Result<Context<'a, Directive<'a>>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let input = self.input;
+        match parser::parse_directive(input) {
+            Ok((rest, directive)) => {
+                self.input = rest;
+                self.parsed = true;
+                Some(Ok(directive))
+            }
+            Err(e) => {
+                self.done = true;
+                if let (Err::Error(_), Ok((rest, _))) =
+                    (&e, parser::parse_multiline_comments(input))
+                {
+                    if self.parsed && rest.is_empty() {
+                        return None;
+                    }
+                }
+                Some(Err(ParseError::syntax(e, input)))
+            }
+        }
+    }
+}
+
+/// Parse `text` lazily, one directive per call to `next`.
+///
+/// Only the current directive is kept in memory, and the header comments of the file are
+/// skipped, see [`crate::GoMod::header_comments`]. The iterator ends after the trailing comments
+/// of the file, or right after yielding the first error.
+pub fn directives(
+    text: &str,
+) -> impl Iterator<Item = Result<Context<'_, Directive<'_>>, ParseError>> {
+    let input = Span::new_extra(text, ParseOptions::default());
+    Directives {
+        input: parser::parse_header(input).map_or(input, |(rest, _)| rest),
+        parsed: false,
+        done: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_gomod, ParseError};
+
+    use super::directives;
+
+    #[test]
+    fn test_directives() {
+        let s = "module example.com/m\n\ngo 1.21\n\nrequire example.com/a v1.0.0\n// trailing\n";
+        let lazy = directives(s).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(lazy, parse_gomod(s).unwrap().directives);
+
+        let mut iter = directives("module example.com/m\nfoo\ngo 1.21\n");
+        assert!(iter.next().unwrap().is_ok());
+        assert_eq!(
+            iter.next().unwrap(),
+            Err(ParseError::Syntax {
+                line: 2,
+                offset: 21,
+                len: 3
+            })
+        );
+        assert!(iter.next().is_none());
+
+        let mut iter = directives("// only comments\n");
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+}
@@ -0,0 +1,446 @@
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt;
+
+use crate::{
+    module::{validate_import_path, validate_module_path, PathError},
+    version::GoVersion,
+    Directive, DirectiveKind, GoMod, Identifier, Range, Replacement,
+};
+
+/// A problem found by [`GoMod::validate`] in a file that otherwise parses fine.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Location of the offending directive or spec, `None` when the problem is about the whole file
+    pub range: Option<Range>,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// The file has no directive at all
+    EmptyFile,
+    /// There's no `module` directive, which is required for a module's go.mod
+    MissingModule,
+    /// A module path rejected by [`crate::module::validate_module_path`], or by
+    /// [`crate::module::validate_import_path`] for the `module` directive
+    InvalidModulePath(PathError),
+    /// A directive needs a newer Go than the `go` line declares, see [`DirectiveKind::min_go`]
+    GoVersionTooOld {
+        directive: DirectiveKind,
+        required: (u16, u16),
+    },
+    /// A toolchain name that isn't `default` nor `go` followed by a Go version, see
+    /// [`GoMod::validate_toolchain`]
+    InvalidToolchain(String),
+    /// The value of the `default` godebug key isn't a Go language version like `go1.21`
+    InvalidGodebugDefault(String),
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyFile => write!(f, "no directives found"),
+            Self::MissingModule => write!(f, "missing module directive"),
+            Self::InvalidModulePath(e) => write!(f, "invalid module path: {e}"),
+            Self::GoVersionTooOld {
+                directive,
+                required: (major, minor),
+            } => write!(
+                f,
+                "{} directive requires go >= {major}.{minor}",
+                directive.keyword()
+            ),
+            Self::InvalidToolchain(name) => {
+                write!(f, "invalid toolchain {name:?}, expected goX.Y.Z")
+            }
+            Self::InvalidGodebugDefault(value) => {
+                write!(f, "invalid godebug default {value:?}, expected goX.Y")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.range {
+            Some((start, _)) => write!(f, "line {}: {}", start.line, self.kind),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+/// A directive out of the conventional order, see [`GoMod::ordering_issues`]
+#[derive(Debug, PartialEq, Eq)]
+pub struct OrderingIssue {
+    /// Location of the offending directive
+    pub range: Range,
+    pub kind: OrderingIssueKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderingIssueKind {
+    /// The `module` directive isn't the first one
+    ModuleNotFirst,
+    /// A `go` directive follows a `require`
+    GoAfterRequire,
+    /// A second `module`, `go` or `toolchain` directive, which Go rejects
+    Duplicate(DirectiveKind),
+}
+
+impl fmt::Display for OrderingIssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ModuleNotFirst => write!(f, "module directive is not the first directive"),
+            Self::GoAfterRequire => write!(f, "go directive after require"),
+            Self::Duplicate(kind) => write!(f, "repeated {} directive", kind.keyword()),
+        }
+    }
+}
+
+impl GoMod<'_> {
+    /// Check the rules Go enforces on a module's go.mod beyond its grammar.
+    ///
+    /// An empty result means the file is valid.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        if self.is_empty() {
+            return vec![Diagnostic {
+                range: None,
+                kind: DiagnosticKind::EmptyFile,
+            }];
+        }
+        let mut ret = vec![];
+        if !self
+            .iter()
+            .any(|i| matches!(i.value, Directive::Module { .. }))
+        {
+            ret.push(Diagnostic {
+                range: None,
+                kind: DiagnosticKind::MissingModule,
+            });
+        }
+        if let Some((major, minor, _)) = self.go_version_parts() {
+            for directive in self.iter() {
+                let kind = directive.value.kind();
+                match kind.min_go() {
+                    Some(required) if (major, minor) < required => ret.push(Diagnostic {
+                        range: Some(directive.range.clone()),
+                        kind: DiagnosticKind::GoVersionTooOld {
+                            directive: kind,
+                            required,
+                        },
+                    }),
+                    _ => {}
+                }
+            }
+        }
+        let mut check_with =
+            |validate: fn(&str) -> Result<(), PathError>, path: &Identifier, range: &Range| {
+                if let Err(e) = validate(path) {
+                    ret.push(Diagnostic {
+                        range: Some(range.clone()),
+                        kind: DiagnosticKind::InvalidModulePath(e),
+                    });
+                }
+            };
+        for directive in self.iter() {
+            match &directive.value {
+                Directive::Module { module_path } => {
+                    check_with(validate_import_path, module_path, &directive.range)
+                }
+                Directive::Require { specs, .. } | Directive::Exclude { specs, .. } => {
+                    for spec in specs {
+                        check_with(validate_module_path, &spec.value.0, &spec.range);
+                    }
+                }
+                Directive::Replace { specs, .. } => {
+                    for spec in specs {
+                        check_with(validate_module_path, &spec.value.module_path, &spec.range);
+                        if let Replacement::Module((path, _)) = &spec.value.replacement {
+                            check_with(validate_module_path, path, &spec.range);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        ret.extend(self.validate_toolchain());
+        for spec in self.godebugs() {
+            let (key, value) = spec.value;
+            let valid = value
+                .strip_prefix("go")
+                .and_then(GoVersion::parse)
+                .is_some_and(|v| v.patch.is_none() && v.prerelease.is_none());
+            if key == "default" && !valid {
+                ret.push(Diagnostic {
+                    range: Some(spec.range.clone()),
+                    kind: DiagnosticKind::InvalidGodebugDefault(value.into()),
+                });
+            }
+        }
+        ret
+    }
+
+    /// Check the names of the `toolchain` directives: `default`, or `go` followed by a Go
+    /// version, e.g. `go1.21.3` or `go1.22rc1`. A custom suffix like `-bigcorp` is allowed, and
+    /// so are `+auto` and `+path`, which Go accepts when the name comes from `GOTOOLCHAIN`.
+    pub fn validate_toolchain(&self) -> Vec<Diagnostic> {
+        self.iter()
+            .filter_map(|directive| match &directive.value {
+                Directive::Toolchain { name } => {
+                    let version = name
+                        .strip_suffix("+auto")
+                        .or_else(|| name.strip_suffix("+path"))
+                        .unwrap_or(name);
+                    let valid =
+                        &**name == "default" || GoVersion::parse_toolchain(version).is_some();
+                    (!valid).then(|| Diagnostic {
+                        range: Some(directive.range.clone()),
+                        kind: DiagnosticKind::InvalidToolchain((**name).into()),
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Report directives out of the conventional order, which parsing itself doesn't enforce:
+    /// `module` not first, `go` after a `require`, and repeated `module`, `go` or `toolchain`
+    /// directives.
+    pub fn ordering_issues(&self) -> Vec<OrderingIssue> {
+        let mut ret = Vec::new();
+        let mut seen = Vec::new();
+        let mut required = false;
+        for (i, directive) in self.iter().enumerate() {
+            let mut push = |kind| {
+                ret.push(OrderingIssue {
+                    range: directive.range.clone(),
+                    kind,
+                })
+            };
+            let kind = directive.value.kind();
+            match kind {
+                DirectiveKind::Module | DirectiveKind::Go | DirectiveKind::Toolchain
+                    if seen.contains(&kind) =>
+                {
+                    push(OrderingIssueKind::Duplicate(kind))
+                }
+                DirectiveKind::Module if i > 0 => push(OrderingIssueKind::ModuleNotFirst),
+                DirectiveKind::Go if required => push(OrderingIssueKind::GoAfterRequire),
+                DirectiveKind::Require => required = true,
+                _ => {}
+            }
+            seen.push(kind);
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{module::PathError, parse_gomod, DirectiveKind, GoMod};
+
+    use super::{Diagnostic, DiagnosticKind, OrderingIssueKind};
+
+    #[test]
+    fn test_missing_module() {
+        let gomod = parse_gomod("// no module here\ngo 1.21\n").unwrap();
+        assert_eq!(
+            gomod.validate(),
+            vec![Diagnostic {
+                range: None,
+                kind: DiagnosticKind::MissingModule
+            }]
+        );
+        assert_eq!(gomod.validate()[0].to_string(), "missing module directive");
+        assert_eq!(
+            GoMod::default().validate(),
+            vec![Diagnostic {
+                range: None,
+                kind: DiagnosticKind::EmptyFile
+            }]
+        );
+        let gomod = parse_gomod("module example.com/m\n\ngo 1.21\n").unwrap();
+        assert!(gomod.validate().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_module_path() {
+        let gomod = parse_gomod(
+            "module example.com/m.\n\nrequire example.com/aux v1.0.0\nreplace example.com/a => ../a\n",
+        )
+        .unwrap();
+        let diagnostics = gomod.validate();
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|i| (i.range.as_ref().unwrap().0.line, &i.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    1,
+                    &DiagnosticKind::InvalidModulePath(PathError::TrailingDot)
+                ),
+                (
+                    3,
+                    &DiagnosticKind::InvalidModulePath(PathError::WindowsReservedName)
+                ),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "line 1: invalid module path: trailing dot in path element"
+        );
+    }
+
+    #[test]
+    fn test_main_module_import_path() {
+        // the main module only needs to be a valid import path, like go's CheckImportPath
+        for text in [
+            "module hello
+",
+            "module m
+",
+            "module Example.com/m
+",
+        ] {
+            assert_eq!(parse_gomod(text).unwrap().validate(), vec![], "{text}");
+        }
+        let gomod = parse_gomod(
+            "module hello
+
+require hello/dep v1.0.0
+",
+        )
+        .unwrap();
+        assert_eq!(
+            gomod
+                .validate()
+                .into_iter()
+                .map(|i| i.kind)
+                .collect::<Vec<_>>(),
+            vec![DiagnosticKind::InvalidModulePath(
+                PathError::MissingDotInFirstElement
+            )]
+        );
+    }
+
+    #[test]
+    fn test_go_version_too_old() {
+        let text = "module example.com/m\n\ngo 1.22\n\ntoolchain go1.22.1\n\ngodebug panicnil=1\n";
+        let gomod = parse_gomod(text).unwrap();
+        assert_eq!(gomod.implied_min_go(), Some((1, 23)));
+        let diagnostics = gomod.validate();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "line 7: godebug directive requires go >= 1.23"
+        );
+        let gomod = parse_gomod("module example.com/m\n\ngo 1.23\n\ngodebug panicnil=1\n").unwrap();
+        assert!(gomod.validate().is_empty());
+        let gomod = parse_gomod("module example.com/m\n\ntoolchain go1.21.0\n").unwrap();
+        assert_eq!(gomod.implied_min_go(), Some((1, 21)));
+        assert!(gomod.validate().is_empty());
+        let text =
+            "module example.com/m\n\ngo 1.24\n\ngodebug panicnil=1\n\nignore ./node_modules\n";
+        let gomod = parse_gomod(text).unwrap();
+        assert_eq!(gomod.implied_min_go(), Some((1, 25)));
+        assert_eq!(
+            gomod.validate()[0].to_string(),
+            "line 7: ignore directive requires go >= 1.25"
+        );
+        assert_eq!(
+            parse_gomod("module example.com/m\n")
+                .unwrap()
+                .implied_min_go(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalid_godebug_default() {
+        let gomod = parse_gomod(
+            "module example.com/m\n\ngodebug (\n\tdefault=go1.21\n\tdefault=1.21\n\tdefault=go1.21.3\n)\n",
+        )
+        .unwrap();
+        let diagnostics = gomod.validate();
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|i| (i.range.as_ref().unwrap().0.line, &i.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (5, &DiagnosticKind::InvalidGodebugDefault("1.21".into())),
+                (6, &DiagnosticKind::InvalidGodebugDefault("go1.21.3".into())),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "line 5: invalid godebug default \"1.21\", expected goX.Y"
+        );
+    }
+
+    #[test]
+    fn test_invalid_toolchain() {
+        for name in [
+            "go1.21.3",
+            "go1.22rc1",
+            "go1.21.3+auto",
+            "go1.21.0-bigcorp",
+            "default",
+        ] {
+            let text = format!("module example.com/m\n\ntoolchain {name}\n");
+            let gomod = parse_gomod(&text).unwrap();
+            assert!(gomod.validate_toolchain().is_empty(), "{name}");
+        }
+        let gomod =
+            parse_gomod("module example.com/m\n\ntoolchain 1.21.3\ntoolchain go1.x\n").unwrap();
+        let diagnostics = gomod.validate();
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|i| (i.range.as_ref().unwrap().0.line, &i.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (3, &DiagnosticKind::InvalidToolchain("1.21.3".into())),
+                (4, &DiagnosticKind::InvalidToolchain("go1.x".into())),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "line 3: invalid toolchain \"1.21.3\", expected goX.Y.Z"
+        );
+    }
+
+    #[test]
+    fn test_ordering_issues() {
+        let gomod = parse_gomod(
+            "go 1.21\nmodule example.com/m\nrequire example.com/a v1.0.0\ngo 1.22\nmodule example.com/n\n",
+        )
+        .unwrap();
+        assert_eq!(
+            gomod
+                .ordering_issues()
+                .iter()
+                .map(|i| (i.range.0.line, i.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (2, OrderingIssueKind::ModuleNotFirst),
+                (4, OrderingIssueKind::Duplicate(DirectiveKind::Go)),
+                (5, OrderingIssueKind::Duplicate(DirectiveKind::Module)),
+            ]
+        );
+        let gomod =
+            parse_gomod("module example.com/m\nrequire example.com/a v1.0.0\ngo 1.21\n").unwrap();
+        assert_eq!(
+            gomod.ordering_issues()[0].kind,
+            OrderingIssueKind::GoAfterRequire
+        );
+        assert_eq!(
+            gomod.ordering_issues()[0].kind.to_string(),
+            "go directive after require"
+        );
+        let gomod =
+            parse_gomod("module example.com/m\ngo 1.21\nrequire example.com/a v1.0.0\n").unwrap();
+        assert!(gomod.ordering_issues().is_empty());
+    }
+}
@@ -0,0 +1,450 @@
+//! Helpers for the semantic versions used in go.mod, following
+//! [golang.org/x/mod/semver](https://pkg.go.dev/golang.org/x/mod/semver).
+//!
+//! Like Go, `v1` and `v1.2` are accepted as shorthands for `v1.0.0` and `v1.2.0`,
+//! and build metadata (e.g. `+incompatible`) is ignored when comparing. `+incompatible` still
+//! matters when matching a version against its module path, see [`matches_path_major`].
+
+use core::{cmp::Ordering, fmt};
+
+use crate::{module::major_version_suffix, Identifier};
+
+/// A parsed `vMAJOR.MINOR.PATCH[-PRERELEASE][+BUILD]` version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemVer<'a> {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// Pre-release part without the leading `-`
+    pub prerelease: Option<&'a str>,
+    /// Build metadata without the leading `+`
+    pub build: Option<&'a str>,
+    /// Whether the build metadata is `incompatible`, marking a major version 2 or above of a
+    /// module without a go.mod or `/vN` path suffix
+    pub incompatible: bool,
+}
+
+fn parse_number(s: &str) -> Option<u64> {
+    if s.is_empty() || !s.bytes().all(|c| c.is_ascii_digit()) || (s.len() > 1 && s.starts_with('0'))
+    {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn valid_idents(s: &str, numeric_leading_zero: bool) -> bool {
+    s.split('.').all(|i| {
+        !i.is_empty()
+            && i.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-')
+            && (numeric_leading_zero
+                || !(i.len() > 1 && i.starts_with('0') && i.bytes().all(|c| c.is_ascii_digit())))
+    })
+}
+
+impl<'a> SemVer<'a> {
+    /// Return `None` if `v` isn't a valid semantic version
+    pub fn parse(v: &'a str) -> Option<Self> {
+        let v = v.strip_prefix('v')?;
+        let (v, build) = match v.split_once('+') {
+            Some((v, build)) => (v, Some(build)),
+            None => (v, None),
+        };
+        let (v, prerelease) = match v.split_once('-') {
+            Some((v, pre)) => (v, Some(pre)),
+            None => (v, None),
+        };
+        let mut parts = v.split('.');
+        let major = parse_number(parts.next()?)?;
+        let (minor, patch) = match (parts.next(), parts.next(), parts.next()) {
+            (None, _, _) => (0, 0),
+            (Some(minor), None, _) => (parse_number(minor)?, 0),
+            (Some(minor), Some(patch), None) => (parse_number(minor)?, parse_number(patch)?),
+            _ => return None,
+        };
+        // shorthands can't carry a pre-release or build suffix
+        if v.split('.').count() < 3 && (prerelease.is_some() || build.is_some()) {
+            return None;
+        }
+        if prerelease.is_some_and(|i| !valid_idents(i, false))
+            || build.is_some_and(|i| !valid_idents(i, true))
+        {
+            return None;
+        }
+        Some(Self {
+            major,
+            minor,
+            patch,
+            prerelease,
+            build,
+            incompatible: build == Some("incompatible"),
+        })
+    }
+
+    /// Compare by semver precedence, ignoring build metadata
+    pub fn precedence(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.prerelease, other.prerelease) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => cmp_prerelease(a, b),
+            })
+    }
+}
+
+/// Error of the `TryFrom` conversions into [`SemVer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InvalidSemVer;
+
+impl fmt::Display for InvalidSemVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid semantic version")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidSemVer {}
+
+impl<'a> TryFrom<&'a str> for SemVer<'a> {
+    type Error = InvalidSemVer;
+
+    fn try_from(v: &'a str) -> Result<Self, InvalidSemVer> {
+        Self::parse(v).ok_or(InvalidSemVer)
+    }
+}
+
+/// Parse the resolved value of a token, e.g. the version of a require spec, whatever its quoting
+impl<'a> TryFrom<&'a Identifier<'_>> for SemVer<'a> {
+    type Error = InvalidSemVer;
+
+    fn try_from(v: &'a Identifier<'_>) -> Result<Self, InvalidSemVer> {
+        Self::try_from(&**v)
+    }
+}
+
+fn cmp_prerelease(a: &str, b: &str) -> Ordering {
+    let mut a = a.split('.');
+    let mut b = b.split('.');
+    loop {
+        let (x, y) = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => (x, y),
+        };
+        let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => x.cmp(y),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+}
+
+/// Compare two versions by semver precedence, `None` if either one is invalid
+pub fn version_cmp(a: &str, b: &str) -> Option<Ordering> {
+    Some(SemVer::parse(a)?.precedence(&SemVer::parse(b)?))
+}
+
+/// Whether `version` is valid for the module at `path`, following Go's `module.CheckPathMajor`:
+///
+/// - `example.com/m` accepts `v0` and `v1` versions, and `v2` or above only with `+incompatible`
+/// - `example.com/m/v2` accepts `v2` versions without `+incompatible`
+/// - `gopkg.in/yaml.v3` accepts `v3` versions, `.v1` pseudo-versions of `v0.0.0` as well
+///
+/// `+incompatible` on a `v0` or `v1` version is always invalid.
+pub fn matches_path_major(version: &str, path: &str) -> bool {
+    let Some(v) = SemVer::parse(version) else {
+        return false;
+    };
+    match major_version_suffix(path) {
+        _ if v.incompatible && v.major < 2 => false,
+        Some(1) if path.starts_with("gopkg.in/") => v.major == 1 || version.starts_with("v0.0.0-"),
+        Some(n) => v.major == n && !v.incompatible,
+        None => v.major < 2 || v.incompatible,
+    }
+}
+
+/// How a dependency version changed, see [`version_bump_kind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BumpKind {
+    Major,
+    Minor,
+    Patch,
+    /// Only the pre-release part changed, e.g. between two pseudo-versions of the same base
+    PreRelease,
+    /// The new version is lower than the old one
+    Downgrade,
+}
+
+/// Classify the change from `old` to `new`.
+///
+/// Return `None` if either version is invalid, or if both have the same precedence
+/// (e.g. `v2.0.0+incompatible` and `v2.0.0`).
+///
+/// Pseudo-versions are pre-releases of the version following their base, so moving from
+/// `v1.2.3` to `v1.2.4-0.20240101000000-abcdefabcdef` is a `Patch`, and moving between two
+/// pseudo-versions of the same base is a `PreRelease`.
+pub fn version_bump_kind(old: &str, new: &str) -> Option<BumpKind> {
+    let old = SemVer::parse(old)?;
+    let new = SemVer::parse(new)?;
+    match new.precedence(&old) {
+        Ordering::Equal => None,
+        Ordering::Less => Some(BumpKind::Downgrade),
+        Ordering::Greater if new.major != old.major => Some(BumpKind::Major),
+        Ordering::Greater if new.minor != old.minor => Some(BumpKind::Minor),
+        Ordering::Greater if new.patch != old.patch => Some(BumpKind::Patch),
+        Ordering::Greater => Some(BumpKind::PreRelease),
+    }
+}
+
+/// A Go release as written in the `go` and `toolchain` directives, e.g. `1.21.3`, `1.21` or
+/// `1.21rc1`. These aren't semantic versions, see [go.dev/doc/toolchain](https://go.dev/doc/toolchain#version).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GoVersion<'a> {
+    pub major: u16,
+    pub minor: u16,
+    /// `None` for language versions like `1.21` and for pre-releases
+    pub patch: Option<u16>,
+    /// `alpha`, `beta` or `rc` followed by a number, e.g. `rc1`
+    pub prerelease: Option<&'a str>,
+}
+
+fn parse_go_number(s: &str) -> Option<u16> {
+    parse_number(s)?.try_into().ok()
+}
+
+impl<'a> GoVersion<'a> {
+    /// Parse the version of a `go` directive, `None` if it's invalid
+    pub fn parse(v: &'a str) -> Option<Self> {
+        let (core, prerelease) = match v.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(i) => (&v[..i], Some(&v[i..])),
+            None => (v, None),
+        };
+        if let Some(pre) = prerelease {
+            let n = ["alpha", "beta", "rc"]
+                .iter()
+                .find_map(|i| pre.strip_prefix(i))?;
+            parse_number(n)?;
+        }
+        let mut parts = core.split('.');
+        let major = parse_go_number(parts.next()?)?;
+        let minor = parse_go_number(parts.next()?)?;
+        let patch = match parts.next() {
+            Some(patch) => Some(parse_go_number(patch)?),
+            None => None,
+        };
+        if parts.next().is_some() || (patch.is_some() && prerelease.is_some()) {
+            return None;
+        }
+        Some(Self {
+            major,
+            minor,
+            patch,
+            prerelease,
+        })
+    }
+
+    /// Parse the name of a `toolchain` directive, e.g. `go1.21rc1`, ignoring a custom suffix
+    /// like `-bigcorp`
+    pub fn parse_toolchain(name: &'a str) -> Option<Self> {
+        let v = name.strip_prefix("go")?;
+        Self::parse(v.split_once('-').map_or(v, |(v, _)| v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cmp::Ordering;
+
+    use crate::{parse_gomod, Identifier};
+
+    use super::{
+        matches_path_major, version_bump_kind, version_cmp, BumpKind, GoVersion, InvalidSemVer,
+        SemVer,
+    };
+
+    #[test]
+    fn test_semver() {
+        assert_eq!(
+            SemVer::parse("v1.2.3-rc.1+meta"),
+            Some(SemVer {
+                major: 1,
+                minor: 2,
+                patch: 3,
+                prerelease: Some("rc.1"),
+                build: Some("meta"),
+                incompatible: false
+            })
+        );
+        assert_eq!(
+            SemVer::parse("v1.2"),
+            Some(SemVer {
+                major: 1,
+                minor: 2,
+                patch: 0,
+                prerelease: None,
+                build: None,
+                incompatible: false
+            })
+        );
+        for v in [
+            "1.2.3",
+            "v1.2.3.4",
+            "v01.2.3",
+            "v1.2-pre",
+            "v1.2.3-01",
+            "v1.2.3-",
+            "v1.2.3+",
+            "v",
+        ] {
+            assert_eq!(SemVer::parse(v), None, "{v}");
+        }
+        assert_eq!(version_cmp("v1.0.0-alpha", "v1.0.0"), Some(Ordering::Less));
+        assert_eq!(
+            version_cmp("v1.0.0-alpha.beta", "v1.0.0-alpha.1"),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            version_cmp("v1.0.0-rc.11", "v1.0.0-rc.2"),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(version_cmp("v1.0.0", "v1"), Some(Ordering::Equal));
+        assert_eq!(version_cmp("v1.0.0", "latest"), None);
+    }
+
+    #[test]
+    fn test_try_from_identifier() {
+        let gomod = parse_gomod(
+            "require (\n\texample.com/a `v1.2.3`\n\texample.com/b \"v0.0.0-20230101000000-abcdef123456\"\n\texample.com/c v2.0.0+incompatible\n\texample.com/d master\n)\n",
+        )
+        .unwrap();
+        let versions: Vec<_> = gomod
+            .requires()
+            .map(|i| SemVer::try_from(&i.value.1))
+            .collect();
+        assert_eq!(versions[0].unwrap().patch, 3);
+        assert_eq!(
+            versions[1].unwrap().prerelease,
+            Some("20230101000000-abcdef123456")
+        );
+        assert!(versions[2].unwrap().incompatible);
+        assert_eq!(versions[3], Err(InvalidSemVer));
+        let v = Identifier::Interpreted("v1.0.0-rc.1".into());
+        assert_eq!(SemVer::try_from(&v).unwrap().prerelease, Some("rc.1"));
+        assert_eq!(SemVer::try_from("1.0.0"), Err(InvalidSemVer));
+    }
+
+    #[test]
+    fn test_incompatible() {
+        assert!(SemVer::parse("v2.0.0+incompatible").unwrap().incompatible);
+        assert!(!SemVer::parse("v2.0.0").unwrap().incompatible);
+        assert_eq!(
+            version_cmp("v2.0.0+incompatible", "v2.0.0"),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            version_cmp("v2.0.1+incompatible", "v2.0.0"),
+            Some(Ordering::Greater)
+        );
+        for (version, path, ok) in [
+            ("v2.0.0+incompatible", "example.com/m", true),
+            ("v2.0.0", "example.com/m", false),
+            ("v1.2.0", "example.com/m", true),
+            ("v1.2.0+incompatible", "example.com/m", false),
+            ("v2.0.0", "example.com/m/v2", true),
+            ("v2.0.0+incompatible", "example.com/m/v2", false),
+            ("v3.0.0", "example.com/m/v2", false),
+            ("v3.0.1", "gopkg.in/yaml.v3", true),
+            (
+                "v0.0.0-20240101000000-abcdefabcdef",
+                "gopkg.in/check.v1",
+                true,
+            ),
+            ("v0.1.0", "gopkg.in/check.v1", false),
+            ("master", "example.com/m", false),
+        ] {
+            assert_eq!(matches_path_major(version, path), ok, "{version} {path}");
+        }
+    }
+
+    #[test]
+    fn test_bump_kind() {
+        assert_eq!(version_bump_kind("v1.2.3", "v2.0.0"), Some(BumpKind::Major));
+        assert_eq!(version_bump_kind("v1.2.3", "v1.3.0"), Some(BumpKind::Minor));
+        assert_eq!(version_bump_kind("v1.2.3", "v1.2.4"), Some(BumpKind::Patch));
+        assert_eq!(
+            version_bump_kind("v1.2.3-rc.1", "v1.2.3-rc.2"),
+            Some(BumpKind::PreRelease)
+        );
+        assert_eq!(
+            version_bump_kind("v1.2.3", "v1.2.3-rc.2"),
+            Some(BumpKind::Downgrade)
+        );
+        assert_eq!(
+            version_bump_kind("v1.3.0", "v1.2.9"),
+            Some(BumpKind::Downgrade)
+        );
+        assert_eq!(
+            version_bump_kind("v1.2.3", "v1.2.4-0.20240101000000-abcdefabcdef"),
+            Some(BumpKind::Patch)
+        );
+        assert_eq!(
+            version_bump_kind(
+                "v0.0.0-20230101000000-abcdefabcdef",
+                "v0.0.0-20240101000000-123456789abc"
+            ),
+            Some(BumpKind::PreRelease)
+        );
+        assert_eq!(
+            version_bump_kind("v1.5.0", "v2.0.0+incompatible"),
+            Some(BumpKind::Major)
+        );
+        assert_eq!(version_bump_kind("v2.0.0+incompatible", "v2.0.0"), None);
+        assert_eq!(version_bump_kind("v1.0.0", "master"), None);
+    }
+
+    #[test]
+    fn test_go_version() {
+        let v = |major, minor, patch, prerelease| GoVersion {
+            major,
+            minor,
+            patch,
+            prerelease,
+        };
+        assert_eq!(GoVersion::parse("1.21"), Some(v(1, 21, None, None)));
+        assert_eq!(GoVersion::parse("1.21.3"), Some(v(1, 21, Some(3), None)));
+        assert_eq!(
+            GoVersion::parse("1.21rc1"),
+            Some(v(1, 21, None, Some("rc1")))
+        );
+        assert_eq!(
+            GoVersion::parse("1.22beta1"),
+            Some(v(1, 22, None, Some("beta1")))
+        );
+        for s in [
+            "1",
+            "1.21rc",
+            "1.21.1rc1",
+            "1.21xx1",
+            "v1.21",
+            "1.021",
+            "1.21.3.4",
+        ] {
+            assert_eq!(GoVersion::parse(s), None, "{s}");
+        }
+        assert_eq!(
+            GoVersion::parse_toolchain("go1.21rc1"),
+            Some(v(1, 21, None, Some("rc1")))
+        );
+        assert_eq!(
+            GoVersion::parse_toolchain("go1.21.1-bigcorp"),
+            Some(v(1, 21, Some(1), None))
+        );
+        assert_eq!(GoVersion::parse_toolchain("1.21.1"), None);
+    }
+}